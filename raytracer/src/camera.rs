@@ -2,14 +2,33 @@ use rayon::prelude::{IndexedParallelIterator, IntoParallelRefMutIterator, Parall
 
 use crate::{
     canvas::Canvas,
+    color::Color,
+    integrator::Integrator,
     point::Point,
     ray::Ray,
+    rng::Rng,
+    sampling::{Adaptive, Sampler},
+    tonemap::ToneMapping,
     transform::{transformable, InversedTransform, Transform},
     world::World,
 };
 
+/// Pinhole camera, looking down -z until it is given a transform.
+///
+/// ```
+/// use raytracer::{Camera, Point, Transform, Transformable, Vector};
+///
+/// let camera = Camera::new(160, 120, std::f64::consts::FRAC_PI_3).with_transform(
+///     Transform::view_transform(
+///         Point::new(0.0, 1.5, -5.0),
+///         Point::new(0.0, 1.0, 0.0),
+///         Vector::new(0.0, 1.0, 0.0),
+///     ),
+/// );
+/// assert_eq!(camera.hsize(), 160);
+/// ```
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub(crate) struct Camera {
+pub struct Camera {
     hsize: usize,
     vsize: usize,
     field_of_view: f64,
@@ -19,8 +38,18 @@ pub(crate) struct Camera {
     pixel_size: f64,
     half_width: f64,
     half_height: f64,
+    sampler: Sampler,
+    adaptive: Option<Adaptive>,
+    tone_mapping: ToneMapping,
+    integrator: Integrator,
 }
 
+// Number of samples shot through every pixel, indexed by row then column.
+pub(crate) type SampleCounts = Vec<Vec<usize>>;
+
+// offsets of the four quadrant centers of a square, in quarters of its size
+const QUADRANTS: [(f64, f64); 4] = [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)];
+
 transformable!(Camera);
 
 impl Camera {
@@ -45,12 +74,72 @@ impl Camera {
             pixel_size,
             half_width,
             half_height,
+            sampler: Sampler::default(),
+            adaptive: None,
+            tone_mapping: ToneMapping::default(),
+            integrator: Integrator::default(),
         }
     }
 
-    pub fn ray_for_pixel(&self, x: usize, y: usize) -> Option<Ray> {
-        let xoffset = (x as f64 + 0.5) * self.pixel_size;
-        let yoffset = (y as f64 + 0.5) * self.pixel_size;
+    pub fn hsize(&self) -> usize {
+        self.hsize
+    }
+
+    pub fn vsize(&self) -> usize {
+        self.vsize
+    }
+
+    pub fn field_of_view(&self) -> f64 {
+        self.field_of_view
+    }
+
+    pub fn with_sampler(mut self, sampler: Sampler) -> Camera {
+        self.sampler = sampler;
+        self
+    }
+
+    pub fn sampler(&self) -> Sampler {
+        self.sampler
+    }
+
+    // adaptive antialiasing replaces the sampler when set
+    pub fn with_adaptive(mut self, adaptive: Option<Adaptive>) -> Camera {
+        self.adaptive = adaptive;
+        self
+    }
+
+    pub fn adaptive(&self) -> Option<Adaptive> {
+        self.adaptive
+    }
+
+    // applied to the rendered canvas before it is written to a low dynamic range image
+    pub fn with_tone_mapping(mut self, tone_mapping: ToneMapping) -> Camera {
+        self.tone_mapping = tone_mapping;
+        self
+    }
+
+    pub fn tone_mapping(&self) -> ToneMapping {
+        self.tone_mapping
+    }
+
+    pub fn with_integrator(mut self, integrator: Integrator) -> Camera {
+        self.integrator = integrator;
+        self
+    }
+
+    pub fn integrator(&self) -> Integrator {
+        self.integrator
+    }
+
+    #[cfg(test)]
+    pub(crate) fn ray_for_pixel(&self, x: usize, y: usize) -> Option<Ray> {
+        self.ray_through(x as f64 + 0.5, y as f64 + 0.5)
+    }
+
+    // `px` and `py` are canvas coordinates in pixels, from the top left corner
+    pub(crate) fn ray_through(&self, px: f64, py: f64) -> Option<Ray> {
+        let xoffset = px * self.pixel_size;
+        let yoffset = py * self.pixel_size;
 
         let world_x = self.half_width - xoffset;
         let world_y = self.half_height - yoffset;
@@ -62,26 +151,174 @@ impl Camera {
         Some(Ray::new(origin, direction))
     }
 
-    pub fn render(&self, world: &World) -> Canvas {
+    // random paths only depend on the seed and the position of the sample
+    fn color_through(&self, world: &World, px: f64, py: f64) -> Color {
+        let stream = px.to_bits() ^ py.to_bits().rotate_left(32);
+        let mut rng = Rng::with_stream(self.sampler.seed(), stream);
+        self.ray_through(px, py).map_or(Color::default(), |ray| {
+            self.integrator.color_at(world, &ray, &mut rng)
+        })
+    }
+
+    // filtered average of every sample of the pixel
+    pub fn pixel_color(&self, world: &World, x: usize, y: usize) -> Color {
+        self.sampled_pixel_color(world, x, y).0
+    }
+
+    fn sampled_pixel_color(&self, world: &World, x: usize, y: usize) -> (Color, usize) {
+        let samples = self.sampler.pixel_samples(x, y);
+        let mut total = Color::default();
+        let mut total_weight = 0.0;
+        for sample in &samples {
+            let color = self.color_through(world, x as f64 + sample.x, y as f64 + sample.y);
+            total = total + color * sample.weight;
+            total_weight += sample.weight;
+        }
+        let color = match total_weight > 0.0 {
+            true => total / total_weight,
+            false => total,
+        };
+        (color, samples.len())
+    }
+
+    // average color of the square of `size` pixels centered on (`px`, `py`),
+    // split in four again while its quadrants do not agree
+    fn refined_color(
+        &self,
+        world: &World,
+        adaptive: &Adaptive,
+        (px, py): (f64, f64),
+        size: f64,
+        depth: usize,
+    ) -> (Color, usize) {
+        let quarter = size / 4.0;
+        let centers = QUADRANTS.map(|(dx, dy)| (px + dx * quarter, py + dy * quarter));
+        let colors = centers.map(|(cx, cy)| self.color_through(world, cx, cy));
+        let contrasted = colors
+            .iter()
+            .any(|a| colors.iter().any(|b| adaptive.is_contrasted(a, b)));
+        if !contrasted || depth >= adaptive.max_depth() {
+            let total = colors.into_iter().fold(Color::default(), |acc, c| acc + c);
+            return (total / 4.0, 4);
+        }
+        centers
+            .into_iter()
+            .map(|center| self.refined_color(world, adaptive, center, size / 2.0, depth + 1))
+            .fold((Color::default(), 0), |(total, count), (color, n)| {
+                (total + color / 4.0, count + n)
+            })
+    }
+
+    fn has_contrasted_neighbor(
+        first_pass: &Canvas,
+        adaptive: &Adaptive,
+        x: usize,
+        y: usize,
+    ) -> bool {
+        let color = first_pass.rows()[y][x];
+        (y.saturating_sub(1)..=y + 1).any(|ny| {
+            (x.saturating_sub(1)..=x + 1).any(|nx| {
+                first_pass
+                    .color(nx, ny)
+                    .is_some_and(|neighbor| adaptive.is_contrasted(&color, neighbor))
+            })
+        })
+    }
+
+    fn render_adaptive(&self, world: &World, adaptive: &Adaptive) -> (Canvas, SampleCounts) {
+        let mut first_pass = Canvas::new(self.hsize, self.vsize);
+        first_pass
+            .rows_mut()
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(y, row)| {
+                row.par_iter_mut().enumerate().for_each(|(x, color)| {
+                    *color = self.color_through(world, x as f64 + 0.5, y as f64 + 0.5)
+                })
+            });
+
         let mut canvas = Canvas::new(self.hsize, self.vsize);
+        let mut counts = vec![vec![1; self.hsize]; self.vsize];
         canvas
+            .rows_mut()
             .par_iter_mut()
+            .zip(counts.par_iter_mut())
             .enumerate()
-            .for_each(|(y, vec_color)| {
-                vec_color.par_iter_mut().enumerate().for_each(|(x, color)| {
-                    if let Some(ray) = self.ray_for_pixel(x, y) {
-                        *color = world.color_at(&ray);
-                    }
-                })
+            .for_each(|(y, (row, row_counts))| {
+                row.par_iter_mut()
+                    .zip(row_counts.par_iter_mut())
+                    .enumerate()
+                    .for_each(|(x, (color, count))| {
+                        *color = first_pass.rows()[y][x];
+                        if adaptive.max_depth() > 0
+                            && Camera::has_contrasted_neighbor(&first_pass, adaptive, x, y)
+                        {
+                            let center = (x as f64 + 0.5, y as f64 + 0.5);
+                            let (refined, n) = self.refined_color(world, adaptive, center, 1.0, 1);
+                            *color = refined;
+                            *count += n;
+                        }
+                    })
             });
+        (canvas, counts)
+    }
+
+    pub fn render(&self, world: &World) -> Canvas {
+        self.render_with_sample_counts(world).0
+    }
+
+    // the image and how many samples each of its pixels received
+    pub fn render_with_sample_counts(&self, world: &World) -> (Canvas, SampleCounts) {
+        if let Some(adaptive) = &self.adaptive {
+            return self.render_adaptive(world, adaptive);
+        }
+        let mut canvas = Canvas::new(self.hsize, self.vsize);
+        let mut counts = vec![vec![0; self.hsize]; self.vsize];
         canvas
+            .rows_mut()
+            .par_iter_mut()
+            .zip(counts.par_iter_mut())
+            .enumerate()
+            .for_each(|(y, (row, row_counts))| {
+                row.par_iter_mut()
+                    .zip(row_counts.par_iter_mut())
+                    .enumerate()
+                    .for_each(|(x, (color, count))| {
+                        (*color, *count) = self.sampled_pixel_color(world, x, y)
+                    })
+            });
+        (canvas, counts)
+    }
+}
+
+// Debug image of the samples per pixel, white for the most sampled pixels.
+pub(crate) fn sample_count_canvas(counts: &SampleCounts) -> Canvas {
+    let height = counts.len();
+    let width = counts.first().map_or(0, Vec::len);
+    let max = counts.iter().flatten().copied().max().unwrap_or(0).max(1);
+    let mut canvas = Canvas::new(width, height);
+    for (y, row) in counts.iter().enumerate() {
+        for (x, &count) in row.iter().enumerate() {
+            let level = count as f64 / max as f64;
+            canvas.write_pixel(x, y, &Color::new(level, level, level));
+        }
     }
+    canvas
 }
 
 #[cfg(test)]
 mod test {
 
-    use crate::{transform::Transformable, util::assert_float_eq, vector::Vector};
+    use crate::{
+        color,
+        light::PointLight,
+        material::Material,
+        sampling::{Filter, SamplingStrategy},
+        shapes::{shape::Shape, ShapeMaterial},
+        transform::Transformable,
+        util::assert_float_eq,
+        vector::Vector,
+    };
 
     use super::*;
 
@@ -147,4 +384,87 @@ mod test {
 
         assert_eq!(r, Some(expected_ray));
     }
+
+    // white sphere on black background, pixel (4, 5) covers its silhouette
+    fn silhouette_scene() -> (World, Camera) {
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), color::WHITE);
+        let sphere = Shape::sphere().with_material(
+            Material::default()
+                .with_ambient(1.0)
+                .with_diffuse(0.0)
+                .with_specular(0.0),
+        );
+        let camera = Camera::new(11, 11, std::f64::consts::FRAC_PI_2).with_transform(
+            Transform::view_transform(
+                Point::new(0.0, 0.0, -5.0),
+                Point::new(0.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+            ),
+        );
+        (World::new(vec![light.into()], vec![sphere]), camera)
+    }
+
+    #[test]
+    fn single_sample_shoots_through_pixel_center() {
+        let (world, camera) = silhouette_scene();
+        assert_eq!(camera.pixel_color(&world, 4, 5), color::WHITE);
+        assert_eq!(camera.pixel_color(&world, 0, 0), color::BLACK);
+    }
+
+    #[test]
+    fn supersampling_smooths_silhouette() {
+        let (world, camera) = silhouette_scene();
+        let camera =
+            camera.with_sampler(Sampler::new(16, SamplingStrategy::Regular, Filter::Box, 0));
+        let c = camera.pixel_color(&world, 4, 5);
+        assert!(c.r() > 0.0 && c.r() < 1.0);
+        assert_eq!(camera.pixel_color(&world, 5, 5), color::WHITE);
+    }
+
+    #[test]
+    fn rendering_is_deterministic_for_a_seed() {
+        let (world, camera) = silhouette_scene();
+        let sampler = Sampler::new(4, SamplingStrategy::Random, Filter::Gaussian, 3);
+        let a = camera.with_sampler(sampler).render(&world);
+        let b = camera.with_sampler(sampler).render(&world);
+        assert_eq!(a.rows(), b.rows());
+    }
+
+    #[test]
+    fn adaptive_rendering_refines_only_edges() {
+        let (world, camera) = silhouette_scene();
+        let camera = camera.with_adaptive(Some(Adaptive::new(0.1, 2)));
+        let (canvas, counts) = camera.render_with_sample_counts(&world);
+
+        // flat background keeps its single sample
+        assert_eq!(counts[0][0], 1);
+        assert_eq!(counts[2][2], 1);
+
+        // the sphere center is next to the edge, but its quadrants agree
+        assert_eq!(counts[5][5], 1 + 4);
+        assert_eq!(canvas.color(5, 5), Some(&color::WHITE));
+
+        // the silhouette is split at least once
+        assert!(counts[5][4] > 1 && counts[5][4] <= 1 + 16);
+        let edge = canvas.color(4, 5).unwrap();
+        assert!(edge.r() > 0.0 && edge.r() < 1.0);
+    }
+
+    #[test]
+    fn adaptive_rendering_without_depth_is_one_sample() {
+        let (world, camera) = silhouette_scene();
+        let adaptive = camera.with_adaptive(Some(Adaptive::new(0.1, 0)));
+        let (canvas, counts) = adaptive.render_with_sample_counts(&world);
+        assert!(counts.iter().flatten().all(|&n| n == 1));
+        assert_eq!(canvas.rows(), camera.render(&world).rows());
+    }
+
+    #[test]
+    fn sample_counts_as_canvas() {
+        let counts = vec![vec![1, 2], vec![4, 0]];
+        let canvas = sample_count_canvas(&counts);
+        assert_eq!(canvas.color(0, 0), Some(&Color::new(0.25, 0.25, 0.25)));
+        assert_eq!(canvas.color(0, 1), Some(&color::WHITE));
+        assert_eq!(canvas.color(1, 1), Some(&color::BLACK));
+    }
 }
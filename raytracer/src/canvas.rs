@@ -1,14 +1,112 @@
 use std::{
     fs::File,
-    io::{BufWriter, Write},
-    ops::{Deref, DerefMut},
+    io::{BufRead, BufReader, BufWriter, Seek, Write},
+    path::Path,
+    str::FromStr,
 };
 
-use crate::color::{self, Color, MAX_COLOR};
+use image::{
+    codecs::{
+        hdr::{HdrDecoder, HdrEncoder},
+        pnm::{PnmSubtype, SampleEncoding},
+    },
+    error::{ParameterError, ParameterErrorKind},
+    ImageError, ImageOutputFormat, ImageResult, Rgb, Rgb32FImage, RgbImage,
+};
+
+use crate::{
+    color::{self, Color, MAX_COLOR},
+    pfm,
+};
 
 const LINE_LENGTH: usize = 70;
 
-pub(crate) struct Canvas {
+const JPEG_QUALITY: u8 = 95;
+
+/// File format of an image written from a canvas.
+///
+/// ```
+/// use std::path::Path;
+/// use raytracer::ImageFormat;
+///
+/// assert_eq!(ImageFormat::from_path(Path::new("scene.JPG")), Some(ImageFormat::Jpeg));
+/// assert_eq!("qoi".parse(), Ok(ImageFormat::Qoi));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Bmp,
+    Tga,
+    // binary P6 pixmap
+    Ppm,
+    Qoi,
+    // floating point formats keeping colors above 1
+    Exr,
+    Hdr,
+    Pfm,
+}
+
+impl ImageFormat {
+    /// Format matching the extension of `path`, if any.
+    pub fn from_path(path: &Path) -> Option<ImageFormat> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        extension.parse().ok()
+    }
+
+    /// Whether the format stores floating point colors without clamping them.
+    pub fn is_hdr(self) -> bool {
+        matches!(self, ImageFormat::Exr | ImageFormat::Hdr | ImageFormat::Pfm)
+    }
+
+    fn image_format(self) -> image::ImageFormat {
+        match self {
+            ImageFormat::Png => image::ImageFormat::Png,
+            ImageFormat::Jpeg => image::ImageFormat::Jpeg,
+            ImageFormat::Bmp => image::ImageFormat::Bmp,
+            ImageFormat::Tga => image::ImageFormat::Tga,
+            ImageFormat::Ppm => image::ImageFormat::Pnm,
+            ImageFormat::Qoi => image::ImageFormat::Qoi,
+            ImageFormat::Exr => image::ImageFormat::OpenExr,
+            ImageFormat::Hdr => image::ImageFormat::Hdr,
+            // not supported by the image crate, handled in the pfm module
+            ImageFormat::Pfm => image::ImageFormat::Pnm,
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "png" => Ok(ImageFormat::Png),
+            "jpeg" | "jpg" => Ok(ImageFormat::Jpeg),
+            "bmp" => Ok(ImageFormat::Bmp),
+            "tga" => Ok(ImageFormat::Tga),
+            "ppm" => Ok(ImageFormat::Ppm),
+            "qoi" => Ok(ImageFormat::Qoi),
+            "exr" => Ok(ImageFormat::Exr),
+            "hdr" => Ok(ImageFormat::Hdr),
+            "pfm" => Ok(ImageFormat::Pfm),
+            s => Err(format!(
+                "unknown image format `{}`, expected one of `png`, `jpeg`, `bmp`, `tga`, `ppm`, `qoi`, `exr`, `hdr`, `pfm`",
+                s
+            )),
+        }
+    }
+}
+
+/// Grid of colors, rows from top to bottom.
+///
+/// ```
+/// use raytracer::{Canvas, Color};
+///
+/// let mut canvas = Canvas::new(10, 20);
+/// canvas.write_pixel(2, 3, &Color::new(1.0, 0.0, 0.0));
+/// assert_eq!(canvas.color(2, 3), Some(&Color::new(1.0, 0.0, 0.0)));
+/// ```
+pub struct Canvas {
     width: usize,
     height: usize,
     canvas: Vec<Vec<Color>>,
@@ -45,6 +143,97 @@ impl Canvas {
         }
     }
 
+    // rows are only handed out as slices, they keep the width of the canvas
+    pub(crate) fn rows(&self) -> &[Vec<Color>] {
+        &self.canvas
+    }
+
+    pub(crate) fn rows_mut(&mut self) -> &mut [Vec<Color>] {
+        &mut self.canvas
+    }
+
+    /// 8 bit image, colors are clamped to [0, 1].
+    pub fn to_rgb_image(&self) -> RgbImage {
+        RgbImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let (r, g, b) = self.canvas[y as usize][x as usize].to_u8();
+            Rgb([r, g, b])
+        })
+    }
+
+    /// Floating point image keeping the unclamped linear colors.
+    pub fn to_rgb32f_image(&self) -> Rgb32FImage {
+        Rgb32FImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let c = self.canvas[y as usize][x as usize];
+            Rgb([c.r() as f32, c.g() as f32, c.b() as f32])
+        })
+    }
+
+    /// Encodes the canvas into `writer` without going through a file.
+    pub fn encode<W: Write + Seek>(&self, writer: &mut W, format: ImageFormat) -> ImageResult<()> {
+        let output_format = match format {
+            ImageFormat::Png => ImageOutputFormat::Png,
+            ImageFormat::Jpeg => ImageOutputFormat::Jpeg(JPEG_QUALITY),
+            ImageFormat::Bmp => ImageOutputFormat::Bmp,
+            ImageFormat::Tga => ImageOutputFormat::Tga,
+            ImageFormat::Ppm => ImageOutputFormat::Pnm(PnmSubtype::Pixmap(SampleEncoding::Binary)),
+            ImageFormat::Qoi => ImageOutputFormat::Qoi,
+            ImageFormat::Exr => {
+                return self
+                    .to_rgb32f_image()
+                    .write_to(writer, ImageOutputFormat::OpenExr)
+            }
+            ImageFormat::Hdr => {
+                let pixels: Vec<_> = self.to_rgb32f_image().pixels().copied().collect();
+                return HdrEncoder::new(writer).encode(&pixels, self.width, self.height);
+            }
+            ImageFormat::Pfm => return pfm::write_pfm(writer, self),
+        };
+        self.to_rgb_image().write_to(writer, output_format)
+    }
+
+    /// Decodes an image, floating point formats are read without losing precision.
+    pub fn decode<R: BufRead + Seek>(reader: &mut R, format: ImageFormat) -> ImageResult<Canvas> {
+        match format {
+            ImageFormat::Pfm => pfm::read_pfm(reader),
+            // the generic hdr decoder of the image crate converts to 8 bit colors
+            ImageFormat::Hdr => {
+                let decoder = HdrDecoder::new(reader)?;
+                let metadata = decoder.metadata();
+                let pixels = decoder
+                    .read_image_hdr()?
+                    .into_iter()
+                    .flat_map(|Rgb(channels)| channels)
+                    .collect();
+                let image = Rgb32FImage::from_raw(metadata.width, metadata.height, pixels)
+                    .ok_or_else(|| {
+                        ImageError::Parameter(ParameterError::from_kind(
+                            ParameterErrorKind::DimensionMismatch,
+                        ))
+                    })?;
+                Ok(Canvas::from(&image))
+            }
+            _ => {
+                let image = image::load(reader, format.image_format())?;
+                Ok(Canvas::from(&image.into_rgb32f()))
+            }
+        }
+    }
+
+    /// Reads an image file, the format is guessed from the content for unknown extensions.
+    pub fn read_image(path: &Path) -> ImageResult<Canvas> {
+        match ImageFormat::from_path(path) {
+            Some(format) => Canvas::decode(&mut BufReader::new(File::open(path)?), format),
+            None => Ok(Canvas::from(&image::open(path)?.into_rgb32f())),
+        }
+    }
+
+    pub fn write_image(&self, path: &Path, format: ImageFormat) -> ImageResult<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.encode(&mut writer, format)?;
+        writer.flush()?;
+        Ok(())
+    }
+
     pub fn write_ppm(&self, filename: &str) -> std::io::Result<()> {
         let file = File::create(filename)?;
         let mut writer = BufWriter::new(file);
@@ -78,24 +267,21 @@ impl Canvas {
     }
 }
 
-impl Deref for Canvas {
-    type Target = Vec<Vec<Color>>;
-
-    fn deref(&self) -> &Self::Target {
-        &self.canvas
-    }
-}
-
-impl DerefMut for Canvas {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.canvas
+impl From<&Rgb32FImage> for Canvas {
+    fn from(image: &Rgb32FImage) -> Canvas {
+        let mut canvas = Canvas::new(image.width() as usize, image.height() as usize);
+        for (x, y, Rgb([r, g, b])) in image.enumerate_pixels() {
+            let color = Color::new(*r as f64, *g as f64, *b as f64);
+            canvas.write_pixel(x as usize, y as usize, &color);
+        }
+        canvas
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use std::fs;
+    use std::{fs, io::Cursor};
     use tempfile::NamedTempFile;
 
     #[test]
@@ -222,4 +408,128 @@ mod test {
         assert_eq!(terminated_char, Some('\n'));
         Ok(())
     }
+
+    fn small_canvas() -> Canvas {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 0, &Color::new(1.5, 0.0, 0.0));
+        canvas.write_pixel(2, 1, &Color::new(0.0, 0.5, -0.5));
+        canvas
+    }
+
+    #[test]
+    fn canvas_to_rgb_image() {
+        let image = small_canvas().to_rgb_image();
+        assert_eq!(image.dimensions(), (3, 2));
+        assert_eq!(image.get_pixel(0, 0), &Rgb([255, 0, 0]));
+        assert_eq!(image.get_pixel(2, 1), &Rgb([0, 128, 0]));
+        assert_eq!(image.get_pixel(1, 0), &Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn canvas_to_rgb32f_image_keeps_unclamped_colors() {
+        let image = small_canvas().to_rgb32f_image();
+        assert_eq!(image.get_pixel(0, 0), &Rgb([1.5, 0.0, 0.0]));
+        assert_eq!(image.get_pixel(2, 1), &Rgb([0.0, 0.5, -0.5]));
+    }
+
+    #[test]
+    fn encode_binary_ppm() {
+        let mut buffer = Cursor::new(Vec::new());
+        small_canvas()
+            .encode(&mut buffer, ImageFormat::Ppm)
+            .expect("could not encode ppm");
+        let bytes = buffer.into_inner();
+        let header = b"P6\n3 2 255\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(bytes.len(), header.len() + 3 * 2 * 3);
+        assert_eq!(&bytes[header.len()..header.len() + 3], &[255, 0, 0]);
+    }
+
+    #[test]
+    fn encoded_images_decode_to_the_same_pixels() {
+        let canvas = small_canvas();
+        // tga has no magic number, so the decoder is chosen explicitly
+        for (format, decoder) in [
+            (ImageFormat::Png, image::ImageFormat::Png),
+            (ImageFormat::Bmp, image::ImageFormat::Bmp),
+            (ImageFormat::Tga, image::ImageFormat::Tga),
+            (ImageFormat::Ppm, image::ImageFormat::Pnm),
+            (ImageFormat::Qoi, image::ImageFormat::Qoi),
+        ] {
+            let mut buffer = Cursor::new(Vec::new());
+            canvas
+                .encode(&mut buffer, format)
+                .expect("could not encode image");
+            let decoded = image::load_from_memory_with_format(buffer.get_ref(), decoder)
+                .expect("could not decode image")
+                .to_rgb8();
+            assert_eq!(decoded, canvas.to_rgb_image(), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn image_format_from_path() {
+        let format = |p: &str| ImageFormat::from_path(Path::new(p));
+        assert_eq!(format("out.png"), Some(ImageFormat::Png));
+        assert_eq!(format("out.jpg"), Some(ImageFormat::Jpeg));
+        assert_eq!(format("out.JPEG"), Some(ImageFormat::Jpeg));
+        assert_eq!(format("dir/out.ppm"), Some(ImageFormat::Ppm));
+        assert_eq!(format("out.qoi"), Some(ImageFormat::Qoi));
+        assert_eq!(format("out.exr"), Some(ImageFormat::Exr));
+        assert_eq!(format("out.hdr"), Some(ImageFormat::Hdr));
+        assert_eq!(format("out.pfm"), Some(ImageFormat::Pfm));
+        assert_eq!(format("out.gif"), None);
+        assert_eq!(format("out"), None);
+        assert!("webp".parse::<ImageFormat>().is_err());
+    }
+
+    fn bright_canvas() -> Canvas {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 0, &Color::new(12.5, 0.25, 0.0));
+        canvas.write_pixel(1, 0, &Color::new(1.0, 2.0, 4.0));
+        canvas.write_pixel(2, 1, &Color::new(0.0, 1000.0, 0.125));
+        canvas
+    }
+
+    #[test]
+    fn floating_point_formats_keep_colors_above_one() {
+        let canvas = bright_canvas();
+        for format in [ImageFormat::Exr, ImageFormat::Pfm] {
+            let mut buffer = Cursor::new(Vec::new());
+            canvas.encode(&mut buffer, format).unwrap();
+            buffer.set_position(0);
+            let decoded = Canvas::decode(&mut buffer, format).unwrap();
+            assert_eq!(decoded.rows(), canvas.rows(), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn radiance_hdr_keeps_colors_above_one() {
+        let canvas = bright_canvas();
+        let mut buffer = Cursor::new(Vec::new());
+        canvas.encode(&mut buffer, ImageFormat::Hdr).unwrap();
+        buffer.set_position(0);
+        let decoded = Canvas::decode(&mut buffer, ImageFormat::Hdr).unwrap();
+        // rgbe shares one exponent between the channels, so precision is relative
+        for (expected, actual) in canvas
+            .rows()
+            .iter()
+            .flatten()
+            .zip(decoded.rows().iter().flatten())
+        {
+            let tolerance = expected.r().max(expected.g()).max(expected.b()) / 64.0;
+            assert!(expected.contrast(actual) <= tolerance, "{:?}", actual);
+        }
+        assert!(decoded.color(2, 1).unwrap().g() > 990.0);
+    }
+
+    #[test]
+    fn read_image_file_by_extension() -> ImageResult<()> {
+        let file = tempfile::Builder::new().suffix(".pfm").tempfile()?;
+        let canvas = bright_canvas();
+        canvas.write_image(file.path(), ImageFormat::Pfm)?;
+        let read = Canvas::read_image(file.path())?;
+        assert_eq!(read.rows(), canvas.rows());
+        Ok(())
+    }
 }
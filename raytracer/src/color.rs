@@ -17,9 +17,17 @@ pub(crate) const WHITE: Color = Color {
     b: 1.0,
 };
 
-// default color is BLACK
+/// Linear rgb color, channels usually between 0 and 1. The default color is black.
+///
+/// ```
+/// use raytracer::Color;
+///
+/// let c = Color::new(0.2, 0.4, 0.6) + Color::new(0.1, 0.1, 0.1);
+/// assert_eq!(c, Color::new(0.3, 0.5, 0.7));
+/// assert_eq!(c.to_u8(), (77, 128, 179));
+/// ```
 #[derive(Debug, Clone, Copy, Default)]
-pub(crate) struct Color {
+pub struct Color {
     r: f64,
     g: f64,
     b: f64,
@@ -43,6 +51,14 @@ impl Color {
         self.b
     }
 
+    // largest difference between two channels, used to find edges
+    pub fn contrast(&self, other: &Color) -> f64 {
+        (self.r - other.r)
+            .abs()
+            .max((self.g - other.g).abs())
+            .max((self.b - other.b).abs())
+    }
+
     pub fn to_u8(self) -> (u8, u8, u8) {
         let convert = |p: f64| -> u8 {
             let p = p * MAX_COLOR as f64;
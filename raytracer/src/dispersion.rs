@@ -0,0 +1,77 @@
+// wavelengths in micrometers of the fraunhofer lines defining the abbe number
+const D_LINE: f64 = 0.5876;
+const F_LINE: f64 = 0.4861;
+const C_LINE: f64 = 0.6563;
+
+// wavelengths in micrometers standing for the red, green and blue channels
+pub(crate) const CHANNEL_WAVELENGTHS: [f64; 3] = [0.65, 0.55, 0.45];
+
+/// Change of the refractive index with the wavelength of light, splitting
+/// white light refracted by a transparent material into its colors.
+///
+/// ```
+/// use raytracer::{Dispersion, Material};
+///
+/// let flint = Material::default()
+///     .with_transparency(1.0)
+///     .with_refractive_index(1.62)
+///     .with_dispersion(Dispersion::Abbe(36.0));
+/// assert_eq!(flint.dispersion(), Some(Dispersion::Abbe(36.0)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dispersion {
+    /// Cauchy's equation `a + b / λ²`, with λ in micrometers. The refractive
+    /// index of the material is only used for reflections.
+    Cauchy { a: f64, b: f64 },
+    /// Abbe number of the material, lower numbers disperse more. The
+    /// refractive index of the material is the one of yellow light.
+    Abbe(f64),
+}
+
+impl Dispersion {
+    // cauchy coefficients, an abbe number is fitted through the index at the d line
+    fn coefficients(&self, refractive_index: f64) -> (f64, f64) {
+        match *self {
+            Dispersion::Cauchy { a, b } => (a, b),
+            Dispersion::Abbe(abbe) => {
+                let spread = 1.0 / (F_LINE * F_LINE) - 1.0 / (C_LINE * C_LINE);
+                let b = (refractive_index - 1.0) / (abbe.max(f64::EPSILON) * spread);
+                (refractive_index - b / (D_LINE * D_LINE), b)
+            }
+        }
+    }
+
+    // refractive index for light of `wavelength` micrometers
+    pub(crate) fn refractive_index(&self, refractive_index: f64, wavelength: f64) -> f64 {
+        let (a, b) = self.coefficients(refractive_index);
+        a + b / (wavelength * wavelength)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::util::assert_float_eq;
+
+    use super::*;
+
+    #[test]
+    fn cauchy_equation() {
+        // bk7 glass
+        let bk7 = Dispersion::Cauchy {
+            a: 1.5046,
+            b: 0.0042,
+        };
+        assert_float_eq!(bk7.refractive_index(1.0, 0.5), 1.5214);
+        assert!(bk7.refractive_index(1.0, 0.45) > bk7.refractive_index(1.0, 0.65));
+    }
+
+    #[test]
+    fn abbe_number_fits_the_fraunhofer_lines() {
+        let glass = Dispersion::Abbe(20.0);
+        let n = |wavelength: f64| glass.refractive_index(1.5, wavelength);
+        assert_float_eq!(n(D_LINE), 1.5);
+        assert_float_eq!((n(D_LINE) - 1.0) / (n(F_LINE) - n(C_LINE)), 20.0);
+        let [red, green, blue] = CHANNEL_WAVELENGTHS.map(n);
+        assert!(red < green && green < blue);
+    }
+}
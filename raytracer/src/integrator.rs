@@ -0,0 +1,461 @@
+use std::str::FromStr;
+
+use crate::{
+    color::{self, Color},
+    dispersion::CHANNEL_WAVELENGTHS,
+    intersect::intersection::ComputedIntersection,
+    microfacet::Microfacet,
+    ray::Ray,
+    rng::Rng,
+    sampling::cosine_hemisphere,
+    vector::Vector,
+    world::World,
+};
+
+const DEFAULT_MAX_DEPTH: usize = 8;
+const DEFAULT_ROULETTE_DEPTH: usize = 3;
+// paths always have a chance to be terminated by russian roulette
+const MAX_SURVIVAL: f64 = 0.95;
+
+// Monte Carlo path tracing settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathTracer {
+    // maximum number of bounces of a path
+    max_depth: usize,
+    // bounces before paths are randomly terminated
+    roulette_depth: usize,
+}
+
+impl Default for PathTracer {
+    fn default() -> PathTracer {
+        PathTracer {
+            max_depth: DEFAULT_MAX_DEPTH,
+            roulette_depth: DEFAULT_ROULETTE_DEPTH,
+        }
+    }
+}
+
+impl PathTracer {
+    pub fn new(max_depth: usize) -> PathTracer {
+        PathTracer {
+            max_depth,
+            ..PathTracer::default()
+        }
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> PathTracer {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn roulette_depth(&self) -> usize {
+        self.roulette_depth
+    }
+
+    pub fn with_roulette_depth(mut self, roulette_depth: usize) -> PathTracer {
+        self.roulette_depth = roulette_depth;
+        self
+    }
+
+    // one random path starting with `ray`
+    pub(crate) fn color_at(&self, world: &World, ray: &Ray, rng: &mut Rng) -> Color {
+        let mut radiance = color::BLACK;
+        let mut throughput = color::WHITE;
+        let mut ray = *ray;
+        // emissive shapes sampled as lights are only seen directly or in
+        // mirrors, diffuse bounces already gathered their light
+        let mut sees_emitters = true;
+        for depth in 0..self.max_depth {
+            let intersections = world.intersect(&ray);
+            let hit = match intersections.hit() {
+                Some(hit) => hit,
+                None => break,
+            };
+            throughput = throughput & hit.medium_transmittance();
+            let object = hit.object();
+            if sees_emitters || !object.is_bounded() {
+                radiance = radiance + (throughput & object.material().emissive());
+            }
+            radiance = radiance + (throughput & self.direct_light(world, hit));
+
+            let (next, weight, specular) = match self.scatter(hit, rng) {
+                Some(bounce) => bounce,
+                None => break,
+            };
+            throughput = throughput & weight;
+            sees_emitters = specular;
+
+            if depth + 1 >= self.roulette_depth {
+                let survival = throughput
+                    .r()
+                    .max(throughput.g())
+                    .max(throughput.b())
+                    .min(MAX_SURVIVAL);
+                if rng.next_f64() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+            ray = next;
+        }
+        radiance
+    }
+
+    // next event estimation, the ambient term of the whitted integrator
+    // stands in for the indirect light traced here
+    fn direct_light(&self, world: &World, hit: &ComputedIntersection) -> Color {
+        let emitted = world.emitted_lighting(hit);
+        world.lights().iter().fold(emitted, |total, light| {
+            let visibility = world.light_visibility(light, hit.over_point());
+            total
+                + light.direct_lighting(
+                    hit.object(),
+                    hit.object().material(),
+                    hit.over_point(),
+                    hit.eye_vector(),
+                    hit.normal_vector(),
+                    visibility,
+                )
+        })
+    }
+
+    // picks the diffuse, reflected or refracted continuation of the path in
+    // proportion to its weight in the material, with the factor keeping the
+    // estimate unbiased, the flag tells whether the bounce is specular
+    fn scatter(&self, hit: &ComputedIntersection, rng: &mut Rng) -> Option<(Ray, Color, bool)> {
+        let material = hit.object().material();
+        if let Some(microfacet) = material.microfacet() {
+            return self.scatter_microfacet(hit, &microfacet, rng);
+        }
+        let mut reflective = material.reflective();
+        let mut transparency = material.transparency();
+        if reflective > 0.0 && transparency > 0.0 {
+            let reflectance = hit.schlick();
+            reflective *= reflectance;
+            transparency *= 1.0 - reflectance;
+        }
+        let total = reflective + transparency + material.diffuse();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let choice = rng.next_f64() * total;
+        if choice < reflective {
+            let direction = glossy(hit, hit.reflect_vector(), rng);
+            let ray = Ray::new(*hit.over_point(), direction);
+            return Some((ray, color::WHITE * total, true));
+        }
+        if choice < reflective + transparency {
+            let (direction, tint) = refraction(hit, rng)?;
+            let direction = glossy(hit, &direction, rng);
+            let ray = Ray::new(*hit.under_point(), direction);
+            return Some((ray, tint * total, true));
+        }
+        let direction = cosine_hemisphere(hit.normal_vector(), rng.next_f64(), rng.next_f64());
+        let albedo = material.color_at(hit.object(), hit.over_point());
+        Some((
+            Ray::new(*hit.over_point(), direction),
+            albedo * total,
+            false,
+        ))
+    }
+
+    // the specular lobe is chosen in proportion to the fresnel reflectance,
+    // the rest of the light is refracted or diffused
+    fn scatter_microfacet(
+        &self,
+        hit: &ComputedIntersection,
+        microfacet: &Microfacet,
+        rng: &mut Rng,
+    ) -> Option<(Ray, Color, bool)> {
+        let material = hit.object().material();
+        let base_color = material.color_at(hit.object(), hit.over_point());
+        let normal = hit.normal_vector();
+        let eye = hit.eye_vector();
+        let fresnel = microfacet.fresnel(base_color, eye.dot(normal));
+        let specular = (fresnel.r() + fresnel.g() + fresnel.b()) / 3.0;
+        let transparency = material.transparency() * (1.0 - specular);
+        let diffuse =
+            (1.0 - microfacet.metallic()) * (1.0 - specular) * (1.0 - material.transparency());
+        let total = specular + transparency + diffuse;
+        if total <= 0.0 {
+            return None;
+        }
+
+        let choice = rng.next_f64() * total;
+        if choice < specular {
+            let half = microfacet.sample_half_vector(normal, rng.next_f64(), rng.next_f64());
+            let direction = half * (2.0 * eye.dot(&half)) - *eye;
+            let weight = microfacet.sample_weight(base_color, normal, eye, &half, &direction);
+            if weight == color::BLACK {
+                return None;
+            }
+            let ray = Ray::new(*hit.over_point(), direction);
+            return Some((ray, weight * (total / specular), microfacet.is_smooth()));
+        }
+        if choice < specular + transparency {
+            let (direction, tint) = refraction(hit, rng)?;
+            // frosted independently of the roughness of the microfacets
+            let direction = glossy(hit, &direction, rng);
+            let ray = Ray::new(*hit.under_point(), direction);
+            return Some((ray, tint * total, true));
+        }
+        let direction = cosine_hemisphere(normal, rng.next_f64(), rng.next_f64());
+        let albedo = (color::WHITE - fresnel) & base_color;
+        Some((
+            Ray::new(*hit.over_point(), direction),
+            albedo * (total / (1.0 - specular)),
+            false,
+        ))
+    }
+}
+
+// refracted direction with the channels it carries, dispersive materials
+// refract one channel picked at random
+fn refraction(hit: &ComputedIntersection, rng: &mut Rng) -> Option<(Vector, Color)> {
+    if !hit.is_dispersive() {
+        return Some((hit.refract_vector()?, color::WHITE));
+    }
+    let channel = ((rng.next_f64() * 3.0) as usize).min(2);
+    let direction = hit.refract_vector_at(CHANNEL_WAVELENGTHS[channel])?;
+    let mut tint = [0.0; 3];
+    tint[channel] = 3.0;
+    Some((direction, Color::new(tint[0], tint[1], tint[2])))
+}
+
+// `direction` spread inside the glossy cone of the material, kept on its side of the surface
+fn glossy(hit: &ComputedIntersection, direction: &Vector, rng: &mut Rng) -> Vector {
+    let material = hit.object().material();
+    if !material.is_glossy() {
+        return *direction;
+    }
+    let perturbed = material.glossy_direction(direction, rng.next_f64(), rng.next_f64());
+    let normal = hit.normal_vector();
+    match normal.dot(&perturbed) * normal.dot(direction) > 0.0 {
+        true => perturbed,
+        false => *direction,
+    }
+}
+
+/// Algorithm computing the color seen along camera rays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Integrator {
+    /// Direct lighting with mirror reflection and refraction.
+    #[default]
+    Whitted,
+    /// Global illumination by Monte Carlo path tracing, noisy with few samples per pixel.
+    PathTracing(PathTracer),
+}
+
+impl Integrator {
+    // only the path tracer has a number of bounces, whitted rays have fixed
+    // reflection and refraction limits
+    pub fn with_max_depth(self, max_depth: usize) -> Result<Integrator, String> {
+        match self {
+            Integrator::PathTracing(tracer) => {
+                Ok(Integrator::PathTracing(tracer.with_max_depth(max_depth)))
+            }
+            Integrator::Whitted => Err(
+                "bounces only apply to the `path` integrator, `whitted` rays have fixed limits"
+                    .to_string(),
+            ),
+        }
+    }
+
+    pub(crate) fn color_at(&self, world: &World, ray: &Ray, rng: &mut Rng) -> Color {
+        match self {
+            Integrator::Whitted => world.color_at(ray),
+            Integrator::PathTracing(tracer) => tracer.color_at(world, ray, rng),
+        }
+    }
+}
+
+impl FromStr for Integrator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "whitted" => Ok(Integrator::Whitted),
+            "path" | "path-tracing" => Ok(Integrator::PathTracing(PathTracer::default())),
+            s => Err(format!(
+                "unknown integrator `{}`, expected one of `whitted`, `path`",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        light::PointLight,
+        material::Material,
+        point::Point,
+        shapes::{shape::Shape, ShapeMaterial},
+        transform::{Transform, Transformable},
+        vector::Vector,
+    };
+
+    use super::*;
+
+    fn average_path_color(world: &World, ray: &Ray, paths: u64) -> Color {
+        let tracer = PathTracer::default();
+        let total = (0..paths).fold(color::BLACK, |total, stream| {
+            let mut rng = Rng::with_stream(7, stream);
+            total + tracer.color_at(world, ray, &mut rng)
+        });
+        total / paths as f64
+    }
+
+    #[test]
+    fn direct_light_matches_whitted_without_ambient() {
+        let sphere = Shape::sphere().with_material(
+            Material::default()
+                .with_color(Color::new(0.8, 1.0, 0.6))
+                .with_ambient(0.0),
+        );
+        let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), color::WHITE);
+        let world = World::default().with_light(light).with_object(sphere);
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        // diffuse bounces leave the scene, so every path gives the same color
+        let expected = world.color_at(&ray);
+        for stream in 0..4 {
+            let mut rng = Rng::with_stream(1, stream);
+            let color = PathTracer::default().color_at(&world, &ray, &mut rng);
+            assert_eq!(color, expected);
+        }
+    }
+
+    #[test]
+    fn emissive_surfaces_are_seen_without_lights() {
+        let emissive = Color::new(2.0, 1.0, 0.5);
+        let sphere = Shape::sphere().with_material(
+            Material::default()
+                .with_emissive(emissive)
+                .with_diffuse(0.0),
+        );
+        let world = World::default().with_object(sphere);
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut rng = Rng::new(3);
+        let color = PathTracer::default().color_at(&world, &ray, &mut rng);
+        assert_eq!(color, emissive);
+    }
+
+    #[test]
+    fn mirrors_reflect_the_path() {
+        let emissive = Color::new(0.5, 0.5, 1.0);
+        // without highlights of the lamp, which is also a light
+        let mirror = Shape::plane()
+            .with_material(
+                Material::default()
+                    .with_diffuse(0.0)
+                    .with_specular(0.0)
+                    .with_reflective(1.0),
+            )
+            .with_transform(Transform::translation(0.0, -1.0, 0.0));
+        let lamp = Shape::sphere()
+            .with_material(
+                Material::default()
+                    .with_emissive(emissive)
+                    .with_diffuse(0.0),
+            )
+            .with_transform(Transform::translation(0.0, 1.0, 2.0));
+        let world = World::default().with_object(mirror).with_object(lamp);
+        // looking down at the mirror, the lamp is seen in the reflection
+        let ray = Ray::new(
+            Point::new(0.0, 1.0, -2.0),
+            Vector::new(0.0, -1.0, 1.0).normalize(),
+        );
+        let mut rng = Rng::new(3);
+        let color = PathTracer::default().color_at(&world, &ray, &mut rng);
+        assert_eq!(color, emissive);
+    }
+
+    #[test]
+    fn indirect_light_reaches_shadowed_points() {
+        let material = Material::default().with_ambient(0.0).with_specular(0.0);
+        let floor = Shape::plane().with_material(material.clone());
+        let ceiling = Shape::plane()
+            .with_material(material.clone())
+            .with_transform(Transform::translation(0.0, 4.0, 0.0));
+        let blocker = Shape::sphere().with_material(material).with_transform(
+            Transform::translation(0.0, 1.0, 0.0) * Transform::scaling(0.5, 0.5, 0.5),
+        );
+        let light = PointLight::new(Point::new(0.0, 2.0, 0.0), color::WHITE);
+        let world = World::default()
+            .with_light(light)
+            .with_object(floor)
+            .with_object(ceiling)
+            .with_object(blocker);
+
+        let ray = Ray::new(
+            Point::new(3.0, 1.0, 0.0),
+            Vector::new(-3.0, -1.0, 0.0).normalize(),
+        );
+        assert_eq!(world.color_at(&ray), color::BLACK);
+        let color = average_path_color(&world, &ray, 64);
+        assert!(color.r() > 0.01, "{:?}", color);
+        assert!(color.r() < 1.0, "{:?}", color);
+    }
+
+    #[test]
+    fn paths_stop_at_max_depth() {
+        // between two facing mirrors a path bounces forever
+        let mirror = Material::default()
+            .with_diffuse(0.0)
+            .with_reflective(1.0)
+            .with_emissive(Color::new(0.1, 0.1, 0.1));
+        let lower = Shape::plane().with_material(mirror.clone());
+        let upper = Shape::plane()
+            .with_material(mirror)
+            .with_transform(Transform::translation(0.0, 1.0, 0.0));
+        let world = World::default().with_object(lower).with_object(upper);
+        let ray = Ray::new(Point::new(0.0, 0.5, 0.0), Vector::new(0.0, 1.0, 0.0));
+        let tracer = PathTracer::new(2).with_roulette_depth(10);
+        let color = tracer.color_at(&world, &ray, &mut Rng::new(5));
+        assert_eq!(color, Color::new(0.2, 0.2, 0.2));
+    }
+
+    #[test]
+    fn parse_integrator_names() {
+        assert_eq!("whitted".parse(), Ok(Integrator::Whitted));
+        assert_eq!(
+            "path".parse(),
+            Ok(Integrator::PathTracing(PathTracer::default()))
+        );
+        assert!("photon".parse::<Integrator>().is_err());
+        assert!(Integrator::Whitted.with_max_depth(3).is_err());
+        assert_eq!(
+            Integrator::PathTracing(PathTracer::default()).with_max_depth(3),
+            Ok(Integrator::PathTracing(PathTracer::new(3)))
+        );
+    }
+
+    #[test]
+    fn emissive_shapes_light_diffuse_surfaces_once() {
+        let floor =
+            Shape::plane().with_material(Material::default().with_ambient(0.0).with_specular(0.0));
+        let lamp = Shape::sphere()
+            .with_material(
+                Material::default()
+                    .with_emissive(Color::new(1.0, 1.0, 1.0))
+                    .with_diffuse(0.0),
+            )
+            .with_transform(Transform::translation(0.0, 3.0, 0.0));
+        let world = World::default().with_object(floor).with_object(lamp);
+        let ray = Ray::new(
+            Point::new(0.0, 1.0, -1.0),
+            Vector::new(0.0, -1.0, 1.0).normalize(),
+        );
+        // diffuse bounces hitting the lamp are not counted again, so every
+        // path sees only the direct light of the lamp
+        let direct = world.color_at(&ray);
+        assert!(direct.r() > 0.0);
+        let color = average_path_color(&world, &ray, 16);
+        assert_eq!(color, direct);
+    }
+}
@@ -1,4 +1,7 @@
 use crate::{
+    color::{self, Color},
+    dispersion::Dispersion,
+    material::Material,
     point::Point,
     ray::Ray,
     shapes::{shape::Shape, ShapeWorld},
@@ -7,6 +10,10 @@ use crate::{
 
 pub(crate) type IntersectionsFactor = Vec<f64>;
 
+// barycentric `u` and `v` of a hit, only reported by triangles
+pub(crate) type UvFactor = (f64, f64);
+pub(crate) type UvIntersectionsFactor = Vec<(f64, f64, f64)>;
+
 const OFFSET_FACTOR: f64 = 1E-10;
 pub(crate) const DEFAULT_REFRACTIVE_INDEX: f64 = 1.0;
 
@@ -14,20 +21,34 @@ pub(crate) const DEFAULT_REFRACTIVE_INDEX: f64 = 1.0;
 pub(crate) struct Intersection<'a> {
     object: &'a Shape,
     t: f64,
+    uv: Option<UvFactor>,
 }
 
 impl<'a> Intersection<'a> {
-    pub fn new(t: f64, object: &Shape) -> Intersection {
-        Intersection { t, object }
+    pub fn new(t: f64, object: &Shape) -> Intersection<'_> {
+        Intersection {
+            t,
+            object,
+            uv: None,
+        }
+    }
+
+    pub fn with_uv(t: f64, u: f64, v: f64, object: &Shape) -> Intersection<'_> {
+        Intersection {
+            t,
+            object,
+            uv: Some((u, v)),
+        }
     }
 
     pub fn prepare_computations(self, ray: &Ray) -> Option<ComputedIntersection<'a>> {
         let t = self.t;
         let object = self.object;
+        let uv = self.uv;
 
         let point = ray.position(t);
         let eye_vector = -ray.direction();
-        let mut normal_vector = object.normal_at(&point)?;
+        let mut normal_vector = object.normal_at_uv(&point, uv)?;
 
         let inside = match normal_vector.dot(&eye_vector) < 0.0 {
             false => false,
@@ -44,6 +65,7 @@ impl<'a> Intersection<'a> {
         Some(ComputedIntersection {
             t,
             object,
+            uv,
             point,
             over_point,
             under_point,
@@ -53,6 +75,11 @@ impl<'a> Intersection<'a> {
             inside,
             n1: None,
             n2: None,
+            medium_absorption: color::BLACK,
+            medium_distance: 0.0,
+            solid: None,
+            n1_dispersion: None,
+            n2_dispersion: None,
         })
     }
 }
@@ -61,6 +88,7 @@ impl<'a> Intersection<'a> {
 pub(crate) struct ComputedIntersection<'a> {
     object: &'a Shape,
     t: f64,
+    uv: Option<UvFactor>,
     point: Point,
     over_point: Point,
     under_point: Point,
@@ -70,6 +98,15 @@ pub(crate) struct ComputedIntersection<'a> {
     n1: Option<f64>,
     n2: Option<f64>,
     inside: bool,
+    // absorption of the object the ray travels in before reaching this
+    // intersection, over the distance since the previous one
+    medium_absorption: Color,
+    medium_distance: f64,
+    // csg the object is part of, refraction goes in and out of the whole solid
+    solid: Option<&'a Shape>,
+    // dispersion of the materials on both sides, around n1 and n2
+    n1_dispersion: Option<Dispersion>,
+    n2_dispersion: Option<Dispersion>,
 }
 
 impl<'a> ComputedIntersection<'a> {
@@ -77,10 +114,24 @@ impl<'a> ComputedIntersection<'a> {
         self.t
     }
 
-    pub fn object(&self) -> &Shape {
+    pub fn object(&self) -> &'a Shape {
         self.object
     }
 
+    // shape whose inside the ray enters or leaves at this intersection
+    pub fn solid(&self) -> &'a Shape {
+        self.solid.unwrap_or(self.object)
+    }
+
+    pub fn set_solid(&mut self, solid: &'a Shape) {
+        self.solid = Some(solid);
+    }
+
+    #[cfg(test)]
+    pub fn uv(&self) -> Option<UvFactor> {
+        self.uv
+    }
+
     pub fn eye_vector(&self) -> &Vector {
         &self.eye_vector
     }
@@ -101,20 +152,46 @@ impl<'a> ComputedIntersection<'a> {
         &self.under_point
     }
 
+    #[cfg(test)]
     pub fn n1(&self) -> Option<f64> {
         self.n1
     }
 
-    pub fn set_n1(&mut self, n1: f64) {
-        self.n1 = Some(n1);
-    }
-
+    #[cfg(test)]
     pub fn n2(&self) -> Option<f64> {
         self.n2
     }
 
-    pub fn set_n2(&mut self, n2: f64) {
-        self.n2 = Some(n2);
+    // indices of the materials the ray leaves and enters, none outside of any object
+    pub fn set_indices(&mut self, exit: Option<&Material>, enter: Option<&Material>) {
+        let index =
+            |m: Option<&Material>| m.map_or(DEFAULT_REFRACTIVE_INDEX, |m| m.refractive_index());
+        self.n1 = Some(index(exit));
+        self.n2 = Some(index(enter));
+        self.n1_dispersion = exit.and_then(|m| m.dispersion());
+        self.n2_dispersion = enter.and_then(|m| m.dispersion());
+    }
+
+    pub fn is_dispersive(&self) -> bool {
+        self.n1_dispersion.is_some() || self.n2_dispersion.is_some()
+    }
+
+    pub fn set_medium(&mut self, absorption: Color, distance: f64) {
+        self.medium_absorption = absorption;
+        self.medium_distance = distance;
+    }
+
+    // light left after crossing the medium in front of the intersection
+    pub fn medium_transmittance(&self) -> Color {
+        if self.medium_absorption == color::BLACK {
+            return color::WHITE;
+        }
+        let channel = |c: f64| (-c * self.medium_distance).exp();
+        Color::new(
+            channel(self.medium_absorption.r()),
+            channel(self.medium_absorption.g()),
+            channel(self.medium_absorption.b()),
+        )
     }
 
     pub fn schlick(&self) -> f64 {
@@ -141,6 +218,41 @@ impl<'a> ComputedIntersection<'a> {
 
         r0 + (1.0 - r0) * (1.0 - cos).powi(5)
     }
+
+    // direction of the refracted ray, none on total internal reflection
+    pub fn refract_vector(&self) -> Option<Vector> {
+        let n1 = self
+            .n1
+            .expect("`refract_vector` should only be called after n1 calculated");
+        let n2 = self
+            .n2
+            .expect("`refract_vector` should only be called after n2 calculated");
+        self.refract_between(n1, n2)
+    }
+
+    // direction of the refracted light of `wavelength` micrometers
+    pub fn refract_vector_at(&self, wavelength: f64) -> Option<Vector> {
+        let at = |n: f64, dispersion: Option<Dispersion>| match dispersion {
+            Some(dispersion) => dispersion.refractive_index(n, wavelength),
+            None => n,
+        };
+        let n1 = at(self.n1?, self.n1_dispersion);
+        let n2 = at(self.n2?, self.n2_dispersion);
+        self.refract_between(n1, n2)
+    }
+
+    fn refract_between(&self, n1: f64, n2: f64) -> Option<Vector> {
+        let n_ratio = n1 / n2;
+        let cos_i = self.eye_vector.dot(&self.normal_vector);
+        let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
+
+        if sin2_t >= 1.0 {
+            return None;
+        }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        Some(self.normal_vector * (n_ratio * cos_i - cos_t) - self.eye_vector * n_ratio)
+    }
 }
 
 #[cfg(test)]
@@ -210,6 +322,34 @@ mod test {
         assert!(comp.point.z() < comp.under_point.z());
     }
 
+    #[test]
+    fn intersection_can_encapsulate_u_and_v() {
+        let s = Shape::triangle(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+        );
+        let i = Intersection::with_uv(3.5, 0.2, 0.4, &s);
+        assert_eq!(i.uv, Some((0.2, 0.4)));
+    }
+
+    #[test]
+    fn preparing_normal_on_smooth_triangle() {
+        let s = Shape::smooth_triangle(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(-1.0, 0.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+        );
+        let r = Ray::new(Point::new(-0.2, 0.3, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let comp = Intersection::with_uv(1.0, 0.45, 0.25, &s)
+            .prepare_computations(&r)
+            .unwrap();
+        assert_eq!(comp.normal_vector, Vector::new(-0.5547, 0.83205, 0.0));
+    }
+
     #[test]
     fn precomputing_reflection_vector() {
         let shape = Shape::plane();
@@ -1,7 +1,7 @@
-use crate::{ray::Ray, shapes::shape::Shape};
+use crate::{color, material::Material, ray::Ray, shapes::shape::Shape};
 
 use super::intersection::{
-    ComputedIntersection, Intersection, IntersectionsFactor, DEFAULT_REFRACTIVE_INDEX,
+    ComputedIntersection, Intersection, IntersectionsFactor, UvIntersectionsFactor,
 };
 
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -27,20 +27,42 @@ impl<'a> Intersections<'a> {
         Intersections { data }
     }
 
-    pub fn get(&self, index: usize) -> Option<&ComputedIntersection> {
+    pub fn with_uv(
+        mut roots: UvIntersectionsFactor,
+        object: &'a Shape,
+        ray: &Ray,
+    ) -> Intersections<'a> {
+        roots.sort_unstable_by(|a, b| {
+            a.0.partial_cmp(&b.0)
+                .unwrap_or_else(|| panic!("`{}` or `{}` will never be NaN", a.0, b.0))
+        });
+        let data = roots
+            .iter()
+            .filter_map(|&(t, u, v)| {
+                Intersection::with_uv(t, u, v, object).prepare_computations(ray)
+            })
+            .collect();
+        Intersections { data }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ComputedIntersection<'a>> {
+        self.data.iter()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&ComputedIntersection<'a>> {
         self.data.get(index)
     }
 
-    pub fn get_mut(&'a mut self, index: usize) -> Option<&mut ComputedIntersection> {
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut ComputedIntersection<'a>> {
         self.data.get_mut(index)
     }
 
-    pub fn hit(&self) -> Option<&ComputedIntersection> {
+    pub fn hit(&self) -> Option<&ComputedIntersection<'a>> {
         let non_negative_index = self.data.partition_point(|i| i.t() < 0.0);
         self.get(non_negative_index)
     }
 
-    pub fn merge(mut self, mut other: Intersections<'a>) -> Intersections {
+    pub fn merge(mut self, mut other: Intersections<'a>) -> Intersections<'a> {
         // use merge like merge sort but push backward
         let mut merged_data = Vec::with_capacity(self.data.len() + other.data.len());
 
@@ -54,12 +76,12 @@ impl<'a> Intersections<'a> {
             }
         }
 
-        while !self.data.is_empty() {
-            merged_data.push(self.data.pop().unwrap());
+        while let Some(comp) = self.data.pop() {
+            merged_data.push(comp);
         }
 
-        while !other.data.is_empty() {
-            merged_data.push(other.data.pop().unwrap());
+        while let Some(comp) = other.data.pop() {
+            merged_data.push(comp);
         }
 
         merged_data.reverse();
@@ -68,37 +90,52 @@ impl<'a> Intersections<'a> {
         self
     }
 
+    // intersections with the parts of `solid` are refracted as crossing it
+    pub fn with_solid(mut self, solid: &'a Shape) -> Intersections<'a> {
+        for comp in self.data.iter_mut() {
+            comp.set_solid(solid);
+        }
+        self
+    }
+
+    // `predicate` is called once for each intersection, in order of `t`
+    pub fn filter<F>(mut self, predicate: F) -> Intersections<'a>
+    where
+        F: FnMut(&ComputedIntersection<'a>) -> bool,
+    {
+        self.data.retain(predicate);
+        self
+    }
+
+    // the solids a ray is inside of are tracked to know the refractive
+    // indices on both sides of every intersection
     pub fn update_refractive_index(mut self) -> Self {
-        let mut container: Vec<&Shape> = Vec::with_capacity(self.count());
+        let mut container: Vec<(&Shape, &Material)> = Vec::with_capacity(self.count());
+        // distances inside a medium are counted from the ray origin at the earliest
+        let mut previous_t = 0.0_f64;
         for comp in self.data.iter_mut() {
-            if container.is_empty() {
-                let exit_index = DEFAULT_REFRACTIVE_INDEX;
-                let enter_index = comp.object().material().refractive_index();
-                comp.set_n1(exit_index);
-                comp.set_n2(enter_index);
-                container.push(comp.object());
-            } else {
-                let presented = container
-                    .iter()
-                    .position(|&object| std::ptr::eq(object, comp.object()));
-                if let Some(index) = presented {
-                    let exit_index = container.last().unwrap().material().refractive_index();
+            let absorption = match container.last() {
+                Some((_, material)) => material.absorption_coefficient(),
+                None => color::BLACK,
+            };
+            comp.set_medium(absorption, (comp.t() - previous_t.max(0.0)).max(0.0));
+            previous_t = comp.t();
+
+            let solid = comp.solid();
+            let exit = container.last().map(|&(_, material)| material);
+            let presented = container
+                .iter()
+                .position(|&(object, _)| std::ptr::eq(object, solid));
+            match presented {
+                Some(index) => {
                     container.remove(index);
-                    let enter_index = match container.last() {
-                        Some(&object) => object.material().refractive_index(),
-                        None => DEFAULT_REFRACTIVE_INDEX,
-                    };
-                    comp.set_n1(exit_index);
-                    comp.set_n2(enter_index);
-                } else {
-                    let exit_index = match container.last() {
-                        Some(&object) => object.material().refractive_index(),
-                        None => DEFAULT_REFRACTIVE_INDEX,
-                    };
-                    let enter_index = comp.object().material().refractive_index();
-                    comp.set_n1(exit_index);
-                    comp.set_n2(enter_index);
-                    container.push(comp.object());
+                    let enter = container.last().map(|&(_, material)| material);
+                    comp.set_indices(exit, enter);
+                }
+                None => {
+                    let material = comp.object().material();
+                    comp.set_indices(exit, Some(material));
+                    container.push((solid, material));
                 }
             }
         }
@@ -111,8 +148,10 @@ impl<'a> Intersections<'a> {
 mod test {
 
     use crate::{
+        color::Color,
+        material::Material,
         point::Point,
-        shapes::ShapeMaterial,
+        shapes::{csg::CsgOperation, ShapeMaterial},
         transform::{Transform, Transformable},
         util::assert_float_eq,
         vector::Vector,
@@ -264,4 +303,55 @@ mod test {
         let reflectance = comp.schlick();
         assert_float_eq!(reflectance, 0.48873);
     }
+
+    #[test]
+    fn intersections_know_the_medium_in_front_of_them() {
+        let absorbing = |absorption: f64| {
+            Shape::sphere().with_material(
+                Material::default()
+                    .with_absorption(Color::new(absorption, absorption, absorption))
+                    .with_density(2.0),
+            )
+        };
+        let a = absorbing(0.5).with_transform(Transform::scaling(3.0, 3.0, 3.0));
+        let b = absorbing(0.1);
+        let w = World::new(vec![], vec![a, b]);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+        let expected = [0.0_f64, 2.0, 0.4, 2.0];
+        assert_eq!(xs.count(), expected.len());
+        for (x, absorbed) in xs.iter().zip(expected) {
+            assert_float_eq!(x.medium_transmittance().g(), (-absorbed).exp());
+        }
+    }
+
+    #[test]
+    fn medium_distance_starts_at_the_ray_origin() {
+        let shape = Shape::sphere()
+            .with_material(Material::default().with_absorption(Color::new(1.0, 0.0, 0.0)));
+        let r = Ray::new(Point::new(0.0, 0.0, 0.5), Vector::new(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![-1.5, 0.5], &shape, &r).update_refractive_index();
+        let exit = xs.get(1).unwrap();
+        assert_float_eq!(exit.medium_transmittance().r(), (-0.5_f64).exp());
+        assert_float_eq!(exit.medium_transmittance().g(), 1.0);
+    }
+
+    #[test]
+    fn refraction_crosses_csg_as_one_solid() {
+        let glass = Material::default().with_refractive_index(1.5);
+        let block = Shape::cube().with_material(glass.clone());
+        let cut = Shape::cube()
+            .with_material(glass)
+            .with_transform(Transform::translation(0.0, 0.0, 1.5));
+        let csg = Shape::csg(CsgOperation::Difference, block, cut);
+        let w = World::new(vec![], vec![csg]);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+        let indices: Vec<_> = xs.iter().map(|x| (x.n1(), x.n2())).collect();
+        // the ray leaves through the face of the cut, not into another object
+        assert_eq!(
+            indices,
+            vec![(Some(1.0), Some(1.5)), (Some(1.5), Some(1.0))]
+        );
+    }
 }
@@ -1,3 +1,32 @@
+//! A ray tracer, scenes are either read from yaml files with [`render_image`]
+//! or built in code and rendered in memory with [`render`].
+//!
+//! ```
+//! use raytracer::{
+//!     render, Camera, Color, Material, Point, PointLight, Shape, ShapeMaterial, Transform,
+//!     Transformable, Vector, World,
+//! };
+//!
+//! let world = World::default()
+//!     .with_light(PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0)))
+//!     .with_object(Shape::sphere().with_material(
+//!         Material::default().with_color(Color::new(1.0, 0.2, 1.0)),
+//!     ))
+//!     .with_object(Shape::plane().with_transform(Transform::translation(0.0, -1.0, 0.0)));
+//!
+//! let camera = Camera::new(32, 24, std::f64::consts::FRAC_PI_3).with_transform(
+//!     Transform::view_transform(
+//!         Point::new(0.0, 1.5, -5.0),
+//!         Point::new(0.0, 0.0, 0.0),
+//!         Vector::new(0.0, 1.0, 0.0),
+//!     ),
+//! );
+//!
+//! let canvas = render(&world, &camera);
+//! assert_eq!((canvas.width(), canvas.height()), (32, 24));
+//! assert_ne!(canvas.color(16, 12), Some(&Color::default()));
+//! ```
+
 pub(crate) mod util;
 
 pub(crate) mod point;
@@ -8,6 +37,8 @@ pub(crate) mod color;
 
 pub(crate) mod canvas;
 
+pub(crate) mod pfm;
+
 pub(crate) mod matrix;
 
 pub(crate) mod transform;
@@ -22,14 +53,58 @@ pub(crate) mod light;
 
 pub(crate) mod phong;
 
+pub(crate) mod microfacet;
+
+pub(crate) mod dispersion;
+
 pub(crate) mod material;
 
 pub(crate) mod world;
 
+pub(crate) mod rng;
+
+pub(crate) mod sampling;
+
+pub(crate) mod integrator;
+
 pub(crate) mod camera;
 
+pub(crate) mod tonemap;
+
 pub(crate) mod patterns;
 
 pub(crate) mod parser;
 
-pub use parser::render::render_image;
+pub use camera::Camera;
+pub use canvas::{Canvas, ImageFormat};
+pub use color::Color;
+pub use dispersion::Dispersion;
+pub use integrator::{Integrator, PathTracer};
+pub use light::{
+    AreaLight, AreaShape, Attenuation, DirectionalLight, Light, PointLight, SpotLight,
+};
+pub use material::Material;
+pub use microfacet::Microfacet;
+pub use parser::{
+    error::{Location, ParseError, ParseErrorKind},
+    render::{render_image, RenderOptions},
+};
+pub use patterns::{
+    mapping::UvMapping,
+    noise::Noise,
+    pattern::Pattern,
+    texture::{ImageTexture, TextureFilter, WrapMode},
+    uv::UvPattern,
+};
+pub use point::Point;
+pub use sampling::{Adaptive, Filter, Sampler, SamplingStrategy};
+pub use shapes::{csg::CsgOperation, shape::Shape, ShapeMaterial};
+pub use tonemap::{Gamma, ToneMapping, ToneOperator};
+pub use transform::{Transform, Transformable};
+pub use vector::Vector;
+pub use world::World;
+
+/// Renders `world` as seen from `camera`, without touching the file system.
+pub fn render(world: &World, camera: &Camera) -> Canvas {
+    camera.render(world)
+}
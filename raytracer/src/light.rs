@@ -1,9 +1,58 @@
-use crate::{color::Color, material::Material, point::Point, shapes::shape::Shape, vector::Vector};
+use crate::{
+    color::Color, material::Material, point::Point, rng::Rng, shapes::shape::Shape, util::INFINITY,
+    vector::Vector,
+};
 
+// How light fades with the distance to its source.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Attenuation {
+    #[default]
+    None,
+    // intensity / (constant + linear * d + quadratic * d^2), unclamped so a
+    // constant below 1 brightens the light close to it; the denominator must
+    // stay positive, so `constant` > 0 and the other terms >= 0
+    Polynomial {
+        constant: f64,
+        linear: f64,
+        quadratic: f64,
+    },
+    // intensity * (radius / d)^2, full intensity within `radius` of the light,
+    // which must be positive
+    InverseSquare {
+        radius: f64,
+    },
+}
+
+impl Attenuation {
+    pub fn factor(&self, distance: f64) -> f64 {
+        match *self {
+            Attenuation::None => 1.0,
+            Attenuation::Polynomial {
+                constant,
+                linear,
+                quadratic,
+            } => 1.0 / (constant + linear * distance + quadratic * distance * distance),
+            Attenuation::InverseSquare { radius } => {
+                let ratio = radius / distance.max(radius);
+                ratio * ratio
+            }
+        }
+    }
+}
+
+/// Light emitted from a single point, giving hard shadows.
+///
+/// ```
+/// use raytracer::{Color, Light, Point, PointLight};
+///
+/// let light: Light = PointLight::new(Point::new(0.0, 10.0, 0.0), Color::new(1.0, 1.0, 1.0)).into();
+/// assert_eq!(light.position(), Some(Point::new(0.0, 10.0, 0.0)));
+/// ```
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub(crate) struct PointLight {
+pub struct PointLight {
     position: Point,
     intensity: Color,
+    attenuation: Attenuation,
 }
 
 impl PointLight {
@@ -11,16 +60,421 @@ impl PointLight {
         PointLight {
             position,
             intensity,
+            attenuation: Attenuation::None,
+        }
+    }
+
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> PointLight {
+        self.attenuation = attenuation;
+        self
+    }
+
+    pub fn attenuation(&self) -> Attenuation {
+        self.attenuation
+    }
+
+    pub fn position(&self) -> Point {
+        self.position
+    }
+
+    pub fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    pub fn lighting(
+        &self,
+        object: &Shape,
+        material: &Material,
+        position: &Point,
+        eye_vector: &Vector,
+        normal_vector: &Vector,
+        visibility: Color,
+    ) -> Color {
+        Light::from(*self).lighting(
+            object,
+            material,
+            position,
+            eye_vector,
+            normal_vector,
+            visibility,
+        )
+    }
+}
+
+// Surface an area light emits from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AreaShape {
+    // parallelogram starting at `corner`, spanned by `uvec` and `vvec`
+    Rect {
+        corner: Point,
+        uvec: Vector,
+        vvec: Vector,
+    },
+    // ellipse around `center`, `uvec` and `vvec` are its radii
+    Disk {
+        center: Point,
+        uvec: Vector,
+        vvec: Vector,
+    },
+    // seen from any point a sphere looks like a disk, only that disk is sampled
+    Sphere {
+        center: Point,
+        radius: f64,
+    },
+}
+
+// Light sampled over a grid of `usteps` by `vsteps` cells, optionally at random inside each cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AreaLight {
+    shape: AreaShape,
+    intensity: Color,
+    usteps: usize,
+    vsteps: usize,
+    jitter: bool,
+    attenuation: Attenuation,
+}
+
+impl AreaLight {
+    pub fn new(shape: AreaShape, intensity: Color, usteps: usize, vsteps: usize) -> AreaLight {
+        AreaLight {
+            shape,
+            intensity,
+            usteps: usteps.max(1),
+            vsteps: vsteps.max(1),
+            jitter: false,
+            attenuation: Attenuation::None,
+        }
+    }
+
+    pub fn rect(corner: Point, uvec: Vector, vvec: Vector, intensity: Color) -> AreaLight {
+        AreaLight::new(AreaShape::Rect { corner, uvec, vvec }, intensity, 1, 1)
+    }
+
+    pub fn disk(center: Point, uvec: Vector, vvec: Vector, intensity: Color) -> AreaLight {
+        AreaLight::new(AreaShape::Disk { center, uvec, vvec }, intensity, 1, 1)
+    }
+
+    pub fn sphere(center: Point, radius: f64, intensity: Color) -> AreaLight {
+        AreaLight::new(AreaShape::Sphere { center, radius }, intensity, 1, 1)
+    }
+
+    pub fn with_steps(mut self, usteps: usize, vsteps: usize) -> AreaLight {
+        self.usteps = usteps.max(1);
+        self.vsteps = vsteps.max(1);
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: bool) -> AreaLight {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> AreaLight {
+        self.attenuation = attenuation;
+        self
+    }
+
+    pub fn attenuation(&self) -> Attenuation {
+        self.attenuation
+    }
+
+    pub fn shape(&self) -> AreaShape {
+        self.shape
+    }
+
+    pub fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    pub fn usteps(&self) -> usize {
+        self.usteps
+    }
+
+    pub fn vsteps(&self) -> usize {
+        self.vsteps
+    }
+
+    pub fn jitter(&self) -> bool {
+        self.jitter
+    }
+
+    pub fn samples(&self) -> usize {
+        self.usteps * self.vsteps
+    }
+
+    pub fn position(&self) -> Point {
+        match self.shape {
+            AreaShape::Rect { corner, uvec, vvec } => corner + uvec / 2.0 + vvec / 2.0,
+            AreaShape::Disk { center, .. } | AreaShape::Sphere { center, .. } => center,
+        }
+    }
+
+    // `u` and `v` in [0, 1) are mapped onto the surface seen from `from`
+    fn point_on_light(&self, u: f64, v: f64, from: &Point) -> Point {
+        let disk = |center: Point, uvec: Vector, vvec: Vector| {
+            let radius = u.sqrt();
+            let angle = 2.0 * std::f64::consts::PI * v;
+            center + uvec * (radius * angle.cos()) + vvec * (radius * angle.sin())
+        };
+        match self.shape {
+            AreaShape::Rect { corner, uvec, vvec } => corner + uvec * u + vvec * v,
+            AreaShape::Disk { center, uvec, vvec } => disk(center, uvec, vvec),
+            AreaShape::Sphere { center, radius } => {
+                let axis = (*from - center).normalize();
+                let helper = match axis.x().abs() < 0.9 {
+                    true => Vector::new(1.0, 0.0, 0.0),
+                    false => Vector::new(0.0, 1.0, 0.0),
+                };
+                let uvec = axis.cross(&helper).normalize() * radius;
+                let vvec = axis.cross(&uvec).normalize() * radius;
+                disk(center, uvec, vvec)
+            }
+        }
+    }
+
+    // one point per cell, jittered points only depend on `from` so renders are repeatable
+    pub fn sample_points(&self, from: &Point) -> Vec<Point> {
+        let mut rng = Rng::at_point(0, from);
+        let mut points = Vec::with_capacity(self.samples());
+        for v in 0..self.vsteps {
+            for u in 0..self.usteps {
+                let (du, dv) = match self.jitter {
+                    true => (rng.next_f64(), rng.next_f64()),
+                    false => (0.5, 0.5),
+                };
+                let u = (u as f64 + du) / self.usteps as f64;
+                let v = (v as f64 + dv) / self.vsteps as f64;
+                points.push(self.point_on_light(u, v, from));
+            }
+        }
+        points
+    }
+}
+
+// Cone of light, full intensity inside `inner_angle`, fading out until `outer_angle`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpotLight {
+    position: Point,
+    direction: Vector,
+    inner_angle: f64,
+    outer_angle: f64,
+    falloff: f64,
+    intensity: Color,
+    attenuation: Attenuation,
+}
+
+impl SpotLight {
+    // angles are measured from `direction` to the side of the cone, in radians
+    pub fn new(
+        position: Point,
+        direction: Vector,
+        inner_angle: f64,
+        outer_angle: f64,
+        intensity: Color,
+    ) -> SpotLight {
+        SpotLight {
+            position,
+            direction: direction.normalize(),
+            inner_angle: inner_angle.min(outer_angle),
+            outer_angle,
+            falloff: 1.0,
+            intensity,
+            attenuation: Attenuation::None,
         }
     }
 
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> SpotLight {
+        self.attenuation = attenuation;
+        self
+    }
+
+    pub fn attenuation(&self) -> Attenuation {
+        self.attenuation
+    }
+
+    // exponent applied to the fade between the two cones, 1 is linear
+    pub fn with_falloff(mut self, falloff: f64) -> SpotLight {
+        self.falloff = falloff;
+        self
+    }
+
     pub fn position(&self) -> Point {
         self.position
     }
 
+    pub fn direction(&self) -> Vector {
+        self.direction
+    }
+
+    pub fn inner_angle(&self) -> f64 {
+        self.inner_angle
+    }
+
+    pub fn outer_angle(&self) -> f64 {
+        self.outer_angle
+    }
+
+    pub fn falloff(&self) -> f64 {
+        self.falloff
+    }
+
+    pub fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    // how much of the light reaches `point`, from 0 outside of the cone to 1 inside the inner one
+    pub fn cone_factor(&self, point: &Point) -> f64 {
+        let cos_angle = (*point - self.position).normalize().dot(&self.direction);
+        let cos_inner = self.inner_angle.cos();
+        let cos_outer = self.outer_angle.cos();
+        if cos_angle >= cos_inner {
+            1.0
+        } else if cos_angle <= cos_outer {
+            0.0
+        } else {
+            ((cos_angle - cos_outer) / (cos_inner - cos_outer)).powf(self.falloff)
+        }
+    }
+}
+
+// Light coming from very far away along `direction`, like the sun.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionalLight {
+    direction: Vector,
+    intensity: Color,
+}
+
+impl DirectionalLight {
+    // `direction` is where the light goes, not where it comes from
+    pub fn new(direction: Vector, intensity: Color) -> DirectionalLight {
+        DirectionalLight {
+            direction: direction.normalize(),
+            intensity,
+        }
+    }
+
+    pub fn direction(&self) -> Vector {
+        self.direction
+    }
+
     pub fn intensity(&self) -> Color {
         self.intensity
     }
+}
+
+// Where a light sample is, seen from the shaded point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct LightSample {
+    // normalized, from the shaded point to the light
+    pub direction: Vector,
+    pub distance: f64,
+}
+
+impl LightSample {
+    fn between(from: &Point, to: &Point) -> LightSample {
+        let v = *to - *from;
+        LightSample {
+            direction: v.normalize(),
+            distance: v.magnitude(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Light {
+    Point(PointLight),
+    Area(AreaLight),
+    Spot(SpotLight),
+    Directional(DirectionalLight),
+}
+
+impl From<PointLight> for Light {
+    fn from(light: PointLight) -> Light {
+        Light::Point(light)
+    }
+}
+
+impl From<AreaLight> for Light {
+    fn from(light: AreaLight) -> Light {
+        Light::Area(light)
+    }
+}
+
+impl From<SpotLight> for Light {
+    fn from(light: SpotLight) -> Light {
+        Light::Spot(light)
+    }
+}
+
+impl From<DirectionalLight> for Light {
+    fn from(light: DirectionalLight) -> Light {
+        Light::Directional(light)
+    }
+}
+
+impl Light {
+    pub fn intensity(&self) -> Color {
+        match self {
+            Light::Point(light) => light.intensity(),
+            Light::Area(light) => light.intensity(),
+            Light::Spot(light) => light.intensity(),
+            Light::Directional(light) => light.intensity(),
+        }
+    }
+
+    // directional lights are infinitely far away, they never fade
+    pub fn attenuation(&self) -> Attenuation {
+        match self {
+            Light::Point(light) => light.attenuation(),
+            Light::Area(light) => light.attenuation(),
+            Light::Spot(light) => light.attenuation(),
+            Light::Directional(_) => Attenuation::None,
+        }
+    }
+
+    pub fn with_attenuation(self, attenuation: Attenuation) -> Light {
+        match self {
+            Light::Point(light) => light.with_attenuation(attenuation).into(),
+            Light::Area(light) => light.with_attenuation(attenuation).into(),
+            Light::Spot(light) => light.with_attenuation(attenuation).into(),
+            Light::Directional(_) => self,
+        }
+    }
+
+    // intensity arriving at `point`, before shadows and attenuation
+    pub fn intensity_at(&self, point: &Point) -> Color {
+        match self {
+            Light::Spot(light) => light.intensity() * light.cone_factor(point),
+            _ => self.intensity(),
+        }
+    }
+
+    // directional lights have no position
+    pub fn position(&self) -> Option<Point> {
+        match self {
+            Light::Point(light) => Some(light.position()),
+            Light::Area(light) => Some(light.position()),
+            Light::Spot(light) => Some(light.position()),
+            Light::Directional(_) => None,
+        }
+    }
+
+    // samples tested for shadows and used for shading, as seen from `from`
+    pub(crate) fn samples(&self, from: &Point) -> Vec<LightSample> {
+        match self {
+            Light::Point(light) => vec![LightSample::between(from, &light.position())],
+            Light::Spot(light) => vec![LightSample::between(from, &light.position())],
+            Light::Area(light) => light
+                .sample_points(from)
+                .iter()
+                .map(|point| LightSample::between(from, point))
+                .collect(),
+            Light::Directional(light) => vec![LightSample {
+                direction: -light.direction(),
+                distance: INFINITY,
+            }],
+        }
+    }
 
     pub fn lighting(
         &self,
@@ -29,16 +483,43 @@ impl PointLight {
         position: &Point,
         eye_vector: &Vector,
         normal_vector: &Vector,
-        shadowed: bool,
+        visibility: Color,
+    ) -> Color {
+        material.lighting(
+            object,
+            self,
+            position,
+            eye_vector,
+            normal_vector,
+            visibility,
+        )
+    }
+
+    // lighting without the ambient term of the material
+    pub fn direct_lighting(
+        &self,
+        object: &Shape,
+        material: &Material,
+        position: &Point,
+        eye_vector: &Vector,
+        normal_vector: &Vector,
+        visibility: Color,
     ) -> Color {
-        material.lighting(object, self, position, eye_vector, normal_vector, shadowed)
+        material.direct_lighting(
+            object,
+            self,
+            position,
+            eye_vector,
+            normal_vector,
+            visibility,
+        )
     }
 }
 
 #[cfg(test)]
 mod test {
 
-    use crate::color;
+    use crate::{color, shapes::ShapeMaterial, util::assert_float_eq};
 
     use super::*;
 
@@ -65,7 +546,7 @@ mod test {
         let normalv = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 0.0, -10.0), color::WHITE);
         let (s, m, position) = shape_material_point_setup();
-        let result = light.lighting(&s, &m, &position, &eyev, &normalv, false);
+        let result = light.lighting(&s, &m, &position, &eyev, &normalv, color::WHITE);
         assert_eq!(result, Color::new(1.9, 1.9, 1.9));
     }
 
@@ -79,7 +560,7 @@ mod test {
         let normalv = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 0.0, -10.0), color::WHITE);
         let (s, m, position) = shape_material_point_setup();
-        let result = light.lighting(&s, &m, &position, &eyev, &normalv, false);
+        let result = light.lighting(&s, &m, &position, &eyev, &normalv, color::WHITE);
         assert_eq!(result, Color::new(1.0, 1.0, 1.0));
     }
 
@@ -89,7 +570,7 @@ mod test {
         let normalv = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 10.0, -10.0), color::WHITE);
         let (s, m, position) = shape_material_point_setup();
-        let result = light.lighting(&s, &m, &position, &eyev, &normalv, false);
+        let result = light.lighting(&s, &m, &position, &eyev, &normalv, color::WHITE);
         assert_eq!(result, Color::new(0.7364, 0.7364, 0.7364));
     }
 
@@ -103,7 +584,7 @@ mod test {
         let normalv = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 10.0, -10.0), color::WHITE);
         let (s, m, position) = shape_material_point_setup();
-        let result = light.lighting(&s, &m, &position, &eyev, &normalv, false);
+        let result = light.lighting(&s, &m, &position, &eyev, &normalv, color::WHITE);
         assert_eq!(result, Color::new(1.6364, 1.6364, 1.6364));
     }
 
@@ -113,7 +594,7 @@ mod test {
         let normalv = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 0.0, 10.0), color::WHITE);
         let (s, m, position) = shape_material_point_setup();
-        let result = light.lighting(&s, &m, &position, &eyev, &normalv, false);
+        let result = light.lighting(&s, &m, &position, &eyev, &normalv, color::WHITE);
         assert_eq!(result, Color::new(0.1, 0.1, 0.1));
     }
 
@@ -122,9 +603,270 @@ mod test {
         let eyev = Vector::new(0.0, 0.0, -1.0);
         let normalv = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 0.0, -10.0), color::WHITE);
-        let shadowed = true;
+        let visibility = color::BLACK;
+        let (s, m, position) = shape_material_point_setup();
+        let result = light.lighting(&s, &m, &position, &eyev, &normalv, visibility);
+        assert_eq!(result, Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn creating_area_light() {
+        let corner = Point::new(0.0, 0.0, 0.0);
+        let light = AreaLight::rect(
+            corner,
+            Vector::new(2.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+            color::WHITE,
+        )
+        .with_steps(4, 2);
+        assert_eq!(light.samples(), 8);
+        assert_eq!(light.position(), Point::new(1.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn finding_points_on_area_light() {
+        let light = AreaLight::rect(
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(2.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+            color::WHITE,
+        )
+        .with_steps(4, 2);
+        let points = light.sample_points(&Point::new(0.0, -5.0, 0.0));
+        assert_eq!(points.len(), 8);
+        assert_eq!(points[0], Point::new(0.25, 0.0, 0.25));
+        assert_eq!(points[1], Point::new(0.75, 0.0, 0.25));
+        assert_eq!(points[4], Point::new(0.25, 0.0, 0.75));
+        assert_eq!(points[7], Point::new(1.75, 0.0, 0.75));
+    }
+
+    #[test]
+    fn jittered_points_stay_in_their_cell() {
+        let light = AreaLight::rect(
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(2.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+            color::WHITE,
+        )
+        .with_steps(4, 2)
+        .with_jitter(true);
+        let from = Point::new(1.0, -5.0, 2.0);
+        let points = light.sample_points(&from);
+        assert_eq!(points, light.sample_points(&from));
+        for (i, p) in points.iter().enumerate() {
+            let (u, v) = ((i % 4) as f64, (i / 4) as f64);
+            assert!(p.x() >= u * 0.5 && p.x() <= (u + 1.0) * 0.5);
+            assert!(p.z() >= v * 0.5 && p.z() <= (v + 1.0) * 0.5);
+        }
+    }
+
+    #[test]
+    fn sphere_light_is_sampled_on_disk_facing_the_point() {
+        let light = AreaLight::sphere(Point::new(0.0, 5.0, 0.0), 1.0, color::WHITE)
+            .with_steps(3, 3)
+            .with_jitter(true);
+        for p in light.sample_points(&Point::new(0.0, 0.0, 0.0)) {
+            assert!((p.y() - 5.0).abs() < 1e-9);
+            assert!((p - Point::new(0.0, 5.0, 0.0)).magnitude() <= 1.0);
+        }
+    }
+
+    #[test]
+    fn lighting_uses_light_visibility() {
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), color::WHITE);
         let (s, m, position) = shape_material_point_setup();
-        let result = light.lighting(&s, &m, &position, &eyev, &normalv, shadowed);
+        let result = light.lighting(
+            &s,
+            &m,
+            &position,
+            &eyev,
+            &normalv,
+            Color::new(0.5, 0.5, 0.5),
+        );
+        assert_eq!(result, Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn lighting_samples_area_light() {
+        let corner = Point::new(-0.5, -0.5, -5.0);
+        let light = Light::from(
+            AreaLight::rect(
+                corner,
+                Vector::new(1.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+                color::WHITE,
+            )
+            .with_steps(2, 2),
+        );
+        let (s, m, _) = shape_material_point_setup();
+        let s = s.with_material(m.with_ambient(0.1).with_diffuse(0.9).with_specular(0.0));
+        let position = Point::new(0.0, 0.0, -1.0);
+        let eyev = (Point::new(0.0, 0.0, -5.0) - position).normalize();
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let result = light.lighting(&s, s.material(), &position, &eyev, &normalv, color::WHITE);
+        // every sample is at the same small angle to the normal
+        let cos = 4.0 / f64::sqrt(16.0 + 0.125);
+        let expected = 0.1 + 0.9 * cos;
+        assert_eq!(result, Color::new(expected, expected, expected));
+    }
+
+    #[test]
+    fn spot_light_cone() {
+        let light = SpotLight::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+            std::f64::consts::FRAC_PI_6,
+            std::f64::consts::FRAC_PI_3,
+            color::WHITE,
+        );
+        let at_angle = |angle: f64| Point::new(angle.sin(), 0.0, angle.cos());
+        assert_eq!(light.cone_factor(&Point::new(0.0, 0.0, 5.0)), 1.0);
+        assert_eq!(light.cone_factor(&at_angle(0.5)), 1.0);
+        assert_eq!(light.cone_factor(&at_angle(1.1)), 0.0);
+        assert_eq!(light.cone_factor(&Point::new(0.0, 0.0, -5.0)), 0.0);
+
+        let middle = light.cone_factor(&at_angle(std::f64::consts::FRAC_PI_4));
+        assert!(middle > 0.0 && middle < 1.0);
+        let sharper = light.with_falloff(2.0);
+        assert_float_eq!(
+            sharper.cone_factor(&at_angle(std::f64::consts::FRAC_PI_4)),
+            middle * middle
+        );
+    }
+
+    #[test]
+    fn lighting_outside_of_spot_light_cone() {
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let (s, m, position) = shape_material_point_setup();
+        let light = Light::from(SpotLight::new(
+            Point::new(0.0, 0.0, -10.0),
+            Vector::new(0.0, 1.0, 0.0),
+            0.1,
+            0.2,
+            color::WHITE,
+        ));
+        let result = light.lighting(&s, &m, &position, &eyev, &normalv, color::WHITE);
         assert_eq!(result, Color::new(0.1, 0.1, 0.1));
+
+        let light = Light::from(SpotLight::new(
+            Point::new(0.0, 0.0, -10.0),
+            Vector::new(0.0, 0.0, 1.0),
+            0.1,
+            0.2,
+            color::WHITE,
+        ));
+        let result = light.lighting(&s, &m, &position, &eyev, &normalv, color::WHITE);
+        assert_eq!(result, Color::new(1.9, 1.9, 1.9));
+    }
+
+    #[test]
+    fn directional_light_comes_from_infinity() {
+        let light = Light::from(DirectionalLight::new(
+            Vector::new(0.0, -2.0, 0.0),
+            color::WHITE,
+        ));
+        assert_eq!(light.position(), None);
+        let samples = light.samples(&Point::new(3.0, 4.0, 5.0));
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].direction, Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(samples[0].distance, INFINITY);
+    }
+
+    #[test]
+    fn lighting_with_directional_light() {
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let (s, m, position) = shape_material_point_setup();
+        let light = Light::from(DirectionalLight::new(
+            Vector::new(0.0, 0.0, 1.0),
+            color::WHITE,
+        ));
+        let result = light.lighting(&s, &m, &position, &eyev, &normalv, color::WHITE);
+        assert_eq!(result, Color::new(1.9, 1.9, 1.9));
+    }
+
+    #[test]
+    fn attenuation_factors() {
+        assert_float_eq!(Attenuation::None.factor(100.0), 1.0);
+
+        let polynomial = Attenuation::Polynomial {
+            constant: 1.0,
+            linear: 0.5,
+            quadratic: 0.25,
+        };
+        assert_float_eq!(polynomial.factor(0.0), 1.0);
+        assert_float_eq!(polynomial.factor(2.0), 1.0 / 3.0);
+
+        let bright = Attenuation::Polynomial {
+            constant: 0.5,
+            linear: 0.0,
+            quadratic: 0.0,
+        };
+        assert_float_eq!(bright.factor(1.0), 2.0);
+
+        let inverse_square = Attenuation::InverseSquare { radius: 2.0 };
+        assert_float_eq!(inverse_square.factor(1.0), 1.0);
+        assert_float_eq!(inverse_square.factor(2.0), 1.0);
+        assert_float_eq!(inverse_square.factor(4.0), 0.25);
+        assert_float_eq!(inverse_square.factor(8.0), 0.0625);
+    }
+
+    #[test]
+    fn lighting_with_attenuated_light() {
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let (s, m, position) = shape_material_point_setup();
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), color::WHITE)
+            .with_attenuation(Attenuation::InverseSquare { radius: 5.0 });
+        let result = light.lighting(&s, &m, &position, &eyev, &normalv, color::WHITE);
+        // ambient is not attenuated, diffuse and specular are divided by 4
+        assert_eq!(
+            result,
+            Color::new(0.1 + 1.8 / 4.0, 0.1 + 1.8 / 4.0, 0.1 + 1.8 / 4.0)
+        );
+    }
+
+    #[test]
+    fn nearer_surfaces_receive_more_light() {
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let (s, m, _) = shape_material_point_setup();
+        let attenuation = Attenuation::Polynomial {
+            constant: 1.0,
+            linear: 0.1,
+            quadratic: 0.01,
+        };
+        let light = Light::from(PointLight::new(Point::new(0.0, 0.0, -10.0), color::WHITE))
+            .with_attenuation(attenuation);
+        let near = light.lighting(
+            &s,
+            &m,
+            &Point::new(0.0, 0.0, -8.0),
+            &eyev,
+            &normalv,
+            color::WHITE,
+        );
+        let far = light.lighting(
+            &s,
+            &m,
+            &Point::new(0.0, 0.0, 0.0),
+            &eyev,
+            &normalv,
+            color::WHITE,
+        );
+        assert!(near.r() > far.r());
+    }
+
+    #[test]
+    fn directional_light_is_never_attenuated() {
+        let light = Light::from(DirectionalLight::new(
+            Vector::new(0.0, 0.0, 1.0),
+            color::WHITE,
+        ))
+        .with_attenuation(Attenuation::InverseSquare { radius: 1.0 });
+        assert_eq!(light.attenuation(), Attenuation::None);
     }
 }
@@ -1,19 +1,106 @@
+use std::{error::Error, process::ExitCode};
+
 use clap::Parser;
-use raytracer::render_image;
+use raytracer::{
+    render_image, Filter, Gamma, ImageFormat, Integrator, RenderOptions, SamplingStrategy,
+    ToneOperator,
+};
 
 #[derive(Parser)]
 struct Cli {
     yaml: std::path::PathBuf,
     image_output: std::path::PathBuf,
+
+    /// Samples per pixel, overrides the camera setting
+    #[arg(long)]
+    samples: Option<usize>,
+
+    /// Sample placement: regular, jittered or random
+    #[arg(long)]
+    sampling: Option<SamplingStrategy>,
+
+    /// Reconstruction filter: box, tent or gaussian
+    #[arg(long)]
+    filter: Option<Filter>,
+
+    /// Seed of the random sample positions
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Color contrast above which a pixel is refined, enables adaptive antialiasing
+    #[arg(long)]
+    adaptive_threshold: Option<f64>,
+
+    /// Maximum number of times a pixel is split, enables adaptive antialiasing
+    #[arg(long)]
+    adaptive_depth: Option<usize>,
+
+    /// Write an image of the number of samples per pixel
+    #[arg(long)]
+    sample_map: Option<std::path::PathBuf>,
+
+    /// Output format: png, jpeg, bmp, tga, ppm, qoi, exr, hdr or pfm, guessed from the extension by default
+    #[arg(long)]
+    format: Option<ImageFormat>,
+
+    /// Tone mapping: clamp, reinhard, extended-reinhard, aces or exposure
+    #[arg(long)]
+    tone_mapping: Option<ToneOperator>,
+
+    /// Exposure in stops applied before tone mapping
+    #[arg(long, allow_hyphen_values = true)]
+    exposure: Option<f64>,
+
+    /// Transfer function of the output: linear or srgb
+    #[arg(long)]
+    gamma: Option<Gamma>,
+
+    /// Rendering algorithm: whitted or path
+    #[arg(long)]
+    integrator: Option<Integrator>,
+
+    /// Maximum number of bounces of the path tracer, only with `--integrator path`
+    #[arg(long)]
+    bounces: Option<usize>,
 }
 
 impl Cli {
-    fn run(&self) {
-        render_image(&self.yaml, &self.image_output);
+    fn run(&self) -> Result<(), Box<dyn Error>> {
+        let options = RenderOptions {
+            samples: self.samples,
+            sampling: self.sampling,
+            filter: self.filter,
+            seed: self.seed,
+            adaptive_threshold: self.adaptive_threshold,
+            adaptive_depth: self.adaptive_depth,
+            sample_map: self.sample_map.clone(),
+            format: self.format,
+            tone_mapping: self.tone_mapping,
+            exposure: self.exposure,
+            gamma: self.gamma,
+            integrator: self.integrator,
+            bounces: self.bounces,
+        };
+        let camera = render_image(&self.yaml, &self.image_output, &options)?;
+        let sampler = camera.sampler();
+        if camera.adaptive().is_none() && sampler.samples() != sampler.requested_samples() {
+            eprintln!(
+                "note: {} samples per pixel requested, rounded up to a square grid of {}",
+                sampler.requested_samples(),
+                sampler.samples()
+            );
+        }
+        Ok(())
     }
 }
 
-fn main() {
+fn main() -> ExitCode {
     let cli = Cli::parse();
-    cli.run();
+    match cli.run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}: {}", cli.yaml.display(), e);
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -1,21 +1,51 @@
 use crate::{
     color::{self, Color},
-    light::PointLight,
+    dispersion::Dispersion,
+    light::Light,
+    microfacet::Microfacet,
     patterns::{pattern::Pattern, PatternWorld},
     phong::PhongReflecionModel,
     point::Point,
+    sampling::uniform_cone,
     shapes::shape::Shape,
     vector::Vector,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub(crate) struct Material {
+/// Surface properties of a shape, Phong shading by default or physically
+/// based with a [`Microfacet`] model.
+///
+/// ```
+/// use raytracer::{Color, Material};
+///
+/// let glass = Material::default()
+///     .with_color(Color::new(0.1, 0.1, 0.1))
+///     .with_transparency(0.9)
+///     .with_refractive_index(1.5);
+/// assert_eq!(glass.refractive_index(), 1.5);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
     color: Color,
     model: PhongReflecionModel,
     pattern: Option<Pattern>,
     reflective: f64,
     transparency: f64,
     refractive_index: f64,
+    casts_shadow: bool,
+    // light given off by the surface itself
+    emissive: Color,
+    // replaces the phong diffuse and specular terms when set
+    microfacet: Option<Microfacet>,
+    // spread of the reflected and refracted rays, 0 for a mirror and clear
+    // glass, unrelated to the roughness of the microfacet model
+    glossy_spread: f64,
+    // rays averaged by glossy reflection and refraction
+    glossy_samples: usize,
+    // light absorbed per unit of distance traveled inside the material
+    absorption: Color,
+    density: f64,
+    // refracts every color channel with its own index when set
+    dispersion: Option<Dispersion>,
 }
 
 impl Default for Material {
@@ -27,6 +57,14 @@ impl Default for Material {
             reflective: 0.0,
             transparency: 0.0,
             refractive_index: 1.0,
+            casts_shadow: true,
+            emissive: color::BLACK,
+            microfacet: None,
+            glossy_spread: 0.0,
+            glossy_samples: 8,
+            absorption: color::BLACK,
+            density: 1.0,
+            dispersion: None,
         }
     }
 }
@@ -114,56 +152,207 @@ impl Material {
         self
     }
 
+    // objects which do not cast shadows are ignored by shadow rays
+    pub fn casts_shadow(&self) -> bool {
+        self.casts_shadow
+    }
+
+    pub fn with_casts_shadow(mut self, casts_shadow: bool) -> Material {
+        self.casts_shadow = casts_shadow;
+        self
+    }
+
+    pub fn emissive(&self) -> Color {
+        self.emissive
+    }
+
+    // bounded shapes also light the world, unbounded ones only glow unless path traced
+    pub fn with_emissive(mut self, emissive: Color) -> Material {
+        self.emissive = emissive;
+        self
+    }
+
+    pub fn microfacet(&self) -> Option<Microfacet> {
+        self.microfacet
+    }
+
+    pub fn with_microfacet(mut self, microfacet: Microfacet) -> Material {
+        self.microfacet = Some(microfacet);
+        self
+    }
+
+    pub fn glossy_spread(&self) -> f64 {
+        self.glossy_spread
+    }
+
+    pub fn with_glossy_spread(mut self, glossy_spread: f64) -> Material {
+        self.glossy_spread = glossy_spread.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn glossy_samples(&self) -> usize {
+        self.glossy_samples
+    }
+
+    pub fn with_glossy_samples(mut self, glossy_samples: usize) -> Material {
+        self.glossy_samples = glossy_samples.max(1);
+        self
+    }
+
+    pub fn absorption(&self) -> Color {
+        self.absorption
+    }
+
+    pub fn with_absorption(mut self, absorption: Color) -> Material {
+        self.absorption = absorption;
+        self
+    }
+
+    pub fn density(&self) -> f64 {
+        self.density
+    }
+
+    pub fn with_density(mut self, density: f64) -> Material {
+        self.density = density.max(0.0);
+        self
+    }
+
+    pub fn dispersion(&self) -> Option<Dispersion> {
+        self.dispersion
+    }
+
+    pub fn with_dispersion(mut self, dispersion: Dispersion) -> Material {
+        self.dispersion = Some(dispersion);
+        self
+    }
+
+    // beer-lambert coefficients, the part of the light left after a distance d
+    // is e^(-coefficient * d) for every channel
+    pub(crate) fn absorption_coefficient(&self) -> Color {
+        self.absorption * self.density
+    }
+
+    pub(crate) fn is_glossy(&self) -> bool {
+        self.glossy_spread > 0.0
+    }
+
+    // `direction` perturbed inside a cone widening with the spread, up to a
+    // hemisphere for a spread of 1
+    pub(crate) fn glossy_direction(&self, direction: &Vector, u1: f64, u2: f64) -> Vector {
+        let cos_max = (self.glossy_spread * std::f64::consts::FRAC_PI_2).cos();
+        uniform_cone(direction, cos_max, u1, u2)
+    }
+
+    // color of the surface at `position`, from the pattern if there is one
+    pub(crate) fn color_at(&self, object: &Shape, position: &Point) -> Color {
+        match &self.pattern {
+            Some(pattern) => pattern.pattern_at_shape(object, position),
+            None => self.color,
+        }
+    }
+
+    // light going through the material, tinted by its color, black for opaque materials
+    pub fn transmittance(&self) -> Color {
+        self.color * self.transparency
+    }
+
+    // `visibility` is the light reaching `position` after shadows, black in full shadow
     pub fn lighting(
         &self,
         object: &Shape,
-        light: &PointLight,
+        light: &Light,
         position: &Point,
         eye_vector: &Vector,
         normal_vector: &Vector,
-        shadowed: bool,
+        visibility: Color,
     ) -> Color {
-        let color = match self.pattern {
-            Some(pattern) => pattern.pattern_at_shape(object, position),
-            None => self.color,
-        };
+        let color = self.color_at(object, position);
+        let ambient = (color & light.intensity()) * self.model.ambient();
+        ambient
+            + self.shade(
+                color,
+                light,
+                position,
+                eye_vector,
+                normal_vector,
+                visibility,
+            )
+    }
 
-        let effective_color = color & light.intensity();
-        let light_vector = (light.position() - *position).normalize();
-        let ambient = effective_color * self.model.ambient();
+    // diffuse and specular light only, for integrators that trace the
+    // indirect light the ambient term stands in for
+    pub fn direct_lighting(
+        &self,
+        object: &Shape,
+        light: &Light,
+        position: &Point,
+        eye_vector: &Vector,
+        normal_vector: &Vector,
+        visibility: Color,
+    ) -> Color {
+        let color = self.color_at(object, position);
+        self.shade(
+            color,
+            light,
+            position,
+            eye_vector,
+            normal_vector,
+            visibility,
+        )
+    }
 
-        if shadowed {
-            return ambient;
+    fn shade(
+        &self,
+        color: Color,
+        light: &Light,
+        position: &Point,
+        eye_vector: &Vector,
+        normal_vector: &Vector,
+        visibility: Color,
+    ) -> Color {
+        if visibility == color::BLACK {
+            return color::BLACK;
         }
 
-        let light_dot_normal = light_vector.dot(normal_vector);
+        let intensity = light.intensity_at(position);
+        let effective_color = color & intensity;
+        let samples = light.samples(position);
+        let attenuation = light.attenuation();
+        let sum = samples.iter().fold(color::BLACK, |total, sample| {
+            let light_vector = sample.direction;
+            let factor = attenuation.factor(sample.distance);
+            if let Some(microfacet) = &self.microfacet {
+                let reflectance =
+                    microfacet.reflectance(color, normal_vector, eye_vector, &light_vector);
+                return total + (reflectance & intensity) * factor;
+            }
+            let light_dot_normal = light_vector.dot(normal_vector);
+            if light_dot_normal < 0.0 {
+                return total;
+            }
 
-        let (diffuse, specular) = match light_dot_normal < 0.0 {
-            true => (color::BLACK, color::BLACK),
-            false => {
-                let diffuse = effective_color * self.model.diffuse() * light_dot_normal;
-                let reflected_vector = -light_vector.reflect(normal_vector);
-                let reflected_dot_eye = reflected_vector.dot(eye_vector);
+            let diffuse = effective_color * self.model.diffuse() * light_dot_normal;
+            let reflected_vector = -light_vector.reflect(normal_vector);
+            let reflected_dot_eye = reflected_vector.dot(eye_vector);
 
-                let specular = match reflected_dot_eye <= 0.0 {
-                    true => color::BLACK,
-                    false => {
-                        let factor = reflected_dot_eye.powf(self.model.shininess());
-                        light.intensity() * self.model.specular() * factor
-                    }
-                };
+            let specular = match reflected_dot_eye <= 0.0 {
+                true => color::BLACK,
+                false => {
+                    let factor = reflected_dot_eye.powf(self.model.shininess());
+                    intensity * self.model.specular() * factor
+                }
+            };
 
-                (diffuse, specular)
-            }
-        };
+            total + (diffuse + specular) * factor
+        });
 
-        ambient + diffuse + specular
+        (sum / samples.len() as f64) & visibility
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::util::assert_float_eq;
+    use crate::{light::PointLight, util::assert_float_eq};
 
     use super::*;
 
@@ -176,12 +365,13 @@ mod test {
         assert_float_eq!(m.reflective, 0.0);
         assert_float_eq!(m.transparency, 0.0);
         assert_float_eq!(m.refractive_index, 1.0);
+        assert_eq!(m.emissive(), color::BLACK);
     }
 
     #[test]
     fn assigning_pattern() {
         let p = Pattern::stripe(color::WHITE, color::BLACK);
-        let m = Material::default().with_pattern(p);
+        let m = Material::default().with_pattern(p.clone());
         assert_eq!(m.pattern, Some(p));
     }
 
@@ -199,14 +389,14 @@ mod test {
 
         let eyev = Vector::new(0.0, 0.0, -1.0);
         let normalv = Vector::new(0.0, 0.0, -1.0);
-        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), color::WHITE);
+        let light = Light::from(PointLight::new(Point::new(0.0, 0.0, -10.0), color::WHITE));
         let c1 = m.lighting(
             &s,
             &light,
             &Point::new(0.9, 0.0, 0.0),
             &eyev,
             &normalv,
-            false,
+            color::WHITE,
         );
         let c2 = m.lighting(
             &s,
@@ -214,9 +404,70 @@ mod test {
             &Point::new(1.1, 0.0, 0.0),
             &eyev,
             &normalv,
-            false,
+            color::WHITE,
         );
         assert_eq!(c1, color::WHITE);
         assert_eq!(c2, color::BLACK);
     }
+
+    #[test]
+    fn direct_lighting_leaves_out_the_ambient_term() {
+        let s = Shape::dummy();
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let light = Light::from(PointLight::new(Point::new(0.0, 0.0, -10.0), color::WHITE));
+        let position = Point::new(0.0, 0.0, 0.0);
+        let m = Material::default();
+        let full = m.lighting(&s, &light, &position, &eyev, &normalv, color::WHITE);
+        let direct = m.direct_lighting(&s, &light, &position, &eyev, &normalv, color::WHITE);
+        assert_eq!(full - direct, Color::new(0.1, 0.1, 0.1));
+        let shadowed = m.direct_lighting(&s, &light, &position, &eyev, &normalv, color::BLACK);
+        assert_eq!(shadowed, color::BLACK);
+    }
+
+    #[test]
+    fn transmittance_of_materials() {
+        assert_eq!(Material::default().transmittance(), color::BLACK);
+        let glass = Material::default()
+            .with_color(Color::new(1.0, 0.5, 0.0))
+            .with_transparency(0.8);
+        assert_eq!(glass.transmittance(), Color::new(0.8, 0.4, 0.0));
+        assert!(Material::default().casts_shadow());
+    }
+
+    #[test]
+    fn lighting_with_a_microfacet_model() {
+        let s = Shape::dummy();
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let light = Light::from(PointLight::new(Point::new(0.0, 0.0, -10.0), color::WHITE));
+        let position = Point::new(0.0, 0.0, 0.0);
+        let m = Material::default()
+            .with_ambient(0.0)
+            .with_color(Color::new(1.0, 0.0, 0.0))
+            .with_microfacet(Microfacet::new(0.0, 1.0));
+        let c = m.lighting(&s, &light, &position, &eyev, &normalv, color::WHITE);
+        // the diffuse part keeps the base color, the specular part is white
+        assert!(c.r() > c.g());
+        assert!(c.g() > 0.0);
+        assert_float_eq!(c.g(), c.b());
+
+        let in_shadow = m.lighting(&s, &light, &position, &eyev, &normalv, color::BLACK);
+        assert_eq!(in_shadow, color::BLACK);
+    }
+
+    #[test]
+    fn glossy_directions_widen_with_the_spread() {
+        let direction = Vector::new(0.0, 1.0, 0.0);
+        let smooth = Material::default();
+        assert!(!smooth.is_glossy());
+        assert_eq!(smooth.glossy_direction(&direction, 0.5, 0.5), direction);
+
+        let spread = |glossy_spread: f64| {
+            let m = Material::default().with_glossy_spread(glossy_spread);
+            m.glossy_direction(&direction, 0.9, 0.3).dot(&direction)
+        };
+        assert!(spread(0.1) > spread(0.5));
+        assert!(spread(1.0) >= -1e-9);
+    }
 }
@@ -0,0 +1,228 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::{self, Color},
+    sampling::around_normal,
+    vector::Vector,
+};
+
+// reflectance at normal incidence of common dielectrics
+const DIELECTRIC_F0: f64 = 0.04;
+// below this the distribution is a perfect mirror for all practical purposes
+const MIN_ALPHA: f64 = 1e-4;
+
+/// Physically based Cook-Torrance reflection, with a GGX distribution of the
+/// microfacet normals, Smith shadowing and Schlick fresnel. The base color is
+/// the color of the material.
+///
+/// ```
+/// use raytracer::{Color, Material, Microfacet};
+///
+/// let gold = Material::default()
+///     .with_color(Color::new(1.0, 0.78, 0.34))
+///     .with_microfacet(Microfacet::new(1.0, 0.3));
+/// assert_eq!(gold.microfacet().map(|m| m.metallic()), Some(1.0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Microfacet {
+    // 0 for dielectrics, 1 for metals tinting their reflections with the base color
+    metallic: f64,
+    // 0 for a mirror, 1 for a fully rough surface
+    roughness: f64,
+}
+
+impl Default for Microfacet {
+    fn default() -> Microfacet {
+        Microfacet::new(0.0, 0.5)
+    }
+}
+
+impl Microfacet {
+    pub fn new(metallic: f64, roughness: f64) -> Microfacet {
+        Microfacet {
+            metallic: metallic.clamp(0.0, 1.0),
+            roughness: roughness.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn metallic(&self) -> f64 {
+        self.metallic
+    }
+
+    pub fn roughness(&self) -> f64 {
+        self.roughness
+    }
+
+    // the usual perceptual remapping of the roughness
+    fn alpha(&self) -> f64 {
+        (self.roughness * self.roughness).max(MIN_ALPHA)
+    }
+
+    pub(crate) fn is_smooth(&self) -> bool {
+        self.alpha() <= MIN_ALPHA
+    }
+
+    // reflectance at normal incidence
+    pub(crate) fn f0(&self, base_color: Color) -> Color {
+        let dielectric = Color::new(DIELECTRIC_F0, DIELECTRIC_F0, DIELECTRIC_F0);
+        dielectric * (1.0 - self.metallic) + base_color * self.metallic
+    }
+
+    pub(crate) fn fresnel(&self, base_color: Color, cos: f64) -> Color {
+        let f0 = self.f0(base_color);
+        f0 + (color::WHITE - f0) * (1.0 - cos.clamp(0.0, 1.0)).powi(5)
+    }
+
+    // density of microfacets oriented along the half vector
+    pub(crate) fn distribution(&self, n_dot_h: f64) -> f64 {
+        let a2 = self.alpha() * self.alpha();
+        let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+        a2 / (PI * d * d)
+    }
+
+    // part of the microfacets seen from both the eye and the light
+    pub(crate) fn geometry(&self, n_dot_v: f64, n_dot_l: f64) -> f64 {
+        let a2 = self.alpha() * self.alpha();
+        let g1 = |cos: f64| 2.0 * cos / (cos + (a2 + (1.0 - a2) * cos * cos).sqrt());
+        g1(n_dot_v) * g1(n_dot_l)
+    }
+
+    // light reflected toward the eye for a unit light from `light_vector`,
+    // including the cosine term, scaled by pi like the phong model
+    pub(crate) fn reflectance(
+        &self,
+        base_color: Color,
+        normal: &Vector,
+        eye: &Vector,
+        light_vector: &Vector,
+    ) -> Color {
+        let n_dot_l = normal.dot(light_vector);
+        let n_dot_v = normal.dot(eye);
+        if n_dot_l <= 0.0 || n_dot_v <= 0.0 {
+            return color::BLACK;
+        }
+        let half = (*eye + *light_vector).normalize();
+        let n_dot_h = normal.dot(&half).max(0.0);
+        let v_dot_h = eye.dot(&half).max(0.0);
+
+        let fresnel = self.fresnel(base_color, v_dot_h);
+        let specular = fresnel
+            * (self.distribution(n_dot_h) * self.geometry(n_dot_v, n_dot_l)
+                / (4.0 * n_dot_l * n_dot_v));
+        let diffuse = ((color::WHITE - fresnel) * (1.0 - self.metallic)) & base_color;
+        (diffuse + specular * PI) * n_dot_l
+    }
+
+    // half vector around `normal` distributed like the microfacet normals
+    pub(crate) fn sample_half_vector(&self, normal: &Vector, u1: f64, u2: f64) -> Vector {
+        let a2 = self.alpha() * self.alpha();
+        let cos_theta = ((1.0 - u1) / (1.0 + (a2 - 1.0) * u1)).sqrt();
+        around_normal(normal, cos_theta, 2.0 * PI * u2)
+    }
+
+    // weight of a reflection along a sampled half vector, the brdf times the
+    // cosine divided by the density of the sample
+    pub(crate) fn sample_weight(
+        &self,
+        base_color: Color,
+        normal: &Vector,
+        eye: &Vector,
+        half: &Vector,
+        direction: &Vector,
+    ) -> Color {
+        let n_dot_l = normal.dot(direction);
+        let n_dot_v = normal.dot(eye);
+        let n_dot_h = normal.dot(half);
+        let v_dot_h = eye.dot(half);
+        if n_dot_l <= 0.0 || n_dot_v <= 0.0 || n_dot_h <= 0.0 || v_dot_h <= 0.0 {
+            return color::BLACK;
+        }
+        let g = self.geometry(n_dot_v, n_dot_l);
+        self.fresnel(base_color, v_dot_h) * (g * v_dot_h / (n_dot_v * n_dot_h))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{rng::Rng, util::assert_float_eq};
+
+    use super::*;
+
+    #[test]
+    fn fresnel_of_dielectrics_and_metals() {
+        let base = Color::new(1.0, 0.5, 0.0);
+        let plastic = Microfacet::new(0.0, 0.5);
+        assert_eq!(plastic.fresnel(base, 1.0), Color::new(0.04, 0.04, 0.04));
+        assert_eq!(plastic.fresnel(base, 0.0), color::WHITE);
+        let metal = Microfacet::new(1.0, 0.5);
+        assert_eq!(metal.fresnel(base, 1.0), base);
+    }
+
+    #[test]
+    fn ggx_distribution_is_normalized() {
+        // the projected area of the microfacets is the area of the surface
+        let model = Microfacet::new(0.0, 0.6);
+        let n = 20000;
+        let step = 1.0 / n as f64;
+        let total: f64 = (0..n)
+            .map(|i| {
+                let cos = (i as f64 + 0.5) * step;
+                model.distribution(cos) * cos * 2.0 * PI * step
+            })
+            .sum();
+        assert!((total - 1.0).abs() < 1e-3, "{}", total);
+    }
+
+    #[test]
+    fn smith_geometry_terms() {
+        let model = Microfacet::new(0.0, 0.5);
+        assert_float_eq!(model.geometry(1.0, 1.0), 1.0);
+        assert!(model.geometry(0.1, 1.0) < model.geometry(0.5, 1.0));
+        // rougher surfaces shadow more
+        let rough = Microfacet::new(0.0, 1.0);
+        assert!(rough.geometry(0.3, 0.3) < model.geometry(0.3, 0.3));
+    }
+
+    #[test]
+    fn reflectance_peaks_in_the_mirror_direction() {
+        let model = Microfacet::new(1.0, 0.2);
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        let eye = Vector::new(0.0, 1.0, -1.0).normalize();
+        let mirror = Vector::new(0.0, 1.0, 1.0).normalize();
+        let aside = Vector::new(1.0, 1.0, 0.0).normalize();
+        let peak = model.reflectance(color::WHITE, &normal, &eye, &mirror);
+        let off = model.reflectance(color::WHITE, &normal, &eye, &aside);
+        assert!(peak.r() > 10.0 * off.r());
+        let below = Vector::new(0.0, -1.0, 1.0).normalize();
+        assert_eq!(
+            model.reflectance(color::WHITE, &normal, &eye, &below),
+            color::BLACK
+        );
+    }
+
+    #[test]
+    fn metals_have_no_diffuse_reflection() {
+        let metal = Microfacet::new(1.0, 1.0);
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        let eye = Vector::new(0.0, 1.0, 0.0);
+        let light = Vector::new(1.0, 1.0, 0.0).normalize();
+        let reflected = metal.reflectance(Color::new(1.0, 0.0, 0.0), &normal, &eye, &light);
+        assert_float_eq!(reflected.g(), 0.0);
+        assert!(reflected.r() > 0.0);
+    }
+
+    #[test]
+    fn sampled_half_vectors_are_around_the_normal() {
+        let model = Microfacet::new(0.0, 0.3);
+        let normal = Vector::new(0.0, 0.0, 1.0);
+        let mut rng = Rng::new(3);
+        for _ in 0..100 {
+            let half = model.sample_half_vector(&normal, rng.next_f64(), rng.next_f64());
+            assert_float_eq!(half.magnitude(), 1.0);
+            assert!(half.dot(&normal) > 0.0);
+        }
+        let mirror = Microfacet::new(0.0, 0.0);
+        let half = mirror.sample_half_vector(&normal, 0.7, 0.2);
+        assert!(half.dot(&normal) > 0.9999);
+    }
+}
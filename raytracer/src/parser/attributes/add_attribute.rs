@@ -1,24 +1,36 @@
+use std::path::Path;
+
 use serde_yaml::Value;
 
-use crate::parser::{objects::object::Object, yaml::DefineAttributes};
+use crate::parser::{
+    objects::{object::Object, ParseResult},
+    yaml::DefineAttributes,
+};
 
-use super::util::substitute;
+use super::util::{find_undefined_reference, resolve_files, substitute};
 
 #[derive(Debug, Clone)]
 pub(crate) struct AddAttribute {
     value: Value,
     attribute_type: String,
+    // position of the object in the yaml file
+    index: usize,
 }
 
 #[allow(dead_code)]
 impl AddAttribute {
-    pub fn new(value: Value, attribute_type: String) -> AddAttribute {
+    pub fn new(value: Value, attribute_type: String, index: usize) -> AddAttribute {
         AddAttribute {
             value,
             attribute_type,
+            index,
         }
     }
 
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
     pub fn value(&self) -> Value {
         self.value.clone()
     }
@@ -31,18 +43,47 @@ impl AddAttribute {
         &self.attribute_type
     }
 
-    pub fn parse(&self) -> Result<Object, serde_yaml::Error> {
+    pub fn parse(&self) -> ParseResult<Object> {
         Object::from_attribute(self)
     }
 
     fn is_shape(&self) -> bool {
         matches!(
             self.attribute_type.as_str(),
-            "sphere" | "plane" | "cube" | "cylinder" | "cone"
+            "sphere" | "plane" | "cube" | "cylinder" | "cone" | "obj" | "csg" | "group"
         )
     }
 
     pub fn substitute(&mut self, attributes: &DefineAttributes) -> bool {
         substitute(&mut self.value, attributes)
     }
+
+    pub fn resolve_files(&mut self, directory: &Path) {
+        resolve_files(&mut self.value, directory)
+    }
+
+    pub fn check_references(&self) -> ParseResult<()> {
+        match find_undefined_reference(&self.value) {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    // `add: {define_id}` of a defined shape (a whole group for example) becomes a group
+    // holding the definition, so its own `transform` and `material` apply on top of it
+    pub fn instantiate(&mut self, attributes: &DefineAttributes) -> bool {
+        let defined = match attributes
+            .get(&self.attribute_type)
+            .and_then(|attr| attr.value())
+        {
+            Some(defined) if defined.get("add").is_some() => defined.clone(),
+            _ => return false,
+        };
+        let mut mapping = self.value.as_mapping().cloned().unwrap_or_default();
+        mapping.insert("add".into(), "group".into());
+        mapping.insert("children".into(), Value::Sequence(vec![defined]));
+        self.value = Value::Mapping(mapping);
+        self.attribute_type = "group".to_string();
+        true
+    }
 }
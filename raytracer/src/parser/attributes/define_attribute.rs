@@ -1,18 +1,24 @@
 use serde_yaml::Value;
 
-use crate::parser::yaml::DefineAttributes;
+use crate::parser::{objects::ParseResult, yaml::DefineAttributes};
 
-use super::util::substitute;
+use super::util::{collect_references, find_undefined_reference, substitute};
 
 #[derive(Debug, Clone)]
 pub(crate) struct DefineAttribute {
     value: Value,
+    // position of the definition in the yaml file
+    index: usize,
 }
 
 #[allow(dead_code)]
 impl DefineAttribute {
-    pub fn new(value: Value) -> DefineAttribute {
-        DefineAttribute { value }
+    pub fn new(value: Value, index: usize) -> DefineAttribute {
+        DefineAttribute { value, index }
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
     }
 
     pub fn value(&self) -> Option<&Value> {
@@ -26,7 +32,7 @@ impl DefineAttribute {
     pub fn extensible(&self) -> bool {
         self.value
             .as_mapping()
-            .map_or(false, |mapping| mapping.contains_key("extend"))
+            .is_some_and(|mapping| mapping.contains_key("extend"))
     }
 
     pub fn extend_value(&self) -> Option<&str> {
@@ -52,4 +58,20 @@ impl DefineAttribute {
     pub fn substitute(&mut self, attributes: &DefineAttributes) -> bool {
         substitute(&mut self.value, attributes)
     }
+
+    // names of other definitions used by this one
+    pub fn references(&self, attributes: &DefineAttributes) -> Vec<String> {
+        let mut references = Vec::new();
+        if let Some(value) = self.value() {
+            collect_references(value, attributes, &mut references);
+        }
+        references
+    }
+
+    pub fn check_references(&self) -> ParseResult<()> {
+        match self.value().and_then(find_undefined_reference) {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
 }
@@ -1,6 +1,21 @@
+use std::path::Path;
+
 use serde_yaml::Value;
 
-use crate::parser::yaml::DefineAttributes;
+use crate::parser::{
+    error::{ParseError, ParseErrorKind},
+    yaml::DefineAttributes,
+};
+
+// keys holding a mapping or a list which could be given by the name of a definition
+const REFERENCE_KEYS: [&str; 6] = [
+    "material",
+    "pattern",
+    "transform",
+    "children",
+    "left",
+    "right",
+];
 
 fn get_value_inside_attributes(value: &mut Value, attributes: &DefineAttributes) -> Option<Value> {
     let s = value.as_str()?;
@@ -48,3 +63,61 @@ pub(crate) fn substitute(value: &mut Value, attributes: &DefineAttributes) -> bo
     };
     success
 }
+
+pub(crate) fn collect_references(
+    value: &Value,
+    attributes: &DefineAttributes,
+    references: &mut Vec<String>,
+) {
+    match value {
+        Value::String(s) if attributes.contains_key(s) => references.push(s.clone()),
+        Value::Mapping(m) => m
+            .iter()
+            .filter(|(k, _)| k.as_str() != Some("define"))
+            .for_each(|(_, v)| collect_references(v, attributes, references)),
+        Value::Sequence(seq) => seq
+            .iter()
+            .for_each(|v| collect_references(v, attributes, references)),
+        _ => (),
+    }
+}
+
+// once every definition is substituted, a name left where a mapping or a list is expected
+// does not refer to any definition
+pub(crate) fn find_undefined_reference(value: &Value) -> Option<ParseError> {
+    let undefined = |key: &str, name: &str| {
+        ParseError::new(ParseErrorKind::UndefinedReference(name.to_string())).with_key(key)
+    };
+    match value {
+        Value::Mapping(m) => m.iter().find_map(|(k, v)| {
+            let key = k.as_str().unwrap_or_default();
+            let referable = REFERENCE_KEYS.contains(&key);
+            match v {
+                Value::String(name) if referable => Some(undefined(key, name)),
+                Value::Sequence(seq) if referable => seq
+                    .iter()
+                    .find_map(|v| v.as_str().map(|name| undefined(key, name)))
+                    .or_else(|| find_undefined_reference(v)),
+                _ => find_undefined_reference(v),
+            }
+        }),
+        Value::Sequence(seq) => seq.iter().find_map(find_undefined_reference),
+        _ => None,
+    }
+}
+
+// relative `file` paths of obj shapes are resolved against `directory`, the one of the scene
+pub(crate) fn resolve_files(value: &mut Value, directory: &Path) {
+    match value {
+        Value::Mapping(m) => {
+            if m.get("add").and_then(Value::as_str) == Some("obj") {
+                if let Some(Value::String(file)) = m.get_mut("file") {
+                    *file = directory.join(&*file).to_string_lossy().into_owned();
+                }
+            }
+            m.iter_mut().for_each(|(_, v)| resolve_files(v, directory));
+        }
+        Value::Sequence(seq) => seq.iter_mut().for_each(|v| resolve_files(v, directory)),
+        _ => (),
+    }
+}
@@ -0,0 +1,287 @@
+use std::{error::Error, fmt::Display};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    line: usize,
+    column: usize,
+}
+
+impl Location {
+    // `line` and `column` start at 1
+    pub fn new(line: usize, column: usize) -> Location {
+        Location { line, column }
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    Yaml(String),
+    Io(String),
+    InvalidDocument(String),
+    InvalidValue(String),
+    MissingField(String),
+    UnknownObject(String),
+    UnknownTransform(String),
+    UnknownPattern(String),
+    UnknownOperation(String),
+    UndefinedReference(String),
+    CyclicReference(Vec<String>),
+    MissingCamera,
+    Obj(String),
+}
+
+impl ParseErrorKind {
+    // text of the yaml file this error is about, used to find where it happens
+    fn offending_text(&self) -> Option<&str> {
+        match self {
+            ParseErrorKind::MissingField(s)
+            | ParseErrorKind::UnknownObject(s)
+            | ParseErrorKind::UnknownTransform(s)
+            | ParseErrorKind::UnknownPattern(s)
+            | ParseErrorKind::UnknownOperation(s)
+            | ParseErrorKind::UndefinedReference(s) => Some(s),
+            ParseErrorKind::CyclicReference(chain) => chain.last().map(String::as_str),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseErrorKind::Yaml(message)
+            | ParseErrorKind::Io(message)
+            | ParseErrorKind::InvalidDocument(message)
+            | ParseErrorKind::InvalidValue(message)
+            | ParseErrorKind::Obj(message) => write!(f, "{}", message),
+            ParseErrorKind::MissingField(s) => write!(f, "missing field `{}`", s),
+            ParseErrorKind::UnknownObject(s) => write!(f, "unknown object type `{}`", s),
+            ParseErrorKind::UnknownTransform(s) => write!(f, "unknown transform `{}`", s),
+            ParseErrorKind::UnknownPattern(s) => write!(f, "unknown pattern type `{}`", s),
+            ParseErrorKind::UnknownOperation(s) => write!(f, "unknown operation `{}`", s),
+            ParseErrorKind::UndefinedReference(s) => write!(f, "`{}` is not defined", s),
+            ParseErrorKind::CyclicReference(chain) => {
+                write!(f, "cyclic reference `{}`", chain.join(" -> "))
+            }
+            ParseErrorKind::MissingCamera => write!(f, "scene does not contain a camera"),
+        }
+    }
+}
+
+// Error of the whole scene pipeline, from reading yaml to building objects.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    kind: ParseErrorKind,
+    key: Option<String>,
+    location: Option<Location>,
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            kind,
+            key: None,
+            location: None,
+        }
+    }
+
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    pub fn location(&self) -> Option<Location> {
+        self.location
+    }
+
+    // the innermost key is the most precise one, it is never overwritten
+    pub fn with_key(mut self, key: &str) -> ParseError {
+        self.key.get_or_insert_with(|| key.to_string());
+        self
+    }
+
+    pub fn with_location(mut self, location: Option<Location>) -> ParseError {
+        if self.location.is_none() {
+            self.location = location;
+        }
+        self
+    }
+
+    pub(crate) fn locate(self, source: &SourceMap, item: usize) -> ParseError {
+        let needles = [self.kind.offending_text(), self.key()];
+        let location = needles
+            .into_iter()
+            .flatten()
+            .find_map(|needle| source.find(item, needle))
+            .or_else(|| source.item_location(item));
+        self.with_location(location)
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(location) = self.location {
+            write!(f, "{}: ", location)?;
+        }
+        write!(f, "{}", self.kind)?;
+        if let Some(key) = &self.key {
+            write!(f, " (key `{}`)", key)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ParseError {}
+
+impl From<serde_yaml::Error> for ParseError {
+    fn from(error: serde_yaml::Error) -> Self {
+        let location = error
+            .location()
+            .map(|l| Location::new(l.line(), l.column()));
+        let mut message = error.to_string();
+        if let Some(location) = location {
+            let suffix = format!(" at line {} column {}", location.line, location.column);
+            message = message.replacen(&suffix, "", 1);
+        }
+        ParseError::new(ParseErrorKind::Yaml(message)).with_location(location)
+    }
+}
+
+// `serde_yaml::Value` does not remember where it comes from,
+// so the top level items are found again in the yaml text.
+#[derive(Debug, Default)]
+pub(crate) struct SourceMap {
+    lines: Vec<String>,
+    // first line and column of every top level item, then the line after it
+    items: Vec<(usize, usize, usize)>,
+}
+
+impl SourceMap {
+    pub fn new(yaml: &str) -> SourceMap {
+        let lines: Vec<String> = yaml.lines().map(str::to_string).collect();
+        let mut starts = Vec::new();
+        let mut indent = None;
+        for (index, line) in lines.iter().enumerate() {
+            let content = line.trim_start();
+            if content.is_empty() || content.starts_with('#') || content.starts_with("---") {
+                continue;
+            }
+            let column = line.len() - content.len();
+            let is_item = content == "-" || content.starts_with("- ");
+            match indent {
+                None if is_item => indent = Some(column),
+                Some(indent) if is_item && column == indent => (),
+                _ => continue,
+            }
+            starts.push((index, column));
+        }
+        let items = starts
+            .iter()
+            .enumerate()
+            .map(|(i, &(line, column))| {
+                let end = starts.get(i + 1).map_or(lines.len(), |&(next, _)| next);
+                (line, column, end)
+            })
+            .collect();
+        SourceMap { lines, items }
+    }
+
+    pub fn item_location(&self, item: usize) -> Option<Location> {
+        let &(line, column, _) = self.items.get(item)?;
+        Some(Location::new(line + 1, column + 1))
+    }
+
+    // first occurrence of `needle` as a whole word inside an item
+    pub fn find(&self, item: usize, needle: &str) -> Option<Location> {
+        let &(start, _, end) = self.items.get(item)?;
+        let is_word = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+        (start..end).find_map(|index| {
+            let line = &self.lines[index];
+            line.match_indices(needle).find_map(|(column, _)| {
+                let before = line[..column].chars().next_back();
+                let after = line[column + needle.len()..].chars().next();
+                match before.is_some_and(is_word) || after.is_some_and(is_word) {
+                    true => None,
+                    false => Some(Location::new(index + 1, column + 1)),
+                }
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    const YAML: &str = "
+# comment
+- add: camera
+  width: 10
+
+- define: material
+  value:
+    - [rotate-x, 1]
+-   add: sphere
+    material: red-material
+";
+
+    #[test]
+    fn finding_top_level_items() {
+        let source = SourceMap::new(YAML);
+        assert_eq!(source.item_location(0), Some(Location::new(3, 1)));
+        assert_eq!(source.item_location(1), Some(Location::new(6, 1)));
+        assert_eq!(source.item_location(2), Some(Location::new(9, 1)));
+        assert_eq!(source.item_location(3), None);
+    }
+
+    #[test]
+    fn finding_text_inside_an_item() {
+        let source = SourceMap::new(YAML);
+        assert_eq!(source.find(1, "rotate-x"), Some(Location::new(8, 8)));
+        assert_eq!(source.find(2, "material"), Some(Location::new(10, 5)));
+        assert_eq!(source.find(2, "red"), None);
+        assert_eq!(source.find(0, "rotate-x"), None);
+    }
+
+    #[test]
+    fn locating_error_prefers_offending_text() {
+        let source = SourceMap::new(YAML);
+        let error = ParseError::new(ParseErrorKind::UndefinedReference(
+            "red-material".to_string(),
+        ))
+        .with_key("material")
+        .locate(&source, 2);
+        assert_eq!(error.location(), Some(Location::new(10, 15)));
+        assert_eq!(
+            error.to_string(),
+            "line 10, column 15: `red-material` is not defined (key `material`)"
+        );
+    }
+
+    #[test]
+    fn error_from_yaml_keeps_its_location() {
+        let error: ParseError = serde_yaml::from_str::<Vec<f64>>("[1, 2, a]")
+            .unwrap_err()
+            .into();
+        assert_eq!(error.location(), Some(Location::new(1, 8)));
+        assert!(!error.to_string().contains(" at line"));
+    }
+}
@@ -1,7 +1,11 @@
 pub(crate) mod yaml;
 
+pub(crate) mod error;
+
 pub(crate) mod objects;
 
 pub(crate) mod render;
 
 pub(crate) mod attributes;
+
+pub(crate) mod obj;
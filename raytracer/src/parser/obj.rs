@@ -0,0 +1,470 @@
+use std::fs;
+
+use crate::{
+    point::Point,
+    shapes::shape::Shape,
+    transform::{Transform, Transformable},
+    vector::Vector,
+};
+
+use super::{
+    error::{ParseError, ParseErrorKind},
+    objects::ParseResult,
+};
+
+// Wavefront OBJ file, only vertices, normals, faces and groups are read. Texture coordinates
+// are checked so that faces referring to them stay valid, but triangles do not keep them, meshes
+// are textured through the mappings of patterns. Every other statement is ignored.
+#[derive(Debug, Default)]
+pub(crate) struct ObjParser {
+    vertices: Vec<Point>,
+    normals: Vec<Vector>,
+    texture_coordinates: usize,
+    default_group: Vec<Shape>,
+    groups: Vec<(String, Vec<Shape>)>,
+    ignored: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FaceVertex {
+    vertex: usize,
+    normal: Option<usize>,
+}
+
+fn obj_error(line_number: usize, message: &str) -> ParseError {
+    let message = format!("obj line {}: {}", line_number, message);
+    ParseError::new(ParseErrorKind::Obj(message))
+}
+
+pub(crate) fn from_str(obj_str: &str) -> ParseResult<ObjParser> {
+    let mut parser = ObjParser::default();
+    for (index, line) in obj_str.lines().enumerate() {
+        parser.parse_line(index + 1, line)?;
+    }
+    Ok(parser)
+}
+
+pub(crate) fn from_file(file_name: &std::path::Path) -> ParseResult<ObjParser> {
+    let obj_str = fs::read_to_string(file_name).map_err(|e| {
+        let message = format!("could not read `{}`: {}", file_name.display(), e);
+        ParseError::new(ParseErrorKind::Io(message))
+    })?;
+    from_str(&obj_str)
+}
+
+impl ObjParser {
+    #[cfg(test)]
+    pub fn vertices(&self) -> &Vec<Point> {
+        &self.vertices
+    }
+
+    #[cfg(test)]
+    pub fn normals(&self) -> &Vec<Vector> {
+        &self.normals
+    }
+
+    #[cfg(test)]
+    pub fn texture_coordinates(&self) -> usize {
+        self.texture_coordinates
+    }
+
+    #[cfg(test)]
+    pub fn ignored(&self) -> usize {
+        self.ignored
+    }
+
+    #[cfg(test)]
+    pub fn default_group(&self) -> &Vec<Shape> {
+        &self.default_group
+    }
+
+    #[cfg(test)]
+    pub fn group(&self, name: &str) -> Option<&Vec<Shape>> {
+        self.groups
+            .iter()
+            .find(|(group_name, _)| group_name == name)
+            .map(|(_, shapes)| shapes)
+    }
+
+    // triangles of the default group are added directly,
+    // every named group becomes a nested group
+    pub fn to_group(&self, transform: Transform) -> Shape {
+        let mut group = Shape::group().with_transform(transform);
+        for shape in &self.default_group {
+            group.add_shape(shape.clone());
+        }
+        for (_, shapes) in &self.groups {
+            let mut child = Shape::group();
+            for shape in shapes {
+                child.add_shape(shape.clone());
+            }
+            group.add_shape(child);
+        }
+        group
+    }
+
+    fn current_group(&mut self) -> &mut Vec<Shape> {
+        match self.groups.last_mut() {
+            Some((_, shapes)) => shapes,
+            None => &mut self.default_group,
+        }
+    }
+
+    fn select_group(&mut self, name: &str) {
+        match self.groups.iter().position(|(n, _)| n == name) {
+            // move the existing group to the end so that it becomes the current group
+            Some(index) => {
+                let group = self.groups.remove(index);
+                self.groups.push(group);
+            }
+            None => self.groups.push((name.to_string(), Vec::new())),
+        }
+    }
+
+    fn parse_line(&mut self, line_number: usize, line: &str) -> ParseResult<()> {
+        let mut tokens = line.split_whitespace();
+        let Some(command) = tokens.next() else {
+            return Ok(());
+        };
+        let arguments: Vec<&str> = tokens.collect();
+
+        match command {
+            "v" => {
+                let [x, y, z] = parse_numbers(line_number, &arguments)?;
+                self.vertices.push(Point::new(x, y, z));
+            }
+            "vn" => {
+                let [x, y, z] = parse_numbers(line_number, &arguments)?;
+                self.normals.push(Vector::new(x, y, z));
+            }
+            "vt" => {
+                // `v` and `w` are optional
+                let [_u] = parse_numbers(line_number, &arguments)?;
+                for argument in arguments.iter().skip(1) {
+                    parse_number(line_number, argument)?;
+                }
+                self.texture_coordinates += 1;
+            }
+            "f" => {
+                let face = arguments
+                    .iter()
+                    .map(|argument| self.parse_face_vertex(line_number, argument))
+                    .collect::<ParseResult<Vec<_>>>()?;
+                if face.len() < 3 {
+                    return Err(obj_error(line_number, "face needs at least 3 vertices"));
+                }
+                let triangles = self.fan_triangulation(&face);
+                self.current_group().extend(triangles);
+            }
+            "g" | "o" => {
+                let name = arguments.join(" ");
+                self.select_group(&name);
+            }
+            _ => self.ignored += 1,
+        }
+        Ok(())
+    }
+
+    // face vertex is one of `v`, `v/vt`, `v//vn` or `v/vt/vn`, indices start at 1,
+    // negative indices refer to the end of the list
+    fn parse_face_vertex(&self, line_number: usize, argument: &str) -> ParseResult<FaceVertex> {
+        let mut indices = argument.split('/');
+
+        let vertex = indices.next().unwrap_or_default();
+        let vertex = resolve_index(line_number, vertex, self.vertices.len(), "vertex")?;
+
+        if let Some(texture) = indices.next().filter(|s| !s.is_empty()) {
+            let count = self.texture_coordinates;
+            resolve_index(line_number, texture, count, "texture coordinate")?;
+        }
+
+        let normal = match indices.next().filter(|s| !s.is_empty()) {
+            Some(normal) => Some(resolve_index(
+                line_number,
+                normal,
+                self.normals.len(),
+                "normal",
+            )?),
+            None => None,
+        };
+
+        Ok(FaceVertex { vertex, normal })
+    }
+
+    fn fan_triangulation(&self, face: &[FaceVertex]) -> Vec<Shape> {
+        let smooth = face.iter().all(|v| v.normal.is_some());
+        (1..face.len() - 1)
+            .map(|index| {
+                let (a, b, c) = (face[0], face[index], face[index + 1]);
+                let (p1, p2, p3) = (
+                    self.vertices[a.vertex],
+                    self.vertices[b.vertex],
+                    self.vertices[c.vertex],
+                );
+                match (smooth, a.normal, b.normal, c.normal) {
+                    (true, Some(n1), Some(n2), Some(n3)) => Shape::smooth_triangle(
+                        p1,
+                        p2,
+                        p3,
+                        self.normals[n1],
+                        self.normals[n2],
+                        self.normals[n3],
+                    ),
+                    _ => Shape::triangle(p1, p2, p3),
+                }
+            })
+            .collect()
+    }
+}
+
+fn parse_number(line_number: usize, s: &str) -> ParseResult<f64> {
+    s.parse()
+        .map_err(|_| obj_error(line_number, &format!("`{}` is not a number", s)))
+}
+
+fn parse_numbers<const N: usize>(line_number: usize, arguments: &[&str]) -> ParseResult<[f64; N]> {
+    if arguments.len() < N {
+        return Err(obj_error(
+            line_number,
+            &format!("expected {} numbers, found {}", N, arguments.len()),
+        ));
+    }
+    let mut numbers = [0.0; N];
+    for (number, argument) in numbers.iter_mut().zip(arguments) {
+        *number = parse_number(line_number, argument)?;
+    }
+    Ok(numbers)
+}
+
+fn resolve_index(line_number: usize, s: &str, count: usize, kind: &str) -> ParseResult<usize> {
+    let index: i64 = s
+        .parse()
+        .map_err(|_| obj_error(line_number, &format!("`{}` is not a {} index", s, kind)))?;
+    let resolved = match index {
+        i if i > 0 => i - 1,
+        i if i < 0 => count as i64 + i,
+        _ => -1,
+    };
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(obj_error(
+            line_number,
+            &format!("{} index `{}` is out of range", kind, s),
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::{shapes::ShapeWorld, util::assert_float_eq};
+
+    use super::*;
+
+    #[test]
+    fn ignoring_unrecognized_lines() -> ParseResult<()> {
+        let obj = "
+There was a young lady named Bright
+who traveled much faster than light.
+She set out one day
+in a relative way,
+and came back the previous night.
+";
+        let parser = from_str(obj)?;
+        assert_eq!(parser.ignored(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn vertex_records() -> ParseResult<()> {
+        let obj = "
+v -1 1 0
+v -1.0000 0.5000 0.0000
+v 1 0 0
+v 1 1 0
+";
+        let parser = from_str(obj)?;
+        assert_eq!(parser.vertices()[0], Point::new(-1.0, 1.0, 0.0));
+        assert_eq!(parser.vertices()[1], Point::new(-1.0, 0.5, 0.0));
+        assert_eq!(parser.vertices()[2], Point::new(1.0, 0.0, 0.0));
+        assert_eq!(parser.vertices()[3], Point::new(1.0, 1.0, 0.0));
+        Ok(())
+    }
+
+    #[test]
+    fn parsing_triangle_faces() -> ParseResult<()> {
+        let obj = "
+v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+f 1 2 3
+f 1 3 4
+";
+        let parser = from_str(obj)?;
+        let g = parser.default_group();
+        let t1 = g[0].as_triangle().unwrap();
+        let t2 = g[1].as_triangle().unwrap();
+        assert_eq!(t1.p1(), parser.vertices()[0]);
+        assert_eq!(t1.p2(), parser.vertices()[1]);
+        assert_eq!(t1.p3(), parser.vertices()[2]);
+        assert_eq!(t2.p1(), parser.vertices()[0]);
+        assert_eq!(t2.p2(), parser.vertices()[2]);
+        assert_eq!(t2.p3(), parser.vertices()[3]);
+        Ok(())
+    }
+
+    #[test]
+    fn triangulating_polygons() -> ParseResult<()> {
+        let obj = "
+v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+v 0 2 0
+
+f 1 2 3 4 5
+";
+        let parser = from_str(obj)?;
+        let g = parser.default_group();
+        assert_eq!(g.len(), 3);
+        let t1 = g[0].as_triangle().unwrap();
+        let t2 = g[1].as_triangle().unwrap();
+        let t3 = g[2].as_triangle().unwrap();
+        assert_eq!(t1.p1(), parser.vertices()[0]);
+        assert_eq!(t1.p2(), parser.vertices()[1]);
+        assert_eq!(t1.p3(), parser.vertices()[2]);
+        assert_eq!(t2.p1(), parser.vertices()[0]);
+        assert_eq!(t2.p2(), parser.vertices()[2]);
+        assert_eq!(t2.p3(), parser.vertices()[3]);
+        assert_eq!(t3.p1(), parser.vertices()[0]);
+        assert_eq!(t3.p2(), parser.vertices()[3]);
+        assert_eq!(t3.p3(), parser.vertices()[4]);
+        Ok(())
+    }
+
+    #[test]
+    fn triangles_in_groups() -> ParseResult<()> {
+        let obj = "
+v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+g FirstGroup
+f 1 2 3
+o SecondGroup
+f 1 3 4
+";
+        let parser = from_str(obj)?;
+        let g1 = parser.group("FirstGroup").unwrap();
+        let g2 = parser.group("SecondGroup").unwrap();
+        let t1 = g1[0].as_triangle().unwrap();
+        let t2 = g2[0].as_triangle().unwrap();
+        assert_eq!(t1.p3(), parser.vertices()[2]);
+        assert_eq!(t2.p3(), parser.vertices()[3]);
+        Ok(())
+    }
+
+    #[test]
+    fn converting_obj_file_to_group() -> ParseResult<()> {
+        let obj = "
+v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+f 1 2 4
+g FirstGroup
+f 1 2 3
+g SecondGroup
+f 1 3 4
+";
+        let parser = from_str(obj)?;
+        let g = parser.to_group(Transform::identity());
+        let children = g.as_group().unwrap();
+        assert_eq!(children.len(), 3);
+        assert!(children[0].as_triangle().is_some());
+        assert!(children[1].is_group());
+        assert!(children[2].is_group());
+        assert_eq!(children[1].as_group().unwrap().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn vertex_normal_and_texture_records() -> ParseResult<()> {
+        let obj = "
+vn 0 0 1
+vn 0.707 0 -0.707
+vn 1 2 3
+vt 0.5 0.25
+vt 0.75
+";
+        let parser = from_str(obj)?;
+        assert_eq!(parser.normals()[0], Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(parser.normals()[1], Vector::new(0.707, 0.0, -0.707));
+        assert_eq!(parser.normals()[2], Vector::new(1.0, 2.0, 3.0));
+        assert_eq!(parser.texture_coordinates(), 2);
+        assert!(from_str("vt 0.5 up").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn faces_with_normals() -> ParseResult<()> {
+        let obj = "
+v 0 1 0
+v -1 0 0
+v 1 0 0
+
+vn -1 0 0
+vn 1 0 0
+vn 0 1 0
+
+vt 0 0
+
+f 1//3 2//1 3//2
+f 1/1/3 2/1/1 3/1/2
+f -3//-1 -2//-3 -1//-2
+";
+        let parser = from_str(obj)?;
+        let g = parser.default_group();
+        let expected = g[0].as_smooth_triangle().unwrap();
+        assert_eq!(expected.triangle().p1(), parser.vertices()[0]);
+        assert_eq!(expected.n1(), parser.normals()[2]);
+        assert_eq!(expected.n2(), parser.normals()[0]);
+        assert_eq!(expected.n3(), parser.normals()[1]);
+        assert_eq!(g[1].as_smooth_triangle(), Some(expected));
+        assert_eq!(g[2].as_smooth_triangle(), Some(expected));
+        Ok(())
+    }
+
+    #[test]
+    fn face_with_invalid_index_is_an_error() {
+        let obj = "
+v 0 1 0
+v -1 0 0
+f 1 2 3
+";
+        let error = from_str(obj).unwrap_err();
+        assert!(error.to_string().contains("obj line 4"));
+    }
+
+    #[test]
+    fn group_from_obj_can_be_intersected() -> ParseResult<()> {
+        let obj = "
+v -1 1 0
+v -1 -1 0
+v 1 -1 0
+v 1 1 0
+f 1 2 3 4
+";
+        let mut g = from_str(obj)?.to_group(Transform::translation(0.0, 0.0, 1.0));
+        g.build_bvh();
+        let r = crate::ray::Ray::new(Point::new(0.5, 0.5, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = g.intersect(&r);
+        assert_eq!(xs.count(), 1);
+        assert_float_eq!(xs.get(0).unwrap().t(), 3.0);
+        Ok(())
+    }
+}
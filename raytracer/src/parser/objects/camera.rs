@@ -2,12 +2,16 @@ use serde::Deserialize;
 
 use crate::{
     camera::Camera,
+    integrator::Integrator,
+    parser::error::{ParseError, ParseErrorKind},
+    sampling::{Adaptive, Sampler},
+    tonemap::ToneMapping,
     transform::{Transform, Transformable},
 };
 
-use super::{point::PointParser, vector::VectorParser, ObjectParser};
+use super::{point::PointParser, vector::VectorParser, ObjectParser, ParseResult};
 
-#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub(crate) struct CameraParser {
     width: usize,
     height: usize,
@@ -18,15 +22,128 @@ pub(crate) struct CameraParser {
     from: PointParser,
     to: PointParser,
     up: VectorParser,
+
+    // antialiasing, one sample through the pixel center when missing
+    samples: Option<usize>,
+    sampling: Option<String>,
+    filter: Option<String>,
+    seed: Option<u64>,
+
+    adaptive: Option<AdaptiveParser>,
+
+    #[serde(rename(deserialize = "tone-mapping"))]
+    tone_mapping: Option<ToneMappingParser>,
+
+    // whitted when missing, `bounces` limits the paths of the path tracer
+    integrator: Option<String>,
+    bounces: Option<usize>,
+}
+
+fn default_threshold() -> f64 {
+    Adaptive::default().threshold()
+}
+
+fn default_max_depth() -> usize {
+    Adaptive::default().max_depth()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub(crate) struct AdaptiveParser {
+    #[serde(default = "default_threshold")]
+    threshold: f64,
+
+    #[serde(rename(deserialize = "max-depth"), default = "default_max_depth")]
+    max_depth: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct ToneMappingParser {
+    operator: Option<String>,
+    exposure: Option<f64>,
+    white: Option<f64>,
+    gamma: Option<String>,
+}
+
+impl ToneMappingParser {
+    fn parse(&self) -> ParseResult<ToneMapping> {
+        let mut tone_mapping = ToneMapping::default();
+        if let Some(operator) = parse_name(&self.operator, "operator")? {
+            tone_mapping = tone_mapping.with_operator(operator);
+        }
+        if let Some(exposure) = self.exposure {
+            tone_mapping = tone_mapping.with_exposure(exposure);
+        }
+        if let Some(white) = self.white {
+            tone_mapping = tone_mapping.with_white(white);
+        }
+        if let Some(gamma) = parse_name(&self.gamma, "gamma")? {
+            tone_mapping = tone_mapping.with_gamma(gamma);
+        }
+        Ok(tone_mapping)
+    }
+}
+
+fn parse_name<T: std::str::FromStr<Err = String>>(
+    name: &Option<String>,
+    key: &str,
+) -> ParseResult<Option<T>> {
+    name.as_deref()
+        .map(|name| {
+            name.parse()
+                .map_err(|e| ParseError::new(ParseErrorKind::InvalidValue(e)).with_key(key))
+        })
+        .transpose()
+}
+
+impl CameraParser {
+    fn sampler(&self) -> ParseResult<Sampler> {
+        let mut sampler = Sampler::default();
+        if let Some(samples) = self.samples {
+            sampler = sampler.with_samples(samples);
+        }
+        if let Some(strategy) = parse_name(&self.sampling, "sampling")? {
+            sampler = sampler.with_strategy(strategy);
+        }
+        if let Some(filter) = parse_name(&self.filter, "filter")? {
+            sampler = sampler.with_filter(filter);
+        }
+        if let Some(seed) = self.seed {
+            sampler = sampler.with_seed(seed);
+        }
+        Ok(sampler)
+    }
+
+    fn integrator(&self) -> ParseResult<Integrator> {
+        let integrator: Integrator =
+            parse_name(&self.integrator, "integrator")?.unwrap_or_default();
+        match self.bounces {
+            Some(bounces) => integrator
+                .with_max_depth(bounces)
+                .map_err(|e| ParseError::new(ParseErrorKind::InvalidValue(e)).with_key("bounces")),
+            None => Ok(integrator),
+        }
+    }
 }
 
 impl ObjectParser<Camera> for CameraParser {
-    fn parse(&self) -> Camera {
-        let from = self.from.parse();
-        let to = self.to.parse();
-        let up = self.up.parse();
+    fn parse(&self) -> ParseResult<Camera> {
+        let from = self.from.parse()?;
+        let to = self.to.parse()?;
+        let up = self.up.parse()?;
         let view_transform = Transform::view_transform(from, to, up);
-        Camera::new(self.width, self.height, self.field_of_view).with_transform(view_transform)
+        let tone_mapping = match &self.tone_mapping {
+            Some(tone_mapping) => tone_mapping.parse()?,
+            None => ToneMapping::default(),
+        };
+        Ok(Camera::new(self.width, self.height, self.field_of_view)
+            .with_transform(view_transform)
+            .with_sampler(self.sampler()?)
+            .with_adaptive(
+                self.adaptive
+                    .map(|adaptive| Adaptive::new(adaptive.threshold, adaptive.max_depth)),
+            )
+            .with_tone_mapping(tone_mapping)
+            .with_integrator(self.integrator()?))
     }
 }
 
@@ -36,8 +153,11 @@ mod test {
     use serde_yaml::Value;
 
     use crate::{
+        integrator::PathTracer,
         parser::{objects::ParseResult, yaml::Parser},
         point::Point,
+        sampling::{Filter, SamplingStrategy},
+        tonemap::{Gamma, ToneOperator},
         vector::Vector,
     };
 
@@ -64,14 +184,23 @@ mod test {
             from: PointParser::new(1.0, 2.0, 3.0),
             to: PointParser::new(4.0, 5.0, 6.0),
             up: VectorParser::new(7.0, 8.0, 9.0),
+            samples: None,
+            sampling: None,
+            filter: None,
+            seed: None,
+            adaptive: None,
+            tone_mapping: None,
+            integrator: None,
+            bounces: None,
         }
     }
 
     #[test]
-    fn parse_to_camera() {
+    fn parse_to_camera() -> ParseResult<()> {
         let camera = default_camera();
         let parser = default_parser();
-        assert_eq!(parser.parse(), camera);
+        assert_eq!(parser.parse()?, camera);
+        Ok(())
     }
 
     #[test]
@@ -108,4 +237,113 @@ mod test {
         assert_eq!(camera, default_camera());
         Ok(())
     }
+
+    #[test]
+    fn parse_antialiasing_settings() -> ParseResult<()> {
+        let yaml = "
+  width: 10
+  height: 20
+  field-of-view: 1.25
+  from: [ 1, 2, 3 ]
+  to: [ 4, 5, 6 ]
+  up: [ 7, 8, 9 ]
+  samples: 16
+  sampling: jittered
+  filter: tent
+  seed: 42";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let camera = CameraParser::from_value(value)?;
+        let sampler = Sampler::new(16, SamplingStrategy::Jittered, Filter::Tent, 42);
+        assert_eq!(camera, default_camera().with_sampler(sampler));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_unknown_filter() -> ParseResult<()> {
+        let mut parser = default_parser();
+        parser.filter = Some("lanczos".to_string());
+        let error = parser.parse().unwrap_err();
+        assert_eq!(error.key(), Some("filter"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_adaptive_settings() -> ParseResult<()> {
+        let yaml = "
+  width: 10
+  height: 20
+  field-of-view: 1.25
+  from: [ 1, 2, 3 ]
+  to: [ 4, 5, 6 ]
+  up: [ 7, 8, 9 ]
+  adaptive:
+    max-depth: 3";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let camera = CameraParser::from_value(value)?;
+        let adaptive = Adaptive::default().with_max_depth(3);
+        assert_eq!(camera, default_camera().with_adaptive(Some(adaptive)));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_tone_mapping_settings() -> ParseResult<()> {
+        let yaml = "
+  width: 10
+  height: 20
+  field-of-view: 1.25
+  from: [ 1, 2, 3 ]
+  to: [ 4, 5, 6 ]
+  up: [ 7, 8, 9 ]
+  tone-mapping:
+    operator: aces
+    exposure: 1.5
+    gamma: srgb";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let camera = CameraParser::from_value(value)?;
+        let tone_mapping = ToneMapping::new(ToneOperator::Aces, 1.5).with_gamma(Gamma::Srgb);
+        assert_eq!(camera, default_camera().with_tone_mapping(tone_mapping));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_unknown_tone_operator() -> ParseResult<()> {
+        let mut parser = default_parser();
+        parser.tone_mapping = Some(ToneMappingParser {
+            operator: Some("filmic".to_string()),
+            exposure: None,
+            white: None,
+            gamma: None,
+        });
+        let error = parser.parse().unwrap_err();
+        assert_eq!(error.key(), Some("operator"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_integrator_settings() -> ParseResult<()> {
+        let yaml = "
+  width: 10
+  height: 20
+  field-of-view: 1.25
+  from: [ 1, 2, 3 ]
+  to: [ 4, 5, 6 ]
+  up: [ 7, 8, 9 ]
+  integrator: path
+  bounces: 4";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let camera = CameraParser::from_value(value)?;
+        let integrator = Integrator::PathTracing(PathTracer::new(4));
+        assert_eq!(camera, default_camera().with_integrator(integrator));
+
+        let mut parser = default_parser();
+        parser.integrator = Some("radiosity".to_string());
+        let error = parser.parse().unwrap_err();
+        assert_eq!(error.key(), Some("integrator"));
+
+        let mut parser = default_parser();
+        parser.bounces = Some(4);
+        let error = parser.parse().unwrap_err();
+        assert_eq!(error.key(), Some("bounces"));
+        Ok(())
+    }
 }
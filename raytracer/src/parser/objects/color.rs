@@ -2,7 +2,7 @@ use serde::Deserialize;
 
 use crate::color::Color;
 
-use super::ObjectParser;
+use super::{ObjectParser, ParseResult};
 
 #[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
 pub(crate) struct ColorParser(f64, f64, f64);
@@ -15,8 +15,8 @@ impl ColorParser {
 }
 
 impl ObjectParser<Color> for ColorParser {
-    fn parse(&self) -> Color {
-        Color::new(self.0, self.1, self.2)
+    fn parse(&self) -> ParseResult<Color> {
+        Ok(Color::new(self.0, self.1, self.2))
     }
 }
 
@@ -45,10 +45,11 @@ mod test {
     }
 
     #[test]
-    fn parse_to_color() {
+    fn parse_to_color() -> ParseResult<()> {
         let color = default_color();
         let parser = default_parser();
-        assert_eq!(parser.parse(), color);
+        assert_eq!(parser.parse()?, color);
+        Ok(())
     }
 
     #[test]
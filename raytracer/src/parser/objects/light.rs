@@ -1,20 +1,240 @@
 use serde::Deserialize;
 
-use crate::light::PointLight;
+use crate::{
+    light::{AreaLight, Attenuation, DirectionalLight, Light, PointLight, SpotLight},
+    parser::error::{ParseError, ParseErrorKind},
+};
 
-use super::{color::ColorParser, point::PointParser, ObjectParser};
+use super::{
+    color::ColorParser, point::PointParser, vector::VectorParser, ObjectParser, ParseResult,
+};
 
-#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+// `type` is one of `point`, `area`, `spot` or `directional`, scenes without it get
+// a point light, or an area light when `corner` or `shape` is given.
+// A point light only needs `at`, area lights are a rectangle from `corner`
+// or a `disk` or `sphere` around `at`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub(crate) struct LightParser {
-    at: PointParser,
+    #[serde(rename(deserialize = "type"), default)]
+    light_type: Option<String>,
+
+    #[serde(default)]
+    at: Option<PointParser>,
     intensity: ColorParser,
+
+    #[serde(default)]
+    direction: Option<VectorParser>,
+    #[serde(rename(deserialize = "inner-angle"), default)]
+    inner_angle: Option<f64>,
+    #[serde(rename(deserialize = "outer-angle"), default)]
+    outer_angle: Option<f64>,
+    #[serde(default)]
+    falloff: Option<f64>,
+
+    #[serde(default)]
+    shape: Option<String>,
+    #[serde(default)]
+    corner: Option<PointParser>,
+    #[serde(default)]
+    uvec: Option<VectorParser>,
+    #[serde(default)]
+    vvec: Option<VectorParser>,
+    #[serde(default)]
+    radius: Option<f64>,
+    #[serde(default)]
+    usteps: Option<usize>,
+    #[serde(default)]
+    vsteps: Option<usize>,
+    #[serde(default)]
+    jitter: bool,
+
+    #[serde(default)]
+    attenuation: Option<AttenuationParser>,
+}
+
+// `type: inverse-square` with a `radius`, otherwise any of `constant`, `linear`
+// and `quadratic`, which default to no attenuation.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct AttenuationParser {
+    #[serde(rename(deserialize = "type"), default)]
+    attenuation_type: Option<String>,
+
+    #[serde(default = "default_constant")]
+    constant: f64,
+    #[serde(default)]
+    linear: f64,
+    #[serde(default)]
+    quadratic: f64,
+
+    #[serde(default)]
+    radius: Option<f64>,
+}
+
+fn default_constant() -> f64 {
+    1.0
 }
 
-impl ObjectParser<PointLight> for LightParser {
-    fn parse(&self) -> PointLight {
-        let position = self.at.parse();
-        let intensity = self.intensity.parse();
-        PointLight::new(position, intensity)
+impl ObjectParser<Attenuation> for AttenuationParser {
+    fn parse(&self) -> ParseResult<Attenuation> {
+        match self.attenuation_type.as_deref().unwrap_or("polynomial") {
+            "none" => Ok(Attenuation::None),
+            "polynomial" => {
+                // a positive denominator at every distance from the light
+                positive(self.constant, "constant")?;
+                not_negative(self.linear, "linear")?;
+                not_negative(self.quadratic, "quadratic")?;
+                Ok(Attenuation::Polynomial {
+                    constant: self.constant,
+                    linear: self.linear,
+                    quadratic: self.quadratic,
+                })
+            }
+            "inverse-square" => {
+                let radius = required(self.radius, "radius")?;
+                positive(radius, "radius")?;
+                Ok(Attenuation::InverseSquare { radius })
+            }
+            s => {
+                let message = format!(
+                    "unknown attenuation type `{}`, expected one of `none`, `polynomial`, `inverse-square`",
+                    s
+                );
+                Err(ParseError::new(ParseErrorKind::InvalidValue(message)).with_key("type"))
+            }
+        }
+    }
+}
+
+fn missing_field(field: &str) -> ParseError {
+    ParseError::new(ParseErrorKind::MissingField(field.to_string())).with_key(field)
+}
+
+fn required<T: Copy>(value: Option<T>, field: &str) -> ParseResult<T> {
+    value.ok_or_else(|| missing_field(field))
+}
+
+fn invalid_value(value: f64, field: &str, expected: &str) -> ParseError {
+    let message = format!("`{}` must be {}, got {}", field, expected, value);
+    ParseError::new(ParseErrorKind::InvalidValue(message)).with_key(field)
+}
+
+fn positive(value: f64, field: &str) -> ParseResult<()> {
+    match value > 0.0 {
+        true => Ok(()),
+        false => Err(invalid_value(value, field, "positive")),
+    }
+}
+
+fn not_negative(value: f64, field: &str) -> ParseResult<()> {
+    match value >= 0.0 {
+        true => Ok(()),
+        false => Err(invalid_value(value, field, "positive or zero")),
+    }
+}
+
+impl LightParser {
+    fn area_shape(&self) -> Option<&str> {
+        match (&self.shape, self.corner) {
+            (Some(shape), _) => Some(shape),
+            (None, Some(_)) => Some("rect"),
+            (None, None) => None,
+        }
+    }
+
+    fn parse_area(&self, shape: &str) -> ParseResult<AreaLight> {
+        let intensity = self.intensity.parse()?;
+        let light = match shape {
+            "rect" => AreaLight::rect(
+                required(self.corner, "corner")?.parse()?,
+                required(self.uvec, "uvec")?.parse()?,
+                required(self.vvec, "vvec")?.parse()?,
+                intensity,
+            ),
+            "disk" => AreaLight::disk(
+                required(self.at, "at")?.parse()?,
+                required(self.uvec, "uvec")?.parse()?,
+                required(self.vvec, "vvec")?.parse()?,
+                intensity,
+            ),
+            "sphere" => AreaLight::sphere(
+                required(self.at, "at")?.parse()?,
+                required(self.radius, "radius")?,
+                intensity,
+            ),
+            s => {
+                let message = format!(
+                    "unknown light shape `{}`, expected one of `rect`, `disk`, `sphere`",
+                    s
+                );
+                return Err(
+                    ParseError::new(ParseErrorKind::InvalidValue(message)).with_key("shape")
+                );
+            }
+        };
+        Ok(light
+            .with_steps(self.usteps.unwrap_or(1), self.vsteps.unwrap_or(1))
+            .with_jitter(self.jitter))
+    }
+
+    fn parse_spot(&self) -> ParseResult<SpotLight> {
+        let outer_angle = required(self.outer_angle, "outer-angle")?;
+        let light = SpotLight::new(
+            required(self.at, "at")?.parse()?,
+            required(self.direction, "direction")?.parse()?,
+            self.inner_angle.unwrap_or(outer_angle),
+            outer_angle,
+            self.intensity.parse()?,
+        );
+        Ok(match self.falloff {
+            Some(falloff) => light.with_falloff(falloff),
+            None => light,
+        })
+    }
+
+    fn light_type(&self) -> &str {
+        match (&self.light_type, self.area_shape()) {
+            (Some(light_type), _) => light_type,
+            (None, Some(_)) => "area",
+            (None, None) => "point",
+        }
+    }
+}
+
+impl ObjectParser<Light> for LightParser {
+    fn parse(&self) -> ParseResult<Light> {
+        let light = self.parse_light()?;
+        match &self.attenuation {
+            Some(attenuation) => {
+                let attenuation = attenuation.parse().map_err(|e| e.with_key("attenuation"))?;
+                Ok(light.with_attenuation(attenuation))
+            }
+            None => Ok(light),
+        }
+    }
+}
+
+impl LightParser {
+    fn parse_light(&self) -> ParseResult<Light> {
+        let intensity = self.intensity.parse()?;
+        match self.light_type() {
+            "point" => {
+                let position = required(self.at, "at")?.parse()?;
+                Ok(PointLight::new(position, intensity).into())
+            }
+            "area" => Ok(self.parse_area(self.area_shape().unwrap_or("rect"))?.into()),
+            "spot" => Ok(self.parse_spot()?.into()),
+            "directional" => {
+                let direction = required(self.direction, "direction")?.parse()?;
+                Ok(DirectionalLight::new(direction, intensity).into())
+            }
+            s => {
+                let message = format!(
+                    "unknown light type `{}`, expected one of `point`, `area`, `spot`, `directional`",
+                    s
+                );
+                Err(ParseError::new(ParseErrorKind::InvalidValue(message)).with_key("type"))
+            }
+        }
     }
 }
 
@@ -27,28 +247,44 @@ mod test {
         color::Color,
         parser::{objects::ParseResult, yaml::Parser},
         point::Point,
+        vector::Vector,
     };
 
     use super::*;
 
-    fn default_point_light() -> PointLight {
+    fn default_point_light() -> Light {
         let position = Point::new(1.0, 2.0, 3.0);
         let intensity = Color::new(0.4, 0.5, 0.6);
-        PointLight::new(position, intensity)
+        PointLight::new(position, intensity).into()
     }
 
     fn default_parser() -> LightParser {
         LightParser {
-            at: PointParser::new(1.0, 2.0, 3.0),
+            light_type: None,
+            at: Some(PointParser::new(1.0, 2.0, 3.0)),
             intensity: ColorParser::new(0.4, 0.5, 0.6),
+            direction: None,
+            inner_angle: None,
+            outer_angle: None,
+            falloff: None,
+            shape: None,
+            corner: None,
+            uvec: None,
+            vvec: None,
+            radius: None,
+            usteps: None,
+            vsteps: None,
+            jitter: false,
+            attenuation: None,
         }
     }
 
     #[test]
-    fn parse_to_point_light() {
+    fn parse_to_point_light() -> ParseResult<()> {
         let light = default_point_light();
         let parser = default_parser();
-        assert_eq!(parser.parse(), light);
+        assert_eq!(parser.parse()?, light);
+        Ok(())
     }
 
     #[test]
@@ -78,4 +314,186 @@ intensity: [0.4, 0.5, 0.6]
         assert_eq!(light, default_point_light());
         Ok(())
     }
+
+    #[test]
+    fn parse_area_light() -> ParseResult<()> {
+        let yaml = "
+corner: [-1, 2, 4]
+uvec: [2, 0, 0]
+vvec: [0, 2, 0]
+usteps: 10
+vsteps: 5
+jitter: true
+intensity: [1.5, 1.5, 1.5]
+";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let light = LightParser::from_value(value)?;
+        let expected = AreaLight::rect(
+            Point::new(-1.0, 2.0, 4.0),
+            Vector::new(2.0, 0.0, 0.0),
+            Vector::new(0.0, 2.0, 0.0),
+            Color::new(1.5, 1.5, 1.5),
+        )
+        .with_steps(10, 5)
+        .with_jitter(true);
+        assert_eq!(light, expected.into());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_sphere_light() -> ParseResult<()> {
+        let yaml = "
+shape: sphere
+at: [0, 5, 0]
+radius: 0.5
+usteps: 4
+vsteps: 4
+intensity: [1, 1, 1]
+";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let light = LightParser::from_value(value)?;
+        let expected = AreaLight::sphere(Point::new(0.0, 5.0, 0.0), 0.5, Color::new(1.0, 1.0, 1.0))
+            .with_steps(4, 4);
+        assert_eq!(light, expected.into());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_area_light_without_vectors() {
+        let mut parser = default_parser();
+        parser.corner = Some(PointParser::new(0.0, 0.0, 0.0));
+        let error = parser.parse().unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &ParseErrorKind::MissingField("uvec".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_spot_light() -> ParseResult<()> {
+        let yaml = "
+type: spot
+at: [0, 10, 0]
+direction: [0, -1, 0]
+inner-angle: 0.3
+outer-angle: 0.5
+falloff: 2
+intensity: [1, 1, 1]
+";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let light = LightParser::from_value(value)?;
+        let expected = SpotLight::new(
+            Point::new(0.0, 10.0, 0.0),
+            Vector::new(0.0, -1.0, 0.0),
+            0.3,
+            0.5,
+            Color::new(1.0, 1.0, 1.0),
+        )
+        .with_falloff(2.0);
+        assert_eq!(light, expected.into());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_directional_light() -> ParseResult<()> {
+        let yaml = "
+type: directional
+direction: [1, -1, 0]
+intensity: [1, 1, 1]
+";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let light = LightParser::from_value(value)?;
+        let expected =
+            DirectionalLight::new(Vector::new(1.0, -1.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(light, expected.into());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_unknown_light_type() {
+        let mut parser = default_parser();
+        parser.light_type = Some("laser".to_string());
+        let error = parser.parse().unwrap_err();
+        assert_eq!(error.key(), Some("type"));
+    }
+
+    #[test]
+    fn parse_light_attenuation() -> ParseResult<()> {
+        let yaml = "
+at: [0, 10, 0]
+intensity: [1, 1, 1]
+attenuation:
+  linear: 0.1
+  quadratic: 0.01
+";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let light = LightParser::from_value(value)?;
+        let attenuation = Attenuation::Polynomial {
+            constant: 1.0,
+            linear: 0.1,
+            quadratic: 0.01,
+        };
+        assert_eq!(light.attenuation(), attenuation);
+
+        let yaml = "
+type: spot
+at: [0, 10, 0]
+direction: [0, -1, 0]
+outer-angle: 0.5
+intensity: [1, 1, 1]
+attenuation:
+  type: inverse-square
+  radius: 4
+";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let light = LightParser::from_value(value)?;
+        assert_eq!(
+            light.attenuation(),
+            Attenuation::InverseSquare { radius: 4.0 }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_inverse_square_without_radius() -> ParseResult<()> {
+        let yaml = "
+at: [0, 10, 0]
+intensity: [1, 1, 1]
+attenuation:
+  type: inverse-square
+";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let error = LightParser::from_value(value).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &ParseErrorKind::MissingField("radius".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_invalid_attenuation() {
+        let cases = [
+            ("type: inverse-square\n  radius: 0", "radius"),
+            ("constant: 0", "constant"),
+            ("constant: -1", "constant"),
+            ("linear: -0.5", "linear"),
+            ("quadratic: -0.1", "quadratic"),
+        ];
+        for (attenuation, key) in cases {
+            let yaml = format!(
+                "
+at: [0, 10, 0]
+intensity: [1, 1, 1]
+attenuation:
+  {}
+",
+                attenuation
+            );
+            let value: Value = serde_yaml::from_str(&yaml).unwrap();
+            let error = LightParser::from_value(value).unwrap_err();
+            assert!(matches!(error.kind(), ParseErrorKind::InvalidValue(_)));
+            assert_eq!(error.key(), Some(key));
+        }
+    }
 }
@@ -1,8 +1,14 @@
 use serde::Deserialize;
+use serde_yaml::Value;
 
-use crate::material::Material;
+use crate::{
+    dispersion::Dispersion,
+    material::Material,
+    microfacet::Microfacet,
+    parser::error::{ParseError, ParseErrorKind},
+};
 
-use super::{color::ColorParser, pattern::PatternParser, ObjectParser};
+use super::{color::ColorParser, pattern::PatternParser, ObjectParser, ParseResult};
 
 fn default_color() -> ColorParser {
     let color = Material::default().color();
@@ -37,7 +43,149 @@ fn default_refractive_index() -> f64 {
     Material::default().refractive_index()
 }
 
+fn default_casts_shadow() -> bool {
+    Material::default().casts_shadow()
+}
+
+fn default_absorption() -> ColorParser {
+    let color = Material::default().absorption();
+    ColorParser::new(color.r(), color.g(), color.b())
+}
+
+fn default_density() -> f64 {
+    Material::default().density()
+}
+
+fn default_glossy_spread() -> f64 {
+    Material::default().glossy_spread()
+}
+
+fn default_glossy_samples() -> usize {
+    Material::default().glossy_samples()
+}
+
+fn default_emissive() -> ColorParser {
+    let color = Material::default().emissive();
+    ColorParser::new(color.r(), color.g(), color.b())
+}
+
+// either an abbe number or the two cauchy coefficients
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub(crate) struct DispersionParser {
+    abbe: Option<f64>,
+    cauchy: Option<[f64; 2]>,
+}
+
+impl DispersionParser {
+    fn parse(&self) -> ParseResult<Dispersion> {
+        match (self.abbe, self.cauchy) {
+            (Some(abbe), None) => Ok(Dispersion::Abbe(abbe)),
+            (None, Some([a, b])) => Ok(Dispersion::Cauchy { a, b }),
+            _ => Err(ParseError::new(ParseErrorKind::InvalidValue(
+                "dispersion needs one of `abbe` or `cauchy`".to_string(),
+            ))
+            .with_key("dispersion")),
+        }
+    }
+}
+
+// reflection model, `phong` when missing, `pbr` uses `metallic` and `roughness`
+// given either in a `model` block or next to it as keys of the material
 #[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "Value")]
+pub(crate) struct ModelParser {
+    model_type: String,
+    metallic: Option<f64>,
+    roughness: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ModelBlockParser {
+    #[serde(rename(deserialize = "type"))]
+    model_type: String,
+
+    #[serde(default)]
+    metallic: Option<f64>,
+
+    #[serde(default)]
+    roughness: Option<f64>,
+}
+
+// `model: pbr` or `model: { type: pbr, ... }`, with errors naming the yaml
+// instead of the parser types
+impl TryFrom<Value> for ModelParser {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(model_type) => Ok(ModelParser {
+                model_type,
+                metallic: None,
+                roughness: None,
+            }),
+            Value::Mapping(_) => {
+                let block: ModelBlockParser = serde_yaml::from_value(value)
+                    .map_err(|e| format!("invalid material model: {}", e))?;
+                Ok(ModelParser {
+                    model_type: block.model_type,
+                    metallic: block.metallic,
+                    roughness: block.roughness,
+                })
+            }
+            _ => Err(
+                "expected a material model name such as `pbr`, or a block with a `type`"
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+impl ModelParser {
+    fn parse(
+        &self,
+        metallic: Option<f64>,
+        roughness: Option<f64>,
+    ) -> ParseResult<Option<Microfacet>> {
+        let metallic = self.metallic.or(metallic);
+        let roughness = self.roughness.or(roughness);
+        match self.model_type.as_str() {
+            "phong" => {
+                pbr_only(metallic, "metallic")?;
+                pbr_only(roughness, "roughness")?;
+                Ok(None)
+            }
+            "pbr" => {
+                let default = Microfacet::default();
+                Ok(Some(Microfacet::new(
+                    metallic.unwrap_or(default.metallic()),
+                    roughness.unwrap_or(default.roughness()),
+                )))
+            }
+            model => Err(ParseError::new(ParseErrorKind::InvalidValue(format!(
+                "unknown material model `{}`, expected one of `phong`, `pbr`",
+                model
+            )))
+            .with_key("model")),
+        }
+    }
+}
+
+// the phong model has no use for the parameters of pbr
+fn pbr_only(value: Option<f64>, field: &str) -> ParseResult<()> {
+    match value {
+        Some(_) => {
+            let message = format!("`{}` is only used by `model: pbr`", field);
+            Err(ParseError::new(ParseErrorKind::InvalidValue(message)).with_key(field))
+        }
+        None => Ok(()),
+    }
+}
+
+// unknown keys are rejected, a misspelled key would otherwise render silently
+// with its default
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct MaterialParser {
     #[serde(default = "default_color")]
     color: ColorParser,
@@ -66,6 +214,43 @@ pub(crate) struct MaterialParser {
     )]
     refractive_index: f64,
 
+    #[serde(rename(deserialize = "casts-shadow"), default = "default_casts_shadow")]
+    casts_shadow: bool,
+
+    #[serde(default = "default_emissive")]
+    emissive: ColorParser,
+
+    #[serde(default)]
+    model: Option<ModelParser>,
+    #[serde(default)]
+    metallic: Option<f64>,
+    #[serde(default)]
+    roughness: Option<f64>,
+
+    // spread of glossy reflections and refractions
+    #[serde(
+        rename(deserialize = "glossiness"),
+        alias = "glossy-spread",
+        default = "default_glossy_spread"
+    )]
+    glossy_spread: f64,
+
+    #[serde(
+        rename(deserialize = "glossy-samples"),
+        default = "default_glossy_samples"
+    )]
+    glossy_samples: usize,
+
+    // light absorbed inside transparent objects per unit of distance
+    #[serde(default = "default_absorption")]
+    absorption: ColorParser,
+
+    #[serde(default = "default_density")]
+    density: f64,
+
+    #[serde(default)]
+    dispersion: Option<DispersionParser>,
+
     #[serde(default)]
     pattern: Option<PatternParser>,
 }
@@ -84,6 +269,16 @@ impl Default for MaterialParser {
             reflective: material.reflective(),
             transparency: material.transparency(),
             refractive_index: material.refractive_index(),
+            casts_shadow: material.casts_shadow(),
+            emissive: default_emissive(),
+            model: None,
+            metallic: None,
+            roughness: None,
+            glossy_spread: default_glossy_spread(),
+            glossy_samples: default_glossy_samples(),
+            absorption: default_absorption(),
+            density: default_density(),
+            dispersion: None,
             pattern: None,
         }
     }
@@ -111,25 +306,57 @@ impl MaterialParser {
             reflective,
             transparency,
             refractive_index,
+            casts_shadow: default_casts_shadow(),
+            emissive: default_emissive(),
+            model: None,
+            metallic: None,
+            roughness: None,
+            glossy_spread: default_glossy_spread(),
+            glossy_samples: default_glossy_samples(),
+            absorption: default_absorption(),
+            density: default_density(),
+            dispersion: None,
             pattern: None,
         }
     }
 }
 
 impl ObjectParser<Material> for MaterialParser {
-    fn parse(&self) -> Material {
+    fn parse(&self) -> ParseResult<Material> {
         let material = Material::default()
-            .with_color(self.color.parse())
+            .with_color(self.color.parse()?)
             .with_diffuse(self.diffuse)
             .with_ambient(self.ambient)
             .with_specular(self.specular)
             .with_shininess(self.shininess)
             .with_reflective(self.reflective)
             .with_transparency(self.transparency)
-            .with_refractive_index(self.refractive_index);
+            .with_refractive_index(self.refractive_index)
+            .with_casts_shadow(self.casts_shadow)
+            .with_emissive(self.emissive.parse()?)
+            .with_absorption(self.absorption.parse()?)
+            .with_density(self.density);
+        let material = match &self.dispersion {
+            Some(dispersion) => material.with_dispersion(dispersion.parse()?),
+            None => material,
+        };
+        let material = material
+            .with_glossy_spread(self.glossy_spread)
+            .with_glossy_samples(self.glossy_samples);
+        let phong = ModelParser {
+            model_type: "phong".to_string(),
+            metallic: None,
+            roughness: None,
+        };
+        let model = self.model.as_ref().unwrap_or(&phong);
+        let microfacet = model.parse(self.metallic, self.roughness)?;
+        let material = match microfacet {
+            Some(microfacet) => material.with_microfacet(microfacet),
+            None => material,
+        };
         match &self.pattern {
-            Some(p) => material.with_pattern(p.parse()),
-            _ => material,
+            Some(p) => Ok(material.with_pattern(p.parse().map_err(|e| e.with_key("pattern"))?)),
+            _ => Ok(material),
         }
     }
 }
@@ -170,15 +397,26 @@ mod test {
             reflective: 0.8,
             transparency: 0.9,
             refractive_index: 1.3,
+            casts_shadow: true,
+            emissive: ColorParser::new(0.0, 0.0, 0.0),
+            model: None,
+            metallic: None,
+            roughness: None,
+            glossy_spread: default_glossy_spread(),
+            glossy_samples: 8,
+            absorption: ColorParser::new(0.0, 0.0, 0.0),
+            density: 1.0,
+            dispersion: None,
             pattern: None,
         }
     }
 
     #[test]
-    fn parse_to_material() {
+    fn parse_to_material() -> ParseResult<()> {
         let material = default_material();
         let parser = default_parser();
-        assert_eq!(parser.parse(), material);
+        assert_eq!(parser.parse()?, material);
+        Ok(())
     }
 
     #[test]
@@ -231,4 +469,174 @@ pattern:
         assert_eq!(material, expected);
         Ok(())
     }
+
+    #[test]
+    fn parse_material_without_shadow() -> ParseResult<()> {
+        let yaml = "
+color: [0.1, 0.2, 0.3]
+casts-shadow: false
+";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let material = MaterialParser::from_value(value)?;
+        assert!(!material.casts_shadow());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_emissive_material() -> ParseResult<()> {
+        let yaml = "
+color: [0.1, 0.2, 0.3]
+emissive: [4, 3, 2]
+";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let material = MaterialParser::from_value(value)?;
+        let expected = Material::default()
+            .with_color(Color::new(0.1, 0.2, 0.3))
+            .with_emissive(Color::new(4.0, 3.0, 2.0));
+        assert_eq!(material, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_pbr_material() -> ParseResult<()> {
+        let yaml = "
+color: [1, 0.78, 0.34]
+model:
+  type: pbr
+  metallic: 1
+  roughness: 0.3
+";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let material = MaterialParser::from_value(value)?;
+        let expected = Material::default()
+            .with_color(Color::new(1.0, 0.78, 0.34))
+            .with_microfacet(Microfacet::new(1.0, 0.3));
+        assert_eq!(material, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_pbr_material_from_its_name() -> ParseResult<()> {
+        let yaml = "
+color: [1, 0.78, 0.34]
+model: pbr
+metallic: 1
+roughness: 0.3
+";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let material = MaterialParser::from_value(value)?;
+        let expected = Material::default()
+            .with_color(Color::new(1.0, 0.78, 0.34))
+            .with_microfacet(Microfacet::new(1.0, 0.3));
+        assert_eq!(material, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_invalid_material_models() -> ParseResult<()> {
+        let value: Value = serde_yaml::from_str("model: [pbr]")?;
+        let error = MaterialParser::from_value(value).unwrap_err();
+        assert!(error.to_string().contains("material model name"));
+        assert!(!error.to_string().contains("Parser"));
+        let value: Value = serde_yaml::from_str("model: { type: pbr, metalic: 1 }")?;
+        let error = MaterialParser::from_value(value).unwrap_err();
+        assert!(error.to_string().contains("metalic"));
+        let value: Value = serde_yaml::from_str("metallic: 1")?;
+        let error = MaterialParser::from_value(value).unwrap_err();
+        assert_eq!(error.key(), Some("metallic"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_unknown_material_model() -> ParseResult<()> {
+        let value: Value = serde_yaml::from_str("model: { type: lambert }")?;
+        let error = MaterialParser::from_value(value).unwrap_err();
+        assert_eq!(error.key(), Some("model"));
+        let value: Value = serde_yaml::from_str("model: lambert")?;
+        let error = MaterialParser::from_value(value).unwrap_err();
+        assert_eq!(error.key(), Some("model"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_glossy_material() -> ParseResult<()> {
+        let yaml = "
+reflective: 0.8
+glossiness: 0.2
+glossy-samples: 16
+";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let material = MaterialParser::from_value(value)?;
+        let expected = Material::default()
+            .with_reflective(0.8)
+            .with_glossy_spread(0.2)
+            .with_glossy_samples(16);
+        assert_eq!(material, expected);
+        let value: Value = serde_yaml::from_str("glossy-spread: 0.2")?;
+        let material = MaterialParser::from_value(value)?;
+        assert_eq!(material.glossy_spread(), 0.2);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_material_with_unknown_key() -> ParseResult<()> {
+        let value: Value = serde_yaml::from_str("glosiness: 0.5")?;
+        let error = MaterialParser::from_value(value).unwrap_err();
+        assert!(error.to_string().contains("glosiness"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_frosted_pbr_material() -> ParseResult<()> {
+        let yaml = "
+transparency: 0.9
+refractive-index: 1.5
+model: { type: pbr, roughness: 0.4 }
+glossiness: 0.1
+";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let material = MaterialParser::from_value(value)?;
+        assert_eq!(material.microfacet().map(|m| m.roughness()), Some(0.4));
+        assert_eq!(material.glossy_spread(), 0.1);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_absorbing_material() -> ParseResult<()> {
+        let yaml = "
+transparency: 0.9
+refractive-index: 1.5
+absorption: [0.8, 0.1, 0.8]
+density: 2.5
+";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let material = MaterialParser::from_value(value)?;
+        let expected = Material::default()
+            .with_transparency(0.9)
+            .with_refractive_index(1.5)
+            .with_absorption(Color::new(0.8, 0.1, 0.8))
+            .with_density(2.5);
+        assert_eq!(material, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_dispersive_materials() -> ParseResult<()> {
+        let value: Value = serde_yaml::from_str("dispersion: { abbe: 36 }")?;
+        let material = MaterialParser::from_value(value)?;
+        assert_eq!(material.dispersion(), Some(Dispersion::Abbe(36.0)));
+
+        let value: Value = serde_yaml::from_str("dispersion: { cauchy: [1.5046, 0.0042] }")?;
+        let material = MaterialParser::from_value(value)?;
+        let cauchy = Dispersion::Cauchy {
+            a: 1.5046,
+            b: 0.0042,
+        };
+        assert_eq!(material.dispersion(), Some(cauchy));
+
+        let value: Value = serde_yaml::from_str("dispersion: {}")?;
+        let error = MaterialParser::from_value(value).unwrap_err();
+        assert_eq!(error.key(), Some("dispersion"));
+        Ok(())
+    }
 }
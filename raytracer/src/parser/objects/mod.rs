@@ -20,13 +20,15 @@ pub(crate) mod vector;
 
 pub(crate) mod pattern;
 
+use super::error::ParseError;
+
 #[allow(dead_code)]
-pub(super) type ParseResult<T> = Result<T, serde_yaml::Error>;
+pub(super) type ParseResult<T> = Result<T, ParseError>;
 
 pub(crate) trait ObjectParser<T>: Sized + for<'de> Deserialize<'de> {
-    fn parse(&self) -> T;
-    fn from_value(value: serde_yaml::Value) -> Result<T, serde_yaml::Error> {
+    fn parse(&self) -> ParseResult<T>;
+    fn from_value(value: serde_yaml::Value) -> ParseResult<T> {
         let parser: Self = serde_yaml::from_value(value)?;
-        Ok(parser.parse())
+        parser.parse()
     }
 }
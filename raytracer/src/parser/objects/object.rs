@@ -1,5 +1,10 @@
 use crate::{
-    camera::Camera, light::PointLight, parser::attributes::add_attribute::AddAttribute,
+    camera::Camera,
+    light::Light,
+    parser::{
+        attributes::add_attribute::AddAttribute,
+        error::{ParseError, ParseErrorKind},
+    },
     shapes::shape::Shape,
 };
 
@@ -10,7 +15,7 @@ use super::{
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum Object {
     Camera(Box<Camera>),
-    Light(Box<PointLight>),
+    Light(Box<Light>),
     Shape(Box<Shape>),
 }
 
@@ -22,10 +27,13 @@ impl Object {
         match attribute_type {
             "camera" => Ok(Object::Camera(Box::new(CameraParser::from_value(value)?))),
             "light" => Ok(Object::Light(Box::new(LightParser::from_value(value)?))),
-            "sphere" | "plane" | "cube" | "cylinder" | "cone" => Ok(Object::Shape(Box::new(
-                ShapeParser::from_value(value, attribute_type)?,
-            ))),
-            s => unimplemented!("Parser for `{}` is not implemented", s),
+            "sphere" | "plane" | "cube" | "cylinder" | "cone" | "obj" | "csg" | "group" => Ok(
+                Object::Shape(Box::new(ShapeParser::from_value(value, attribute_type)?)),
+            ),
+            s => {
+                let kind = ParseErrorKind::UnknownObject(s.to_string());
+                Err(ParseError::new(kind).with_key("add"))
+            }
         }
     }
     pub fn as_camera(&self) -> Option<&Camera> {
@@ -35,7 +43,7 @@ impl Object {
         }
     }
 
-    pub fn as_light(&self) -> Option<&PointLight> {
+    pub fn as_light(&self) -> Option<&Light> {
         match self {
             Object::Light(light) => Some(light),
             _ => None,
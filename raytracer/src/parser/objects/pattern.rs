@@ -1,35 +1,284 @@
+use std::path::Path;
+
 use serde::Deserialize;
 
-use crate::{patterns::pattern::Pattern, transform::Transformable};
+use crate::{
+    color::Color,
+    parser::error::{ParseError, ParseErrorKind},
+    patterns::{
+        mapping::UvMapping,
+        noise::Noise,
+        pattern::Pattern,
+        texture::{ImageTexture, TextureFilter, WrapMode},
+        uv::UvPattern,
+    },
+    tonemap::Gamma,
+    transform::Transformable,
+};
+
+use super::{color::ColorParser, transform::TransformParser, ObjectParser, ParseResult};
+
+fn missing_field(field: &str) -> ParseError {
+    ParseError::new(ParseErrorKind::MissingField(field.to_string())).with_key(field)
+}
 
-use super::{color::ColorParser, transform::TransformParser, ObjectParser};
+fn two_colors(colors: &[ColorParser]) -> ParseResult<(Color, Color)> {
+    let [left_color, right_color] = colors else {
+        let message = format!("pattern needs 2 colors, found {}", colors.len());
+        return Err(ParseError::new(ParseErrorKind::InvalidValue(message)).with_key("colors"));
+    };
+    Ok((left_color.parse()?, right_color.parse()?))
+}
+
+// two colors for checkers, a main color and one per corner for align checks
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum UvColorsParser {
+    Pair(Vec<ColorParser>),
+    Corners {
+        main: ColorParser,
+        ul: ColorParser,
+        ur: ColorParser,
+        bl: ColorParser,
+        br: ColorParser,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct UvPatternParser {
+    #[serde(rename(deserialize = "type"))]
+    pattern_type: String,
+
+    #[serde(default)]
+    width: Option<f64>,
+    #[serde(default)]
+    height: Option<f64>,
+
+    #[serde(default)]
+    colors: Option<UvColorsParser>,
+
+    // image textures, the file is relative to the working directory,
+    // `filter` is `nearest` or `bilinear` and `gamma` tells how the colors of
+    // the file are encoded, `linear` by default, `srgb` for most 8 bit images
+    #[serde(default)]
+    file: Option<String>,
+    #[serde(default)]
+    filter: Option<String>,
+    #[serde(default)]
+    wrap: Option<String>,
+    #[serde(default)]
+    gamma: Option<String>,
+}
+
+impl UvPatternParser {
+    fn parse_image(&self) -> ParseResult<UvPattern> {
+        let file = self.file.as_deref().ok_or_else(|| missing_field("file"))?;
+        let invalid =
+            |e: String, key: &str| ParseError::new(ParseErrorKind::InvalidValue(e)).with_key(key);
+        let filter: TextureFilter = match &self.filter {
+            Some(filter) => filter.parse().map_err(|e| invalid(e, "filter"))?,
+            None => TextureFilter::default(),
+        };
+        let wrap: WrapMode = match &self.wrap {
+            Some(wrap) => wrap.parse().map_err(|e| invalid(e, "wrap"))?,
+            None => WrapMode::default(),
+        };
+        let gamma: Gamma = match &self.gamma {
+            Some(gamma) => gamma.parse().map_err(|e| invalid(e, "gamma"))?,
+            None => Gamma::default(),
+        };
+        let path = Path::new(file);
+        let texture = ImageTexture::from_file(path, filter, wrap, gamma).map_err(|e| {
+            let message = format!("could not read `{}`: {}", path.display(), e);
+            ParseError::new(ParseErrorKind::Io(message)).with_key("file")
+        })?;
+        Ok(UvPattern::image(texture))
+    }
+}
+
+impl ObjectParser<UvPattern> for UvPatternParser {
+    fn parse(&self) -> ParseResult<UvPattern> {
+        if self.pattern_type == "image" {
+            return self.parse_image();
+        }
+        let colors = self
+            .colors
+            .as_ref()
+            .ok_or_else(|| missing_field("colors"))?;
+        match (self.pattern_type.as_str(), colors) {
+            ("checkers", UvColorsParser::Pair(colors)) => {
+                let (left_color, right_color) = two_colors(colors)?;
+                let width = self.width.ok_or_else(|| missing_field("width"))?;
+                let height = self.height.ok_or_else(|| missing_field("height"))?;
+                Ok(UvPattern::checkers(width, height, left_color, right_color))
+            }
+            (
+                "align_check",
+                UvColorsParser::Corners {
+                    main,
+                    ul,
+                    ur,
+                    bl,
+                    br,
+                },
+            ) => Ok(UvPattern::align_check(
+                main.parse()?,
+                ul.parse()?,
+                ur.parse()?,
+                bl.parse()?,
+                br.parse()?,
+            )),
+            ("checkers" | "align_check", _) => {
+                let message = format!("wrong colors for a `{}` pattern", self.pattern_type);
+                Err(ParseError::new(ParseErrorKind::InvalidValue(message)).with_key("colors"))
+            }
+            (s, _) => {
+                let kind = ParseErrorKind::UnknownPattern(s.to_string());
+                Err(ParseError::new(kind).with_key("type"))
+            }
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 pub(crate) struct PatternParser {
     #[serde(rename(deserialize = "type"))]
     pattern_type: String,
 
+    #[serde(default)]
     colors: Vec<ColorParser>,
 
+    // texture maps apply `uv_pattern`, or a pattern per face for cube maps
+    #[serde(default)]
+    mapping: Option<String>,
+    #[serde(default)]
+    uv_pattern: Option<UvPatternParser>,
+    #[serde(default)]
+    left: Option<UvPatternParser>,
+    #[serde(default)]
+    front: Option<UvPatternParser>,
+    #[serde(default)]
+    right: Option<UvPatternParser>,
+    #[serde(default)]
+    back: Option<UvPatternParser>,
+    #[serde(default)]
+    up: Option<UvPatternParser>,
+    #[serde(default)]
+    down: Option<UvPatternParser>,
+
+    // noise patterns, `pattern` is the one jittered by `perturbed`
+    #[serde(default)]
+    pattern: Option<Box<PatternParser>>,
+    #[serde(default)]
+    seed: u64,
+    #[serde(default)]
+    octaves: Option<usize>,
+    #[serde(default)]
+    scale: Option<f64>,
+    #[serde(default)]
+    turbulence: Option<f64>,
+
     #[serde(default)]
     transform: TransformParser,
 }
 
+const DEFAULT_PERTURBED_SCALE: f64 = 0.2;
+const DEFAULT_MARBLE_TURBULENCE: f64 = 5.0;
+const DEFAULT_WOOD_TURBULENCE: f64 = 0.2;
+
+impl PatternParser {
+    fn noise(&self) -> Noise {
+        let noise = Noise::new(self.seed);
+        match self.octaves {
+            Some(octaves) => noise.with_octaves(octaves),
+            None => noise,
+        }
+    }
+
+    fn parse_perturbed(&self) -> ParseResult<Pattern> {
+        let pattern = self
+            .pattern
+            .as_ref()
+            .ok_or_else(|| missing_field("pattern"))?
+            .parse()
+            .map_err(|e| e.with_key("pattern"))?;
+        // the jittered points are moved into the space of the inner pattern
+        if pattern.inversed_transform().is_none() {
+            let message = "the transform of a perturbed pattern must be invertible".to_string();
+            return Err(ParseError::new(ParseErrorKind::InvalidValue(message))
+                .with_key("transform")
+                .with_key("pattern"));
+        }
+        let scale = self.scale.unwrap_or(DEFAULT_PERTURBED_SCALE);
+        Ok(Pattern::perturbed(pattern, self.noise(), scale))
+    }
+
+    fn parse_map(&self) -> ParseResult<Pattern> {
+        let mapping = self
+            .mapping
+            .as_deref()
+            .ok_or_else(|| missing_field("mapping"))?;
+        if mapping == "cube" {
+            let face = |parser: &Option<UvPatternParser>, field: &'static str| {
+                parser
+                    .as_ref()
+                    .ok_or_else(|| missing_field(field))?
+                    .parse()
+                    .map_err(|e| e.with_key(field))
+            };
+            return Ok(Pattern::cube_map(
+                face(&self.left, "left")?,
+                face(&self.front, "front")?,
+                face(&self.right, "right")?,
+                face(&self.back, "back")?,
+                face(&self.up, "up")?,
+                face(&self.down, "down")?,
+            ));
+        }
+        let mapping: UvMapping = mapping
+            .parse()
+            .map_err(|e| ParseError::new(ParseErrorKind::InvalidValue(e)).with_key("mapping"))?;
+        let uv_pattern = self
+            .uv_pattern
+            .as_ref()
+            .ok_or_else(|| missing_field("uv_pattern"))?
+            .parse()
+            .map_err(|e| e.with_key("uv_pattern"))?;
+        Ok(Pattern::map(mapping, uv_pattern))
+    }
+}
+
 #[allow(dead_code)]
 impl ObjectParser<Pattern> for PatternParser {
-    fn parse(&self) -> Pattern {
-        assert_eq!(self.colors.len(), 2, "Only support pattern with 2 colors");
-        let left_color = self.colors[0].parse();
-        let right_color = self.colors[1].parse();
+    fn parse(&self) -> ParseResult<Pattern> {
         let pattern = match self.pattern_type.as_str() {
-            "stripes" => Pattern::stripe(left_color, right_color),
-            "checkers" => Pattern::checker(left_color, right_color),
-            "gradients" => Pattern::gradient(left_color, right_color),
-            "rings" => Pattern::ring(left_color, right_color),
-            _ => unimplemented!(),
+            "map" => self.parse_map()?,
+            "perturbed" => self.parse_perturbed()?,
+            s => {
+                let (left_color, right_color) = two_colors(&self.colors)?;
+                match s {
+                    "stripes" => Pattern::stripe(left_color, right_color),
+                    "checkers" => Pattern::checker(left_color, right_color),
+                    "gradients" => Pattern::gradient(left_color, right_color),
+                    "rings" => Pattern::ring(left_color, right_color),
+                    "marble" => {
+                        let turbulence = self.turbulence.unwrap_or(DEFAULT_MARBLE_TURBULENCE);
+                        Pattern::marble(left_color, right_color, self.noise(), turbulence)
+                    }
+                    "wood" => {
+                        let turbulence = self.turbulence.unwrap_or(DEFAULT_WOOD_TURBULENCE);
+                        Pattern::wood(left_color, right_color, self.noise(), turbulence)
+                    }
+                    "granite" => Pattern::granite(left_color, right_color, self.noise()),
+                    s => {
+                        let kind = ParseErrorKind::UnknownPattern(s.to_string());
+                        return Err(ParseError::new(kind).with_key("type"));
+                    }
+                }
+            }
         };
-        let transform = self.transform.parse();
-        pattern.with_transform(transform)
+        let transform = self.transform.parse()?;
+        Ok(pattern.with_transform(transform))
     }
 }
 
@@ -39,6 +288,7 @@ mod test {
     use serde_yaml::Value;
 
     use crate::{
+        canvas::{Canvas, ImageFormat},
         color::Color,
         parser::objects::{transform::SingleTransformParser, ParseResult},
         transform::Transform,
@@ -67,6 +317,19 @@ mod test {
                 ColorParser::new(0.1, 0.2, 0.3),
                 ColorParser::new(0.4, 0.5, 0.6),
             ],
+            mapping: None,
+            uv_pattern: None,
+            left: None,
+            front: None,
+            right: None,
+            back: None,
+            up: None,
+            down: None,
+            pattern: None,
+            seed: 0,
+            octaves: None,
+            scale: None,
+            turbulence: None,
             transform,
         }
     }
@@ -87,10 +350,11 @@ transform:
     }
 
     #[test]
-    fn parse_to_pattern() {
+    fn parse_to_pattern() -> ParseResult<()> {
         let pattern = default_pattern();
         let parser = default_parser();
-        assert_eq!(parser.parse(), pattern);
+        assert_eq!(parser.parse()?, pattern);
+        Ok(())
     }
 
     #[test]
@@ -116,4 +380,215 @@ colors:
         assert_eq!(inversed, expected);
         Ok(())
     }
+
+    #[test]
+    fn parse_spherical_texture_map() -> ParseResult<()> {
+        let yaml = "
+type: map
+mapping: spherical
+uv_pattern:
+  type: checkers
+  width: 16
+  height: 8
+  colors:
+    - [0, 0.5, 0]
+    - [1, 1, 1]
+";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let pattern = PatternParser::from_value(value)?;
+        let checkers = UvPattern::checkers(
+            16.0,
+            8.0,
+            Color::new(0.0, 0.5, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        );
+        assert_eq!(pattern, Pattern::map(UvMapping::Spherical, checkers));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_cube_map() -> ParseResult<()> {
+        let face = |main: f64| {
+            format!(
+                "
+  type: align_check
+  colors:
+    main: [{}, {}, {}]
+    ul: [1, 0, 0]
+    ur: [1, 1, 0]
+    bl: [0, 1, 0]
+    br: [0, 1, 1]",
+                main, main, main
+            )
+        };
+        let yaml = format!(
+            "type: map\nmapping: cube\nleft:{}\nfront:{}\nright:{}\nback:{}\nup:{}\ndown:{}\n",
+            face(0.1),
+            face(0.2),
+            face(0.3),
+            face(0.4),
+            face(0.5),
+            face(0.6)
+        );
+        let value: Value = serde_yaml::from_str(&yaml)?;
+        let pattern = PatternParser::from_value(value)?;
+        let face = |c: f64| {
+            UvPattern::align_check(
+                Color::new(c, c, c),
+                Color::new(1.0, 0.0, 0.0),
+                Color::new(1.0, 1.0, 0.0),
+                Color::new(0.0, 1.0, 0.0),
+                Color::new(0.0, 1.0, 1.0),
+            )
+        };
+        let expected = Pattern::cube_map(
+            face(0.1),
+            face(0.2),
+            face(0.3),
+            face(0.4),
+            face(0.5),
+            face(0.6),
+        );
+        assert_eq!(pattern, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_image_texture() -> ParseResult<()> {
+        let file = tempfile::Builder::new().suffix(".ppm").tempfile().unwrap();
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(1, 0, &Color::new(1.0, 1.0, 1.0));
+        canvas.write_image(file.path(), ImageFormat::Ppm).unwrap();
+        let yaml = format!(
+            "
+type: map
+mapping: planar
+uv_pattern:
+  type: image
+  file: {}
+  filter: nearest
+  wrap: mirror
+  gamma: srgb
+",
+            file.path().display()
+        );
+        let value: Value = serde_yaml::from_str(&yaml)?;
+        let pattern = PatternParser::from_value(value)?;
+        let texture = ImageTexture::new(&canvas, TextureFilter::Nearest, WrapMode::Mirror).unwrap();
+        let expected = Pattern::map(UvMapping::Planar, UvPattern::image(texture));
+        assert_eq!(pattern, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_image_texture_errors() -> ParseResult<()> {
+        let value: Value = serde_yaml::from_str("{type: image, file: missing.png}")?;
+        let error = UvPatternParser::from_value(value).unwrap_err();
+        assert!(matches!(error.kind(), ParseErrorKind::Io(_)));
+        assert_eq!(error.key(), Some("file"));
+        let value: Value = serde_yaml::from_str("{type: image, file: a.png, wrap: tile}")?;
+        let error = UvPatternParser::from_value(value).unwrap_err();
+        assert_eq!(error.key(), Some("wrap"));
+        let value: Value = serde_yaml::from_str("{type: image, file: a.png, gamma: rec709}")?;
+        let error = UvPatternParser::from_value(value).unwrap_err();
+        assert_eq!(error.key(), Some("gamma"));
+        let value: Value = serde_yaml::from_str("{type: image, file: a.png, filter: trilinear}")?;
+        let error = UvPatternParser::from_value(value).unwrap_err();
+        assert_eq!(error.key(), Some("filter"));
+        let value: Value = serde_yaml::from_str("{type: checkers, width: 2, height: 2}")?;
+        let error = UvPatternParser::from_value(value).unwrap_err();
+        assert_eq!(error.key(), Some("colors"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_empty_image_texture() -> ParseResult<()> {
+        let file = tempfile::Builder::new().suffix(".pfm").tempfile().unwrap();
+        Canvas::new(0, 0)
+            .write_image(file.path(), ImageFormat::Pfm)
+            .unwrap();
+        let yaml = format!("{{type: image, file: {}}}", file.path().display());
+        let value: Value = serde_yaml::from_str(&yaml)?;
+        let error = UvPatternParser::from_value(value).unwrap_err();
+        assert!(error.to_string().contains("empty"), "{}", error);
+        assert_eq!(error.key(), Some("file"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_perturbed_pattern() -> ParseResult<()> {
+        let yaml = "
+type: perturbed
+scale: 0.3
+seed: 42
+octaves: 2
+pattern:
+  type: stripes
+  colors:
+    - [1, 1, 1]
+    - [0, 0, 0]
+  transform:
+    - [scale, 0.5, 0.5, 0.5]
+";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let pattern = PatternParser::from_value(value)?;
+        let stripes = Pattern::stripe(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0))
+            .with_transform(Transform::scaling(0.5, 0.5, 0.5));
+        let expected = Pattern::perturbed(stripes, Noise::new(42).with_octaves(2), 0.3);
+        assert_eq!(pattern, expected);
+        let value: Value = serde_yaml::from_str("type: perturbed")?;
+        let error = PatternParser::from_value(value).unwrap_err();
+        assert_eq!(error.key(), Some("pattern"));
+
+        let yaml = "
+type: perturbed
+pattern:
+  type: stripes
+  colors:
+    - [1, 1, 1]
+    - [0, 0, 0]
+  transform:
+    - [scale, 0, 1, 1]
+";
+        let value: Value = serde_yaml::from_str(yaml)?;
+        let error = PatternParser::from_value(value).unwrap_err();
+        assert!(matches!(error.kind(), ParseErrorKind::InvalidValue(_)));
+        assert_eq!(error.key(), Some("transform"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_noise_patterns() -> ParseResult<()> {
+        let (left, right) = (Color::new(0.9, 0.9, 0.85), Color::new(0.2, 0.2, 0.25));
+        let parse = |yaml: &str| -> ParseResult<Pattern> {
+            let value: Value = serde_yaml::from_str(yaml)?;
+            PatternParser::from_value(value)
+        };
+        let colors = "colors: [[0.9, 0.9, 0.85], [0.2, 0.2, 0.25]]";
+        let marble = parse(&format!(
+            "{{type: marble, turbulence: 3, seed: 7, {}}}",
+            colors
+        ))?;
+        assert_eq!(marble, Pattern::marble(left, right, Noise::new(7), 3.0));
+        let wood = parse(&format!("{{type: wood, octaves: 6, {}}}", colors))?;
+        let noise = Noise::new(0).with_octaves(6);
+        assert_eq!(
+            wood,
+            Pattern::wood(left, right, noise, DEFAULT_WOOD_TURBULENCE)
+        );
+        let granite = parse(&format!("{{type: granite, seed: 3, {}}}", colors))?;
+        assert_eq!(granite, Pattern::granite(left, right, Noise::new(3)));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_texture_map_errors() -> ParseResult<()> {
+        let value: Value = serde_yaml::from_str("{type: map, mapping: toroidal}")?;
+        let error = PatternParser::from_value(value).unwrap_err();
+        assert_eq!(error.key(), Some("mapping"));
+        let value: Value = serde_yaml::from_str("{type: map, mapping: planar}")?;
+        let error = PatternParser::from_value(value).unwrap_err();
+        assert_eq!(error.key(), Some("uv_pattern"));
+        Ok(())
+    }
 }
@@ -2,7 +2,7 @@ use serde::Deserialize;
 
 use crate::point::Point;
 
-use super::ObjectParser;
+use super::{ObjectParser, ParseResult};
 
 #[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
 pub(crate) struct PointParser(f64, f64, f64);
@@ -15,8 +15,8 @@ impl PointParser {
 }
 
 impl ObjectParser<Point> for PointParser {
-    fn parse(&self) -> Point {
-        Point::new(self.0, self.1, self.2)
+    fn parse(&self) -> ParseResult<Point> {
+        Ok(Point::new(self.0, self.1, self.2))
     }
 }
 
@@ -38,10 +38,11 @@ mod test {
     }
 
     #[test]
-    fn parse_to_point() {
+    fn parse_to_point() -> ParseResult<()> {
         let point = default_point();
         let parser = default_parser();
-        assert_eq!(parser.parse(), point);
+        assert_eq!(parser.parse()?, point);
+        Ok(())
     }
 
     #[test]
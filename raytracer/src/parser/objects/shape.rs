@@ -1,30 +1,64 @@
+use std::path::Path;
+
 use serde::Deserialize;
 use serde_yaml::Value;
 
 use crate::{
-    shapes::{shape::Shape, ShapeMaterial},
-    transform::Transformable,
+    parser::{
+        error::{ParseError, ParseErrorKind},
+        obj,
+    },
+    shapes::{csg::CsgOperation, shape::Shape, ShapeMaterial},
+    transform::{Transform, Transformable},
     util::INFINITY,
 };
 
-use super::{material::MaterialParser, transform::TransformParser, ObjectParser};
+use super::{material::MaterialParser, transform::TransformParser, ObjectParser, ParseResult};
 
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 pub(crate) struct ShapeParser {
     #[serde(default)]
-    material: MaterialParser,
+    material: Option<MaterialParser>,
 
     #[serde(default)]
     min: Option<f64>,
     #[serde(default)]
     max: Option<f64>,
 
+    #[serde(default)]
+    file: Option<String>,
+
+    #[serde(default)]
+    children: Option<Vec<Value>>,
+
+    #[serde(default)]
+    operation: Option<String>,
+    #[serde(default)]
+    left: Option<Value>,
+    #[serde(default)]
+    right: Option<Value>,
+
     #[serde(default)]
     transform: TransformParser,
 }
 
+fn missing_field(field: &str) -> ParseError {
+    ParseError::new(ParseErrorKind::MissingField(field.to_string())).with_key(field)
+}
+
 impl ShapeParser {
-    pub fn parse(&self, shape_type: &str) -> Shape {
+    pub fn parse(&self, shape_type: &str) -> ParseResult<Shape> {
+        self.parse_with_material(shape_type, None)
+    }
+
+    // shapes without their own material take the one of their parent
+    fn parse_with_material(
+        &self,
+        shape_type: &str,
+        inherited: Option<&MaterialParser>,
+    ) -> ParseResult<Shape> {
+        let transform = self.transform.parse()?;
+        let material = self.material.as_ref().or(inherited);
         let shape = match shape_type {
             "sphere" => Shape::sphere(),
             "plane" => Shape::plane(),
@@ -47,17 +81,74 @@ impl ShapeParser {
                     Shape::closed_cone(minimum, maximum)
                 }
             }
-            _ => unimplemented!(),
+            "obj" => {
+                let file = self.file.as_ref().ok_or_else(|| missing_field("file"))?;
+                // the transform is set on the whole group below, its children follow it
+                obj::from_file(Path::new(file))
+                    .map_err(|e| e.with_key("file"))?
+                    .to_group(Transform::identity())
+            }
+            "group" => {
+                let mut group = Shape::group().with_transform(transform);
+                for child in self.children.iter().flatten() {
+                    group.add_shape(ShapeParser::child_from_value(
+                        Some(child),
+                        "children",
+                        material,
+                    )?);
+                }
+                // children already carry their materials
+                return Ok(group);
+            }
+            "csg" => {
+                let operation = match self.operation.as_deref() {
+                    Some("union") => CsgOperation::Union,
+                    Some("intersection") => CsgOperation::Intersection,
+                    Some("difference") => CsgOperation::Difference,
+                    Some(s) => {
+                        let kind = ParseErrorKind::UnknownOperation(s.to_string());
+                        return Err(ParseError::new(kind).with_key("operation"));
+                    }
+                    None => return Err(missing_field("operation")),
+                };
+                let left = ShapeParser::child_from_value(self.left.as_ref(), "left", material)?;
+                let right = ShapeParser::child_from_value(self.right.as_ref(), "right", material)?;
+                // children already carry their materials
+                return Ok(Shape::csg(operation, left, right).transformed_by(transform));
+            }
+            s => {
+                let kind = ParseErrorKind::UnknownObject(s.to_string());
+                return Err(ParseError::new(kind).with_key("add"));
+            }
         };
-        let material = self.material.parse();
+        let material = material
+            .cloned()
+            .unwrap_or_default()
+            .parse()
+            .map_err(|e| e.with_key("material"))?;
         let shape = shape.with_material(material);
-        let transform = self.transform.parse();
-        shape.with_transform(transform)
+        Ok(shape.with_transform(transform))
     }
 
-    pub fn from_value(value: Value, attribute_type: &str) -> Result<Shape, serde_yaml::Error> {
+    // child shape is a mapping with its type under `type` (or `add`) and the usual shape keys
+    fn child_from_value(
+        value: Option<&Value>,
+        field: &'static str,
+        inherited: Option<&MaterialParser>,
+    ) -> ParseResult<Shape> {
+        let value = value.ok_or_else(|| missing_field(field))?;
+        let shape_type = value
+            .get("type")
+            .or_else(|| value.get("add"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| missing_field("type"))?;
+        let parser: ShapeParser = serde_yaml::from_value(value.clone())?;
+        parser.parse_with_material(shape_type, inherited)
+    }
+
+    pub fn from_value(value: Value, attribute_type: &str) -> ParseResult<Shape> {
         let parser: ShapeParser = serde_yaml::from_value(value)?;
-        Ok(parser.parse(attribute_type))
+        parser.parse(attribute_type)
     }
 }
 
@@ -107,18 +198,24 @@ mod test {
             SingleTransformParser::Shearing("shear".to_string(), 1.0, 2.0, 3.0, 4.0, 5.0, 6.0),
         ]);
         ShapeParser {
-            material: material_parser,
+            material: Some(material_parser),
             min: None,
             max: None,
+            file: None,
+            children: None,
+            operation: None,
+            left: None,
+            right: None,
             transform: transform_parser,
         }
     }
 
     #[test]
-    fn parse_to_shape() {
+    fn parse_to_shape() -> ParseResult<()> {
         let (shape_type, shape) = default_object();
         let parser = default_parser();
-        assert_eq!(parser.parse(&shape_type), shape);
+        assert_eq!(parser.parse(&shape_type)?, shape);
+        Ok(())
     }
 
     #[test]
@@ -174,4 +271,156 @@ transform:
         assert_eq!(shape, default_shape);
         Ok(())
     }
+
+    #[test]
+    fn parse_obj_from_str() -> ParseResult<()> {
+        let mut file = tempfile::Builder::new().suffix(".obj").tempfile().unwrap();
+        std::io::Write::write_all(
+            &mut file,
+            b"v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nf 1 2 3\ng Second\nf 1 3 4\n",
+        )
+        .unwrap();
+        let yaml = format!(
+            "
+- add: obj
+  file: {}
+  material:
+    color: [0.1, 0.2, 0.3]
+  transform:
+  - ['translate', 1.0, 2.0, 3.0]
+    ",
+            file.path().display()
+        );
+        let parser = Parser::from_yaml(&yaml)?;
+        let add_attributes = parser.add_attributes();
+        let shape = ShapeParser::from_value(add_attributes[0].value(), "obj")?;
+
+        let children = shape.as_group().unwrap();
+        assert_eq!(children.len(), 2);
+        let triangle = &children[0];
+        assert_eq!(triangle.material().color(), Color::new(0.1, 0.2, 0.3));
+        assert_eq!(triangle.transform(), Transform::translation(1.0, 2.0, 3.0));
+        let nested = &children[1].as_group().unwrap()[0];
+        assert_eq!(nested.material().color(), Color::new(0.1, 0.2, 0.3));
+        assert_eq!(nested.transform(), Transform::translation(1.0, 2.0, 3.0));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_obj_without_file() {
+        let value: Value = serde_yaml::from_str("material: {}").unwrap();
+        assert!(ShapeParser::from_value(value, "obj").is_err());
+    }
+
+    #[test]
+    fn parse_csg_from_str() -> ParseResult<()> {
+        let yaml = "
+- add: csg
+  operation: difference
+  material:
+    color: [0.1, 0.2, 0.3]
+  left:
+    type: cube
+  right:
+    type: sphere
+    material:
+      color: [1, 0, 0]
+    transform:
+      - [scale, 1.3, 1.3, 1.3]
+  transform:
+    - [translate, 1, 2, 3]
+    ";
+        let parser = Parser::from_yaml(yaml)?;
+        let add_attributes = parser.add_attributes();
+        let shape = ShapeParser::from_value(add_attributes[0].value(), "csg")?;
+
+        let csg = shape.as_csg().unwrap();
+        assert_eq!(csg.operation(), CsgOperation::Difference);
+        assert!(csg.left().as_cube().is_some());
+        assert_eq!(csg.left().material().color(), Color::new(0.1, 0.2, 0.3));
+        assert_eq!(
+            csg.left().transform(),
+            Transform::translation(1.0, 2.0, 3.0)
+        );
+        assert!(csg.right().as_sphere().is_some());
+        assert_eq!(csg.right().material().color(), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(
+            csg.right().transform(),
+            Transform::scaling(1.3, 1.3, 1.3).translate(1.0, 2.0, 3.0)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_csg_with_unknown_operation() {
+        let yaml = "
+operation: xor
+left:
+  type: cube
+right:
+  type: sphere
+";
+        let value: Value = serde_yaml::from_str(yaml).unwrap();
+        assert!(ShapeParser::from_value(value, "csg").is_err());
+    }
+
+    #[test]
+    fn parse_group_from_str() -> ParseResult<()> {
+        let yaml = "
+- add: group
+  material:
+    color: [0.1, 0.2, 0.3]
+  transform:
+    - [translate, 1, 2, 3]
+  children:
+    - add: sphere
+    - add: cube
+      material:
+        color: [1, 0, 0]
+      transform:
+        - [scale, 2, 2, 2]
+    - add: group
+      transform:
+        - [translate, 0, 0, 1]
+      children:
+        - add: cylinder
+          min: 0
+          max: 1
+    ";
+        let parser = Parser::from_yaml(yaml)?;
+        let add_attributes = parser.add_attributes();
+        let shape = ShapeParser::from_value(add_attributes[0].value(), "group")?;
+
+        let children = shape.as_group().unwrap();
+        assert_eq!(children.len(), 3);
+
+        let sphere = &children[0];
+        assert!(sphere.as_sphere().is_some());
+        assert_eq!(sphere.material().color(), Color::new(0.1, 0.2, 0.3));
+        assert_eq!(sphere.transform(), Transform::translation(1.0, 2.0, 3.0));
+
+        let cube = &children[1];
+        assert_eq!(cube.material().color(), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(
+            cube.transform(),
+            Transform::scaling(2.0, 2.0, 2.0).translate(1.0, 2.0, 3.0)
+        );
+
+        let cylinder = &children[2].as_group().unwrap()[0];
+        assert!(cylinder.as_cylinder().is_some());
+        assert_eq!(cylinder.material().color(), Color::new(0.1, 0.2, 0.3));
+        assert_eq!(
+            cylinder.transform(),
+            Transform::translation(0.0, 0.0, 1.0).translate(1.0, 2.0, 3.0)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_empty_group() -> ParseResult<()> {
+        let value: Value = serde_yaml::from_str("transform: []")?;
+        let shape = ShapeParser::from_value(value, "group")?;
+        assert_eq!(shape.as_group().map(|g| g.len()), Some(0));
+        Ok(())
+    }
 }
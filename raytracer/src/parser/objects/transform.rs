@@ -1,9 +1,12 @@
 use serde::{Deserialize, Serialize};
 use serde_yaml::Value;
 
-use crate::transform::Transform;
+use crate::{
+    parser::error::{ParseError, ParseErrorKind},
+    transform::Transform,
+};
 
-use super::ObjectParser;
+use super::{ObjectParser, ParseResult};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -15,29 +18,42 @@ pub(crate) enum SingleTransformParser {
 
 #[allow(dead_code)]
 impl SingleTransformParser {
-    pub fn parse(&self) -> Transform {
-        match self {
+    pub fn parse(&self) -> ParseResult<Transform> {
+        let transform = match self {
             SingleTransformParser::Rotation(s, a) => match s.as_str() {
-                "rotate-x" => Transform::rotation_x(*a),
-                "rotate-y" => Transform::rotation_y(*a),
-                "rotate-z" => Transform::rotation_z(*a),
-                _ => unimplemented!(),
+                "rotate-x" => Some(Transform::rotation_x(*a)),
+                "rotate-y" => Some(Transform::rotation_y(*a)),
+                "rotate-z" => Some(Transform::rotation_z(*a)),
+                _ => None,
             },
             SingleTransformParser::TranslationScaling(s, x, y, z) => match s.as_str() {
-                "translate" => Transform::translation(*x, *y, *z),
-                "scale" => Transform::scaling(*x, *y, *z),
-                _ => unimplemented!(),
+                "translate" => Some(Transform::translation(*x, *y, *z)),
+                "scale" => Some(Transform::scaling(*x, *y, *z)),
+                _ => None,
             },
             SingleTransformParser::Shearing(s, xy, xz, yx, yz, zx, zy) => match s.as_str() {
-                "shear" => Transform::shearing(*xy, *xz, *yx, *yz, *zx, *zy),
-                _ => unimplemented!(),
+                "shear" => Some(Transform::shearing(*xy, *xz, *yx, *yz, *zx, *zy)),
+                _ => None,
             },
+        };
+        // the name might be valid but used with the wrong number of arguments
+        transform.ok_or_else(|| {
+            ParseError::new(ParseErrorKind::UnknownTransform(self.name().to_string()))
+                .with_key("transform")
+        })
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            SingleTransformParser::Rotation(s, ..)
+            | SingleTransformParser::TranslationScaling(s, ..)
+            | SingleTransformParser::Shearing(s, ..) => s,
         }
     }
 
-    pub fn from_value(value: Value) -> Result<Transform, serde_yaml::Error> {
+    pub fn from_value(value: Value) -> ParseResult<Transform> {
         let parser: SingleTransformParser = serde_yaml::from_value(value)?;
-        Ok(parser.parse())
+        parser.parse()
     }
 }
 
@@ -51,13 +67,11 @@ impl TransformParser {
     }
 }
 impl ObjectParser<Transform> for TransformParser {
-    fn parse(&self) -> Transform {
+    fn parse(&self) -> ParseResult<Transform> {
         self.0
             .iter()
             .rev()
-            .map(|t| t.parse())
-            .reduce(|acc, t| acc * t)
-            .unwrap_or_else(Transform::identity)
+            .try_fold(Transform::identity(), |acc, t| Ok(acc * t.parse()?))
     }
 }
 
@@ -69,10 +83,11 @@ mod test {
     use super::*;
 
     #[test]
-    fn parse_to_rotate() {
+    fn parse_to_rotate() -> ParseResult<()> {
         let transform = Transform::rotation_y(1.5);
         let parser = SingleTransformParser::Rotation("rotate-y".to_string(), 1.5);
-        assert_eq!(parser.parse(), transform);
+        assert_eq!(parser.parse()?, transform);
+        Ok(())
     }
 
     #[test]
@@ -86,11 +101,12 @@ mod test {
     }
 
     #[test]
-    fn parse_to_translate() {
+    fn parse_to_translate() -> ParseResult<()> {
         let transform = Transform::translation(1.5, 2.5, 3.5);
         let parser =
             SingleTransformParser::TranslationScaling("translate".to_string(), 1.5, 2.5, 3.5);
-        assert_eq!(parser.parse(), transform);
+        assert_eq!(parser.parse()?, transform);
+        Ok(())
     }
 
     #[test]
@@ -104,10 +120,11 @@ mod test {
     }
 
     #[test]
-    fn parse_to_scale() {
+    fn parse_to_scale() -> ParseResult<()> {
         let transform = Transform::scaling(1.5, 2.5, 3.5);
         let parser = SingleTransformParser::TranslationScaling("scale".to_string(), 1.5, 2.5, 3.5);
-        assert_eq!(parser.parse(), transform);
+        assert_eq!(parser.parse()?, transform);
+        Ok(())
     }
 
     #[test]
@@ -121,11 +138,12 @@ mod test {
     }
 
     #[test]
-    fn parse_to_shear() {
+    fn parse_to_shear() -> ParseResult<()> {
         let transform = Transform::shearing(1.5, 2.5, 3.5, 7.5, 6.4, -5.3);
         let parser =
             SingleTransformParser::Shearing("shear".to_string(), 1.5, 2.5, 3.5, 7.5, 6.4, -5.3);
-        assert_eq!(parser.parse(), transform);
+        assert_eq!(parser.parse()?, transform);
+        Ok(())
     }
 
     #[test]
@@ -166,10 +184,11 @@ mod test {
     }
 
     #[test]
-    fn parser_to_combined_transform() {
+    fn parser_to_combined_transform() -> ParseResult<()> {
         let transform = default_transform();
         let parser = default_parser();
-        assert_eq!(parser.parse(), transform);
+        assert_eq!(parser.parse()?, transform);
+        Ok(())
     }
 
     #[test]
@@ -2,7 +2,7 @@ use serde::Deserialize;
 
 use crate::vector::Vector;
 
-use super::ObjectParser;
+use super::{ObjectParser, ParseResult};
 
 #[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
 pub(crate) struct VectorParser(f64, f64, f64);
@@ -15,8 +15,8 @@ impl VectorParser {
 }
 
 impl ObjectParser<Vector> for VectorParser {
-    fn parse(&self) -> Vector {
-        Vector::new(self.0, self.1, self.2)
+    fn parse(&self) -> ParseResult<Vector> {
+        Ok(Vector::new(self.0, self.1, self.2))
     }
 }
 
@@ -38,10 +38,11 @@ mod test {
     }
 
     #[test]
-    fn parse_to_vector() {
+    fn parse_to_vector() -> ParseResult<()> {
         let vector = default_vector();
         let parser = default_parser();
-        assert_eq!(parser.parse(), vector);
+        assert_eq!(parser.parse()?, vector);
+        Ok(())
     }
 
     #[test]
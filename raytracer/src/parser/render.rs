@@ -1,28 +1,110 @@
-use std::path::PathBuf;
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
 
-use tempfile::NamedTempFile;
+use crate::{
+    camera::{sample_count_canvas, Camera},
+    canvas::ImageFormat,
+    integrator::Integrator,
+    sampling::{Filter, SamplingStrategy},
+    tonemap::{Gamma, ToneOperator},
+    world::World,
+};
 
-use crate::world::World;
+use super::{
+    error::{ParseError, ParseErrorKind},
+    objects::object::Object,
+    yaml,
+};
 
-use super::{objects::object::Object, yaml};
+// Settings given on the command line, they take precedence over the scene file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderOptions {
+    pub samples: Option<usize>,
+    pub sampling: Option<SamplingStrategy>,
+    pub filter: Option<Filter>,
+    pub seed: Option<u64>,
+    // any of the adaptive settings turns adaptive antialiasing on
+    pub adaptive_threshold: Option<f64>,
+    pub adaptive_depth: Option<usize>,
+    // debug image of the number of samples per pixel
+    pub sample_map: Option<PathBuf>,
+    // image format of the output, guessed from the file extension when not given
+    pub format: Option<ImageFormat>,
+    pub tone_mapping: Option<ToneOperator>,
+    pub exposure: Option<f64>,
+    pub gamma: Option<Gamma>,
+    pub integrator: Option<Integrator>,
+    pub bounces: Option<usize>,
+}
 
-fn create_named_temporary_file() -> String {
-    let file = NamedTempFile::new().expect("Could not create temporary file");
-    let filename = file
-        .path()
-        .to_str()
-        .expect("Path could not convert to str")
-        .to_string();
-    file.close().expect("Error closing filename");
+impl RenderOptions {
+    fn apply(&self, camera: Camera) -> Result<Camera, String> {
+        let mut sampler = camera.sampler();
+        if let Some(samples) = self.samples {
+            sampler = sampler.with_samples(samples);
+        }
+        if let Some(strategy) = self.sampling {
+            sampler = sampler.with_strategy(strategy);
+        }
+        if let Some(filter) = self.filter {
+            sampler = sampler.with_filter(filter);
+        }
+        if let Some(seed) = self.seed {
+            sampler = sampler.with_seed(seed);
+        }
+        let mut adaptive = camera.adaptive();
+        if self.adaptive_threshold.is_some() || self.adaptive_depth.is_some() {
+            let mut settings = adaptive.unwrap_or_default();
+            if let Some(threshold) = self.adaptive_threshold {
+                settings = settings.with_threshold(threshold);
+            }
+            if let Some(depth) = self.adaptive_depth {
+                settings = settings.with_max_depth(depth);
+            }
+            adaptive = Some(settings);
+        }
+        let mut tone_mapping = camera.tone_mapping();
+        if let Some(operator) = self.tone_mapping {
+            tone_mapping = tone_mapping.with_operator(operator);
+        }
+        if let Some(exposure) = self.exposure {
+            tone_mapping = tone_mapping.with_exposure(exposure);
+        }
+        if let Some(gamma) = self.gamma {
+            tone_mapping = tone_mapping.with_gamma(gamma);
+        }
+        let mut integrator = self.integrator.unwrap_or(camera.integrator());
+        if let Some(bounces) = self.bounces {
+            integrator = integrator.with_max_depth(bounces)?;
+        }
+        Ok(camera
+            .with_sampler(sampler)
+            .with_integrator(integrator)
+            .with_adaptive(adaptive)
+            .with_tone_mapping(tone_mapping))
+    }
+}
 
-    let path = PathBuf::from(filename).with_extension("ppm");
-    path.into_os_string()
-        .into_string()
-        .expect("Error create temporary file")
+fn image_format(
+    image_file: &Path,
+    format: Option<ImageFormat>,
+) -> Result<ImageFormat, Box<dyn Error>> {
+    let format = format
+        .or_else(|| ImageFormat::from_path(image_file))
+        .ok_or_else(|| format!("unknown image format of `{}`", image_file.display()))?;
+    Ok(format)
 }
 
-pub fn render_image(yaml_file: &std::path::PathBuf, image_file: &std::path::PathBuf) {
-    let objects = yaml::from_file(yaml_file).expect("Error rendering yaml");
+// Returns the camera the image was rendered with, its sampler holds the number of samples per
+// pixel actually taken.
+pub fn render_image(
+    yaml_file: &Path,
+    image_file: &Path,
+    options: &RenderOptions,
+) -> Result<Camera, Box<dyn Error>> {
+    let objects = yaml::from_file(yaml_file)?;
 
     let mut camera = None;
     let mut lights = Vec::new();
@@ -34,17 +116,105 @@ pub fn render_image(yaml_file: &std::path::PathBuf, image_file: &std::path::Path
             Object::Shape(shape) => shapes.push(*shape),
         }
     }
-    let camera = camera.expect("Camera does not exist in yaml file");
+    let camera = camera.ok_or_else(|| ParseError::new(ParseErrorKind::MissingCamera))?;
+    let camera = options.apply(camera)?;
     let world = World::new(lights, shapes);
-    let canvas = camera.render(&world);
+    // whitted shading lights the scene with emissive shapes through their bounds
+    if camera.integrator() == Integrator::Whitted && world.has_unbounded_emitter() {
+        let message = "emissive shapes without bounds cannot light a scene with the `whitted` \
+                       integrator, bound them or use `path`"
+            .to_string();
+        return Err(ParseError::new(ParseErrorKind::InvalidValue(message))
+            .with_key("emissive")
+            .into());
+    }
+    let (canvas, counts) = camera.render_with_sample_counts(&world);
+
+    // floating point images keep the linear colors for later compositing
+    let format = image_format(image_file, options.format)?;
+    let canvas = match format.is_hdr() {
+        true => canvas,
+        false => camera.tone_mapping().apply(&canvas),
+    };
+    canvas.write_image(image_file, format)?;
+    if let Some(sample_map) = &options.sample_map {
+        // the sample map follows its own extension first
+        let format = match ImageFormat::from_path(sample_map) {
+            Some(format) => format,
+            None => image_format(sample_map, options.format)?,
+        };
+        sample_count_canvas(&counts).write_image(sample_map, format)?;
+    }
+    Ok(camera)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
 
-    let ppm_file = create_named_temporary_file();
-    canvas
-        .write_ppm(&ppm_file)
-        .expect("Error write to ppm file");
+    use super::*;
 
-    image::open(ppm_file)
-        .expect("Error open ppm_file")
-        .save(image_file)
-        .expect("Error write to image");
+    #[test]
+    fn unbounded_emitters_need_the_path_tracer() {
+        let scene = "
+- add: camera
+  width: 4
+  height: 4
+  field-of-view: 1
+  from: [0, 1, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+  integrator: {}
+- add: plane
+  material:
+    emissive: [1, 1, 1]
+";
+        let image = tempfile::Builder::new().suffix(".png").tempfile().unwrap();
+        let render = |integrator: &str| {
+            let mut yaml = tempfile::Builder::new().suffix(".yaml").tempfile().unwrap();
+            write!(yaml, "{}", scene.replace("{}", integrator)).unwrap();
+            render_image(yaml.path(), image.path(), &RenderOptions::default())
+        };
+        let error = render("whitted").unwrap_err();
+        assert!(error.to_string().contains("emissive"), "{}", error);
+        assert!(render("path").is_ok());
+    }
+
+    #[test]
+    fn bounces_need_the_path_tracer() {
+        let camera = Camera::new(2, 2, 1.0);
+        let options = RenderOptions {
+            bounces: Some(3),
+            ..RenderOptions::default()
+        };
+        assert!(options.apply(camera).is_err());
+        let options = RenderOptions {
+            integrator: "path".parse().ok(),
+            ..options
+        };
+        assert!(options.apply(camera).is_ok());
+    }
+
+    #[test]
+    fn rendered_camera_reports_the_samples_taken() {
+        let scene = "
+- add: camera
+  width: 2
+  height: 2
+  field-of-view: 1
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+";
+        let mut yaml = tempfile::Builder::new().suffix(".yaml").tempfile().unwrap();
+        write!(yaml, "{}", scene).unwrap();
+        let image = tempfile::Builder::new().suffix(".png").tempfile().unwrap();
+        let options = RenderOptions {
+            samples: Some(5),
+            ..RenderOptions::default()
+        };
+        let camera = render_image(yaml.path(), image.path(), &options).unwrap();
+        assert_eq!(camera.sampler().requested_samples(), 5);
+        assert_eq!(camera.sampler().samples(), 9);
+    }
 }
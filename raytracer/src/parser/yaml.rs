@@ -1,22 +1,28 @@
 use serde_yaml::Value;
-use std::{collections::HashMap, error::Error, fs};
+use std::{collections::HashMap, fs, path::Path};
 
 use super::{
     attributes::{add_attribute::AddAttribute, define_attribute::DefineAttribute},
+    error::{Location, ParseError, ParseErrorKind, SourceMap},
     objects::{object::Object, ParseResult},
 };
 
+#[cfg(test)]
 pub(crate) fn from_str(yaml_str: &str) -> ParseResult<Vec<Object>> {
-    Parser::from_yaml(yaml_str)?
-        .add_attributes()
-        .iter()
-        .map(|attr| attr.parse())
-        .collect()
+    Parser::from_yaml(yaml_str)?.parse()
 }
 
-pub(crate) fn from_file(file_name: &std::path::PathBuf) -> Result<Vec<Object>, Box<dyn Error>> {
-    let yaml_str = fs::read_to_string(file_name)?;
-    Ok(from_str(&yaml_str)?)
+pub(crate) fn from_file(file_name: &Path) -> ParseResult<Vec<Object>> {
+    let yaml_str = fs::read_to_string(file_name).map_err(|e| {
+        let message = format!("could not read `{}`: {}", file_name.display(), e);
+        ParseError::new(ParseErrorKind::Io(message))
+    })?;
+    let mut parser = Parser::from_yaml(&yaml_str)?;
+    // files referenced by the scene are next to it
+    if let Some(directory) = file_name.parent() {
+        parser.resolve_files(directory);
+    }
+    parser.parse()
 }
 
 pub(crate) type DefineAttributes = HashMap<String, DefineAttribute>;
@@ -25,88 +31,168 @@ pub(crate) type DefineAttributes = HashMap<String, DefineAttribute>;
 pub(crate) struct Parser {
     add_attributes: Vec<AddAttribute>,
     define_attributes: DefineAttributes,
+    source: SourceMap,
 }
 
 impl Parser {
-    pub(crate) fn from_yaml(yaml: &str) -> Result<Parser, serde_yaml::Error> {
+    pub(crate) fn from_yaml(yaml: &str) -> ParseResult<Parser> {
         let mut parser = Parser::from_yaml_without_preprocessing(yaml)?;
-        parser.prepare();
+        parser.prepare()?;
         Ok(parser)
     }
 
+    fn parse(&self) -> ParseResult<Vec<Object>> {
+        self.add_attributes()
+            .iter()
+            .map(|attr| {
+                attr.parse()
+                    .map_err(|e| e.locate(&self.source, attr.index()))
+            })
+            .collect()
+    }
+
+    fn resolve_files(&mut self, directory: &Path) {
+        self.add_attributes
+            .iter_mut()
+            .for_each(|attribute| attribute.resolve_files(directory));
+    }
+
     pub(crate) fn add_attributes(&self) -> &Vec<AddAttribute> {
         &self.add_attributes
     }
 
-    fn from_value(values: Value) -> Parser {
+    fn from_value(values: Value, source: SourceMap) -> ParseResult<Parser> {
         let mut add_attributes = Vec::new();
         let mut define_attributes = HashMap::new();
 
-        let seq = values
-            .as_sequence()
-            .expect("yaml file must define an array of objects.");
+        let seq = values.as_sequence().ok_or_else(|| {
+            let message = "yaml file must define an array of objects".to_string();
+            ParseError::new(ParseErrorKind::InvalidDocument(message))
+                .with_location(Some(Location::new(1, 1)))
+        })?;
+
+        for (index, value) in seq.iter().enumerate() {
+            let error = |message: &str, key: Option<&str>| {
+                let error = ParseError::new(ParseErrorKind::InvalidDocument(message.to_string()));
+                match key {
+                    Some(key) => error.with_key(key),
+                    None => error,
+                }
+                .locate(&source, index)
+            };
 
-        for value in seq {
             let mapping = value
                 .as_mapping()
-                .expect("Each object in yaml must be a mapping.");
-            if mapping.contains_key("add") {
-                let attribute_type = mapping["add"]
+                .ok_or_else(|| error("each object in yaml must be a mapping", None))?;
+            if let Some(add) = mapping.get("add") {
+                let attribute_type = add
                     .as_str()
-                    .expect("object with `add: {add_attr}` key must have `{add_attr}` as string")
+                    .ok_or_else(|| error("`add` must be a string", Some("add")))?
                     .to_string();
-                add_attributes.push(AddAttribute::new(value.clone(), attribute_type));
-            } else if mapping.contains_key("define") {
-                let define_id = mapping["define"]
+                add_attributes.push(AddAttribute::new(value.clone(), attribute_type, index));
+            } else if let Some(define) = mapping.get("define") {
+                let define_id = define
                     .as_str()
-                    .expect(
-                        "object with `define : {define_id}` key must have `{define_id}` as string",
-                    )
+                    .ok_or_else(|| error("`define` must be a string", Some("define")))?
                     .to_string();
-                define_attributes.insert(define_id, DefineAttribute::new(value.clone()));
+                define_attributes.insert(define_id, DefineAttribute::new(value.clone(), index));
             } else {
-                unreachable!("mapping does not contains `add` or `define` key");
+                return Err(error("object must have an `add` or a `define` key", None));
             }
         }
 
-        Parser {
+        Ok(Parser {
             add_attributes,
             define_attributes,
-        }
+            source,
+        })
     }
 
-    fn from_yaml_without_preprocessing(yaml: &str) -> Result<Parser, serde_yaml::Error> {
+    fn from_yaml_without_preprocessing(yaml: &str) -> ParseResult<Parser> {
         let values: Value = serde_yaml::from_str(yaml)?;
-        Ok(Parser::from_value(values))
+        Parser::from_value(values, SourceMap::new(yaml))
     }
 
-    fn extend(&mut self) {
-        let mut extensible_attributes = HashMap::new();
-        for (k, v) in self.define_attributes.iter() {
-            if v.extensible() {
-                extensible_attributes.insert(k.clone(), v.clone());
-            }
+    fn locate_define(&self, error: ParseError, name: &str) -> ParseError {
+        match self.define_attributes.get(name) {
+            Some(attr) => error.locate(&self.source, attr.index()),
+            None => error,
         }
-        loop {
-            let extensible = extensible_attributes.iter().any(|(_, v)| v.extensible());
-            if !extensible {
-                break;
-            }
-            extensible_attributes.values_mut().for_each(|v| {
-                if let Some(extend) = v.extend_value() {
-                    let other = self.define_attributes.get(extend).unwrap();
-                    v.extend(other);
-                }
+    }
+
+    fn extend(&mut self) -> ParseResult<()> {
+        let mut names: Vec<String> = self.define_attributes.keys().cloned().collect();
+        names.sort_unstable();
+        for name in names {
+            let mut chain = Vec::new();
+            self.extend_define(&name, &mut chain)
+                .map_err(|e| self.locate_define(e, &name))?;
+        }
+        Ok(())
+    }
+
+    // parents are extended first, `chain` holds the definitions being extended
+    fn extend_define(&mut self, name: &str, chain: &mut Vec<String>) -> ParseResult<()> {
+        let Some(parent) = self.define_attributes[name].extend_value() else {
+            return Ok(());
+        };
+        let parent = parent.to_string();
+        chain.push(name.to_string());
+        if chain.contains(&parent) {
+            chain.push(parent);
+            let kind = ParseErrorKind::CyclicReference(chain.clone());
+            return Err(ParseError::new(kind).with_key("extend"));
+        }
+        if !self.define_attributes.contains_key(&parent) {
+            let kind = ParseErrorKind::UndefinedReference(parent);
+            return Err(ParseError::new(kind).with_key("extend"));
+        }
+        self.extend_define(&parent, chain)?;
+
+        let other = self.define_attributes[&parent].clone();
+        self.define_attributes
+            .get_mut(name)
+            .and_then(|attr| attr.extend(&other))
+            .ok_or_else(|| {
+                let message = format!("`{}` and `{}` must both define a mapping", name, parent);
+                ParseError::new(ParseErrorKind::InvalidValue(message)).with_key("value")
             })
+    }
+
+    // definitions refering to each other would be substituted forever
+    fn check_cyclic_references(&self) -> ParseResult<()> {
+        fn visit(
+            name: &str,
+            attributes: &DefineAttributes,
+            chain: &mut Vec<String>,
+        ) -> Option<Vec<String>> {
+            if chain.iter().any(|n| n == name) {
+                let mut cycle = chain.clone();
+                cycle.push(name.to_string());
+                return Some(cycle);
+            }
+            chain.push(name.to_string());
+            let cycle = attributes[name]
+                .references(attributes)
+                .iter()
+                .find_map(|reference| visit(reference, attributes, chain));
+            chain.pop();
+            cycle
         }
-        self.define_attributes.iter_mut().for_each(|(k, v)| {
-            if let Some(extended_value) = extensible_attributes.get(k) {
-                *v = extended_value.clone();
+
+        let mut names: Vec<&String> = self.define_attributes.keys().collect();
+        names.sort_unstable();
+        for name in names {
+            if let Some(cycle) = visit(name, &self.define_attributes, &mut Vec::new()) {
+                let error = ParseError::new(ParseErrorKind::CyclicReference(cycle));
+                return Err(self.locate_define(error, name));
             }
-        });
+        }
+        Ok(())
     }
 
-    fn substitute_defined_attributes(&mut self) {
+    fn substitute_defined_attributes(&mut self) -> ParseResult<()> {
+        self.check_cyclic_references()?;
         loop {
             let copy_defined_attributes = self.define_attributes.clone();
             let success = self
@@ -117,18 +203,28 @@ impl Parser {
                 break;
             }
         }
+        for (name, attr) in &self.define_attributes {
+            attr.check_references()
+                .map_err(|e| self.locate_define(e, name))?;
+        }
+        Ok(())
     }
 
-    fn substitute_add_attributes(&mut self) {
+    fn substitute_add_attributes(&mut self) -> ParseResult<()> {
         for attribute in &mut self.add_attributes {
             attribute.substitute(&self.define_attributes);
+            attribute.instantiate(&self.define_attributes);
+            attribute
+                .check_references()
+                .map_err(|e| e.locate(&self.source, attribute.index()))?;
         }
+        Ok(())
     }
 
-    pub(crate) fn prepare(&mut self) {
-        self.extend();
-        self.substitute_defined_attributes();
-        self.substitute_add_attributes();
+    pub(crate) fn prepare(&mut self) -> ParseResult<()> {
+        self.extend()?;
+        self.substitute_defined_attributes()?;
+        self.substitute_add_attributes()
     }
 }
 
@@ -211,7 +307,7 @@ value:
     fn extend_all_defined_attribute() -> ParseResult<()> {
         let yaml = default_yaml();
         let mut parser = Parser::from_yaml_without_preprocessing(&yaml)?;
-        parser.extend();
+        parser.extend()?;
         for v in parser.define_attributes.values() {
             assert!(!v.extensible());
         }
@@ -232,8 +328,8 @@ value:
         ";
 
         let mut parser = Parser::from_yaml_without_preprocessing(yaml)?;
-        parser.extend();
-        parser.substitute_defined_attributes();
+        parser.extend()?;
+        parser.substitute_defined_attributes()?;
 
         let small_object = parser.define_attributes.get("small-object").unwrap();
         let expected = "
@@ -276,9 +372,9 @@ value:
 ";
 
         let mut parser = Parser::from_yaml_without_preprocessing(yaml)?;
-        parser.extend();
-        parser.substitute_defined_attributes();
-        parser.substitute_add_attributes();
+        parser.extend()?;
+        parser.substitute_defined_attributes()?;
+        parser.substitute_add_attributes()?;
 
         let cube = &parser.add_attributes[0];
         let expected = "
@@ -452,4 +548,216 @@ transform:
         assert_eq!(material, &expected);
         Ok(())
     }
+
+    #[test]
+    fn parse_defined_group_from_str() -> ParseResult<()> {
+        let yaml = "
+- define: red-material
+  value:
+    color: [1, 0, 0]
+- define: leg
+  value:
+    add: group
+    children:
+      - add: cylinder
+        min: 0
+        max: 1
+- define: table
+  value:
+    add: group
+    material: red-material
+    children:
+      - add: cube
+      - leg
+      - leg
+- add: table
+  transform:
+    - [translate, 0, 1, 0]
+";
+        let objects = from_str(yaml)?;
+        assert_eq!(objects.len(), 1);
+        let instance = objects[0].as_shape().unwrap().as_group().unwrap();
+        let table = instance[0].as_group().unwrap();
+        assert_eq!(table.len(), 3);
+
+        let leg = &table[1].as_group().unwrap()[0];
+        assert!(leg.as_cylinder().is_some());
+        assert_eq!(leg.material().color(), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(leg.transform(), Transform::translation(0.0, 1.0, 0.0));
+        Ok(())
+    }
+
+    fn parse_error(yaml: &str) -> ParseError {
+        from_str(yaml).unwrap_err()
+    }
+
+    #[test]
+    fn unknown_object_type_is_reported_with_location() {
+        let yaml = "
+- add: camera
+  width: 10
+  height: 20
+  field-of-view: 1.25
+  from: [1, 2, 3]
+  to: [4, 5, 6]
+  up: [7, 8, 9]
+- add: teapot
+  transform:
+    - [scale, 1, 1, 1]
+";
+        let error = parse_error(yaml);
+        assert_eq!(
+            error.kind(),
+            &ParseErrorKind::UnknownObject("teapot".to_string())
+        );
+        assert_eq!(error.key(), Some("add"));
+        assert_eq!(error.location(), Some(Location::new(9, 8)));
+    }
+
+    #[test]
+    fn bad_transform_name_is_reported() {
+        let yaml = "
+- add: sphere
+  transform:
+    - [translate, 1, 2, 3]
+    - [rotate-w, 1.5]
+";
+        let error = parse_error(yaml);
+        assert_eq!(
+            error.kind(),
+            &ParseErrorKind::UnknownTransform("rotate-w".to_string())
+        );
+        assert_eq!(error.key(), Some("transform"));
+        assert_eq!(error.location(), Some(Location::new(5, 8)));
+    }
+
+    #[test]
+    fn pattern_with_wrong_number_of_colors_is_reported() {
+        let yaml = "
+- add: plane
+  material:
+    pattern:
+      type: stripes
+      colors:
+        - [1, 1, 1]
+";
+        let error = parse_error(yaml);
+        assert!(matches!(error.kind(), ParseErrorKind::InvalidValue(_)));
+        assert_eq!(error.key(), Some("colors"));
+        assert_eq!(error.location(), Some(Location::new(6, 7)));
+    }
+
+    #[test]
+    fn undefined_reference_is_reported() {
+        let yaml = "
+- define: white-material
+  value:
+    color: [1, 1, 1]
+- add: cube
+  material: whit-material
+";
+        let error = parse_error(yaml);
+        assert_eq!(
+            error.kind(),
+            &ParseErrorKind::UndefinedReference("whit-material".to_string())
+        );
+        assert_eq!(error.key(), Some("material"));
+        assert_eq!(error.location(), Some(Location::new(6, 13)));
+    }
+
+    #[test]
+    fn undefined_extend_is_reported() {
+        let yaml = "
+- define: blue-material
+  extend: white-material
+  value:
+    color: [0, 0, 1]
+";
+        let error = parse_error(yaml);
+        assert_eq!(
+            error.kind(),
+            &ParseErrorKind::UndefinedReference("white-material".to_string())
+        );
+        assert_eq!(error.key(), Some("extend"));
+        assert_eq!(error.location(), Some(Location::new(3, 11)));
+    }
+
+    #[test]
+    fn cyclic_extend_is_reported() {
+        let yaml = "
+- define: a
+  extend: b
+  value:
+    color: [0, 0, 1]
+- define: b
+  extend: a
+  value:
+    color: [0, 1, 0]
+";
+        let error = parse_error(yaml);
+        assert_eq!(
+            error.kind(),
+            &ParseErrorKind::CyclicReference(vec!["a".into(), "b".into(), "a".into()])
+        );
+        assert_eq!(error.location(), Some(Location::new(2, 11)));
+    }
+
+    #[test]
+    fn cyclic_definitions_are_reported() {
+        let yaml = "
+- define: a
+  value:
+    - b
+- define: b
+  value:
+    - a
+";
+        let error = parse_error(yaml);
+        assert!(matches!(error.kind(), ParseErrorKind::CyclicReference(_)));
+    }
+
+    #[test]
+    fn object_without_add_or_define_is_reported() {
+        let yaml = "
+- add: sphere
+- remove: sphere
+";
+        let error = parse_error(yaml);
+        assert!(matches!(error.kind(), ParseErrorKind::InvalidDocument(_)));
+        assert_eq!(error.location(), Some(Location::new(3, 1)));
+    }
+
+    #[test]
+    fn yaml_syntax_error_keeps_its_location() {
+        let yaml = "
+- add: sphere
+  transform: [[scale, 1, 1, 1]
+";
+        let error = parse_error(yaml);
+        assert!(matches!(error.kind(), ParseErrorKind::Yaml(_)));
+        assert!(error.location().is_some());
+    }
+
+    #[test]
+    fn obj_files_are_relative_to_the_scene() -> ParseResult<()> {
+        let directory = tempfile::tempdir().unwrap();
+        fs::create_dir(directory.path().join("meshes")).unwrap();
+        let obj = "v -1 1 0\nv -1 0 0\nv 1 0 0\nf 1 2 3\n";
+        fs::write(directory.path().join("meshes/triangle.obj"), obj).unwrap();
+        let yaml = "
+- define: mesh
+  value:
+    add: obj
+    file: meshes/triangle.obj
+- add: group
+  children:
+    - mesh
+";
+        let scene = directory.path().join("scene.yaml");
+        fs::write(&scene, yaml).unwrap();
+        let objects = from_file(&scene)?;
+        let group = objects[0].as_shape().unwrap().as_group().unwrap();
+        assert_eq!(group[0].as_group().unwrap().len(), 1);
+        Ok(())
+    }
 }
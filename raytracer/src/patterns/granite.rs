@@ -0,0 +1,50 @@
+use crate::{color::Color, point::Point};
+
+use super::{noise::Noise, PatternLocal};
+
+// grains of two colors, turbulence pushed to sharp contrast
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct GranitePattern {
+    left_color: Color,
+    right_color: Color,
+    noise: Noise,
+}
+
+impl GranitePattern {
+    pub fn new(left_color: Color, right_color: Color, noise: Noise) -> GranitePattern {
+        GranitePattern {
+            left_color,
+            right_color,
+            noise,
+        }
+    }
+}
+
+impl PatternLocal for GranitePattern {
+    fn pattern_at(&self, point: &Point) -> Color {
+        let t = ((self.noise.turbulence(point) - 0.3) / 0.4).clamp(0.0, 1.0);
+        let fraction = t * t * (3.0 - 2.0 * t);
+        self.left_color + (self.right_color - self.left_color) * fraction
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::color;
+
+    use super::*;
+
+    #[test]
+    fn granite_mixes_both_colors() {
+        let pattern = GranitePattern::new(color::BLACK, color::WHITE, Noise::new(9));
+        let colors: Vec<Color> = (0..500)
+            .map(|i| {
+                let i = i as f64;
+                pattern.pattern_at(&Point::new(i * 0.173, i * 0.091, i * 0.057))
+            })
+            .collect();
+        assert!(colors.contains(&color::BLACK));
+        assert!(colors.contains(&color::WHITE));
+        assert_eq!(pattern.pattern_at(&Point::new(0.0, 0.0, 0.0)), color::BLACK);
+    }
+}
@@ -0,0 +1,274 @@
+use std::{f64::consts::PI, str::FromStr};
+
+use crate::{color::Color, point::Point, vector::Vector};
+
+use super::{uv::UvPattern, PatternLocal};
+
+/// Projection of points of a shape onto texture coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UvMapping {
+    /// Longitude and latitude of the point on a unit sphere.
+    Spherical,
+    /// The `x` and `z` coordinates, repeating every unit.
+    Planar,
+    /// Angle around the `y` axis and height, repeating every unit.
+    Cylindrical,
+}
+
+impl UvMapping {
+    pub(crate) fn uv(&self, point: &Point) -> (f64, f64) {
+        match self {
+            UvMapping::Spherical => {
+                let theta = point.x().atan2(point.z());
+                let radius = Vector::new(point.x(), point.y(), point.z()).magnitude();
+                let phi = (point.y() / radius).clamp(-1.0, 1.0).acos();
+                let raw_u = theta / (2.0 * PI);
+                (1.0 - (raw_u + 0.5), 1.0 - phi / PI)
+            }
+            UvMapping::Planar => (point.x().rem_euclid(1.0), point.z().rem_euclid(1.0)),
+            UvMapping::Cylindrical => {
+                let theta = point.x().atan2(point.z());
+                let raw_u = theta / (2.0 * PI);
+                (1.0 - (raw_u + 0.5), point.y().rem_euclid(1.0))
+            }
+        }
+    }
+}
+
+impl FromStr for UvMapping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "spherical" => Ok(UvMapping::Spherical),
+            "planar" => Ok(UvMapping::Planar),
+            "cylindrical" => Ok(UvMapping::Cylindrical),
+            s => Err(format!(
+                "unknown mapping `{}`, expected one of `spherical`, `planar`, `cylindrical`, `cube`",
+                s
+            )),
+        }
+    }
+}
+
+// 2d pattern wrapped around a shape
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TextureMap {
+    mapping: UvMapping,
+    uv_pattern: UvPattern,
+}
+
+impl TextureMap {
+    pub fn new(mapping: UvMapping, uv_pattern: UvPattern) -> TextureMap {
+        TextureMap {
+            mapping,
+            uv_pattern,
+        }
+    }
+}
+
+impl PatternLocal for TextureMap {
+    fn pattern_at(&self, point: &Point) -> Color {
+        let (u, v) = self.mapping.uv(point);
+        self.uv_pattern.uv_pattern_at(u, v)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CubeFace {
+    Left,
+    Front,
+    Right,
+    Back,
+    Up,
+    Down,
+}
+
+impl CubeFace {
+    // face of the unit cube the point is on, the one of its largest coordinate
+    fn from_point(point: &Point) -> CubeFace {
+        let (x, y, z) = (point.x(), point.y(), point.z());
+        let coord = x.abs().max(y.abs()).max(z.abs());
+        match coord {
+            c if c == x => CubeFace::Right,
+            c if c == -x => CubeFace::Left,
+            c if c == y => CubeFace::Up,
+            c if c == -y => CubeFace::Down,
+            c if c == z => CubeFace::Front,
+            _ => CubeFace::Back,
+        }
+    }
+
+    // coordinates in [0, 1] on the face, as seen from outside of the cube
+    fn uv(&self, point: &Point) -> (f64, f64) {
+        let (x, y, z) = (point.x(), point.y(), point.z());
+        let wrap = |c: f64| c.rem_euclid(2.0) / 2.0;
+        match self {
+            CubeFace::Front => (wrap(x + 1.0), wrap(y + 1.0)),
+            CubeFace::Back => (wrap(1.0 - x), wrap(y + 1.0)),
+            CubeFace::Left => (wrap(z + 1.0), wrap(y + 1.0)),
+            CubeFace::Right => (wrap(1.0 - z), wrap(y + 1.0)),
+            CubeFace::Up => (wrap(x + 1.0), wrap(1.0 - z)),
+            CubeFace::Down => (wrap(x + 1.0), wrap(z + 1.0)),
+        }
+    }
+}
+
+// a 2d pattern on every face of a unit cube
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CubeMap {
+    // left, front, right, back, up and down
+    faces: [UvPattern; 6],
+}
+
+impl CubeMap {
+    pub fn new(faces: [UvPattern; 6]) -> CubeMap {
+        CubeMap { faces }
+    }
+}
+
+impl PatternLocal for CubeMap {
+    fn pattern_at(&self, point: &Point) -> Color {
+        let face = CubeFace::from_point(point);
+        let (u, v) = face.uv(point);
+        self.faces[face as usize].uv_pattern_at(u, v)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{color, util::assert_float_eq};
+
+    use super::*;
+
+    fn assert_uv(mapping: UvMapping, point: Point, u: f64, v: f64) {
+        let uv = mapping.uv(&point);
+        assert_float_eq!(uv.0, u);
+        assert_float_eq!(uv.1, v);
+    }
+
+    #[test]
+    fn spherical_mapping() {
+        let sqrt_2_2 = std::f64::consts::FRAC_1_SQRT_2;
+        let expected = [
+            (Point::new(0.0, 0.0, -1.0), 0.0, 0.5),
+            (Point::new(1.0, 0.0, 0.0), 0.25, 0.5),
+            (Point::new(0.0, 0.0, 1.0), 0.5, 0.5),
+            (Point::new(-1.0, 0.0, 0.0), 0.75, 0.5),
+            (Point::new(0.0, 1.0, 0.0), 0.5, 1.0),
+            (Point::new(0.0, -1.0, 0.0), 0.5, 0.0),
+            (Point::new(sqrt_2_2, sqrt_2_2, 0.0), 0.25, 0.75),
+        ];
+        for (point, u, v) in expected {
+            assert_uv(UvMapping::Spherical, point, u, v);
+        }
+    }
+
+    #[test]
+    fn planar_mapping() {
+        let expected = [
+            (Point::new(0.25, 0.0, 0.5), 0.25, 0.5),
+            (Point::new(0.25, 0.0, -0.25), 0.25, 0.75),
+            (Point::new(0.25, 0.5, -0.25), 0.25, 0.75),
+            (Point::new(1.25, 0.0, 0.5), 0.25, 0.5),
+            (Point::new(0.25, 0.0, -1.75), 0.25, 0.25),
+            (Point::new(1.0, 0.0, -1.0), 0.0, 0.0),
+        ];
+        for (point, u, v) in expected {
+            assert_uv(UvMapping::Planar, point, u, v);
+        }
+    }
+
+    #[test]
+    fn cylindrical_mapping() {
+        let sqrt_2_2 = std::f64::consts::FRAC_1_SQRT_2;
+        let expected = [
+            (Point::new(0.0, 0.0, -1.0), 0.0, 0.0),
+            (Point::new(0.0, 0.5, -1.0), 0.0, 0.5),
+            (Point::new(0.0, 1.0, -1.0), 0.0, 0.0),
+            (Point::new(sqrt_2_2, 0.5, -sqrt_2_2), 0.125, 0.5),
+            (Point::new(1.0, 0.5, 0.0), 0.25, 0.5),
+            (Point::new(-sqrt_2_2, 0.75, sqrt_2_2), 0.625, 0.75),
+        ];
+        for (point, u, v) in expected {
+            assert_uv(UvMapping::Cylindrical, point, u, v);
+        }
+    }
+
+    #[test]
+    fn texture_map_with_spherical_mapping() {
+        let checkers = UvPattern::checkers(16.0, 8.0, color::BLACK, color::WHITE);
+        let pattern = TextureMap::new(UvMapping::Spherical, checkers);
+        let expected = [
+            (Point::new(0.4315, 0.4670, 0.7719), color::WHITE),
+            (Point::new(-0.9654, 0.2552, -0.0534), color::BLACK),
+            (Point::new(0.1039, 0.7090, 0.6975), color::WHITE),
+            (Point::new(-0.4986, -0.7856, -0.3663), color::BLACK),
+            (Point::new(0.0, 0.0, 1.0), color::BLACK),
+        ];
+        for (point, color) in expected {
+            assert_eq!(pattern.pattern_at(&point), color);
+        }
+    }
+
+    #[test]
+    fn faces_of_a_cube() {
+        let expected = [
+            (Point::new(-1.0, 0.5, -0.25), CubeFace::Left),
+            (Point::new(1.1, -0.75, 0.8), CubeFace::Right),
+            (Point::new(0.1, 0.6, 0.9), CubeFace::Front),
+            (Point::new(-0.7, 0.0, -2.0), CubeFace::Back),
+            (Point::new(0.5, 1.0, 0.9), CubeFace::Up),
+            (Point::new(-0.2, -1.3, 1.1), CubeFace::Down),
+        ];
+        for (point, face) in expected {
+            assert_eq!(CubeFace::from_point(&point), face);
+        }
+    }
+
+    #[test]
+    fn uv_on_cube_faces() {
+        let expected = [
+            (CubeFace::Front, Point::new(-0.5, 0.5, 1.0), 0.25, 0.75),
+            (CubeFace::Back, Point::new(0.5, -0.5, -1.0), 0.25, 0.25),
+            (CubeFace::Left, Point::new(-1.0, 0.5, -0.5), 0.25, 0.75),
+            (CubeFace::Right, Point::new(1.0, -0.5, 0.5), 0.25, 0.25),
+            (CubeFace::Up, Point::new(-0.5, 1.0, -0.5), 0.25, 0.75),
+            (CubeFace::Down, Point::new(-0.5, -1.0, 0.5), 0.25, 0.75),
+        ];
+        for (face, point, u, v) in expected {
+            let uv = face.uv(&point);
+            assert_float_eq!(uv.0, u);
+            assert_float_eq!(uv.1, v);
+        }
+    }
+
+    #[test]
+    fn cube_map_picks_the_pattern_of_every_face() {
+        let face = |c: f64| {
+            let main = Color::new(c, c, c);
+            UvPattern::align_check(main, color::WHITE, color::WHITE, color::WHITE, color::BLACK)
+        };
+        let cube = CubeMap::new([
+            face(0.1),
+            face(0.2),
+            face(0.3),
+            face(0.4),
+            face(0.5),
+            face(0.6),
+        ]);
+        let expected = [
+            (Point::new(-1.0, 0.0, 0.0), 0.1),
+            (Point::new(0.0, 0.0, 1.0), 0.2),
+            (Point::new(1.0, 0.0, 0.0), 0.3),
+            (Point::new(0.0, 0.0, -1.0), 0.4),
+            (Point::new(0.0, 1.0, 0.0), 0.5),
+            (Point::new(0.0, -1.0, 0.0), 0.6),
+        ];
+        for (point, c) in expected {
+            assert_eq!(cube.pattern_at(&point), Color::new(c, c, c));
+        }
+        // bottom right corner of the front face
+        assert_eq!(cube.pattern_at(&Point::new(0.9, -0.9, 1.0)), color::BLACK);
+    }
+}
@@ -0,0 +1,65 @@
+use std::f64::consts::PI;
+
+use crate::{color::Color, point::Point};
+
+use super::{noise::Noise, PatternLocal};
+
+// stripes along x bent by turbulence into veins
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MarblePattern {
+    left_color: Color,
+    right_color: Color,
+    noise: Noise,
+    turbulence: f64,
+}
+
+impl MarblePattern {
+    pub fn new(
+        left_color: Color,
+        right_color: Color,
+        noise: Noise,
+        turbulence: f64,
+    ) -> MarblePattern {
+        MarblePattern {
+            left_color,
+            right_color,
+            noise,
+            turbulence,
+        }
+    }
+}
+
+impl PatternLocal for MarblePattern {
+    fn pattern_at(&self, point: &Point) -> Color {
+        let x = point.x() + self.turbulence * self.noise.turbulence(point);
+        let fraction = 0.5 + 0.5 * (x * PI).sin();
+        self.left_color + (self.right_color - self.left_color) * fraction
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::color;
+
+    use super::*;
+
+    #[test]
+    fn marble_without_turbulence_is_a_sine_along_x() {
+        let pattern = MarblePattern::new(color::BLACK, color::WHITE, Noise::new(0), 0.0);
+        assert_eq!(pattern.pattern_at(&Point::new(0.5, 0.0, 0.0)), color::WHITE);
+        assert_eq!(pattern.pattern_at(&Point::new(1.5, 3.0, 2.0)), color::BLACK);
+        assert_eq!(
+            pattern.pattern_at(&Point::new(0.0, 1.0, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+    }
+
+    #[test]
+    fn turbulence_bends_the_veins() {
+        let pattern = MarblePattern::new(color::BLACK, color::WHITE, Noise::new(0), 4.0);
+        let colors: Vec<Color> = (0..10)
+            .map(|i| pattern.pattern_at(&Point::new(0.5, i as f64 * 0.37, 0.2)))
+            .collect();
+        assert!(colors.iter().any(|c| *c != colors[0]));
+    }
+}
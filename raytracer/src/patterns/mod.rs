@@ -1,8 +1,18 @@
+use std::sync::Arc;
+
 use crate::{color::Color, point::Point, shapes::shape::Shape};
 
 use self::{
-    checker::CheckerPattern, dummy::DummyPattern, gradient::GradientPattern, ring::RingPattern,
+    checker::CheckerPattern,
+    dummy::DummyPattern,
+    gradient::GradientPattern,
+    granite::GranitePattern,
+    mapping::{CubeMap, TextureMap},
+    marble::MarblePattern,
+    perturbed::PerturbedPattern,
+    ring::RingPattern,
     stripe::StripedPattern,
+    wood::WoodPattern,
 };
 
 pub(crate) mod pattern;
@@ -17,14 +27,37 @@ pub(crate) mod ring;
 
 pub(crate) mod checker;
 
+pub(crate) mod uv;
+
+pub(crate) mod mapping;
+
+pub(crate) mod texture;
+
+pub(crate) mod noise;
+
+pub(crate) mod perturbed;
+
+pub(crate) mod marble;
+
+pub(crate) mod wood;
+
+pub(crate) mod granite;
+
 #[allow(dead_code)]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) enum PatternKind {
     Stripe(StripedPattern),
     Dummy(DummyPattern),
     Gradient(GradientPattern),
     Ring(RingPattern),
     Checker(CheckerPattern),
+    Map(TextureMap),
+    // faces are shared, the pattern is copied with every material
+    CubeMap(Arc<CubeMap>),
+    Perturbed(PerturbedPattern),
+    Marble(MarblePattern),
+    Wood(WoodPattern),
+    Granite(GranitePattern),
 }
 
 pub(crate) trait PatternLocal {
@@ -0,0 +1,193 @@
+use crate::{point::Point, rng::Rng};
+
+/// Seeded 3d gradient noise, the improved noise of Ken Perlin, summed over
+/// `octaves` for fractal brownian motion and turbulence.
+///
+/// ```
+/// use raytracer::{Noise, Point};
+///
+/// let noise = Noise::new(7).with_octaves(6);
+/// let value = noise.fbm(&Point::new(0.3, 1.7, -2.1));
+/// assert!(value.abs() <= 2.0);
+/// assert_eq!(noise.noise(&Point::new(1.0, 2.0, 3.0)), 0.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Noise {
+    // shuffled 0..256 repeated twice, hashes the corners of the lattice
+    permutation: Vec<u8>,
+    octaves: usize,
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Noise::new(0)
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+// dot product with one of the 12 gradients pointing to the edges of a cube
+fn gradient(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = match h {
+        h if h < 4 => y,
+        12 | 14 => x,
+        _ => z,
+    };
+    let u = if h & 1 == 0 { u } else { -u };
+    let v = if h & 2 == 0 { v } else { -v };
+    u + v
+}
+
+impl Noise {
+    pub fn new(seed: u64) -> Noise {
+        let mut rng = Rng::new(seed);
+        let mut permutation: Vec<u8> = (0..=255).collect();
+        for i in (1..permutation.len()).rev() {
+            let j = ((rng.next_f64() * (i + 1) as f64) as usize).min(i);
+            permutation.swap(i, j);
+        }
+        permutation.extend_from_within(..);
+        Noise {
+            permutation,
+            octaves: 4,
+        }
+    }
+
+    pub fn octaves(&self) -> usize {
+        self.octaves
+    }
+
+    // every octave doubles the frequency and halves the amplitude
+    pub fn with_octaves(mut self, octaves: usize) -> Noise {
+        self.octaves = octaves.max(1);
+        self
+    }
+
+    /// Smooth noise in about [-1, 1], zero on every integer coordinate.
+    pub fn noise(&self, point: &Point) -> f64 {
+        let p = &self.permutation;
+        let (x, y, z) = (point.x().floor(), point.y().floor(), point.z().floor());
+        let (xi, yi, zi) = (
+            x.rem_euclid(256.0) as usize,
+            y.rem_euclid(256.0) as usize,
+            z.rem_euclid(256.0) as usize,
+        );
+        let (x, y, z) = (point.x() - x, point.y() - y, point.z() - z);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let a = p[xi] as usize + yi;
+        let (aa, ab) = (p[a] as usize + zi, p[a + 1] as usize + zi);
+        let b = p[xi + 1] as usize + yi;
+        let (ba, bb) = (p[b] as usize + zi, p[b + 1] as usize + zi);
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, gradient(p[aa], x, y, z), gradient(p[ba], x - 1.0, y, z)),
+                lerp(
+                    u,
+                    gradient(p[ab], x, y - 1.0, z),
+                    gradient(p[bb], x - 1.0, y - 1.0, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    gradient(p[aa + 1], x, y, z - 1.0),
+                    gradient(p[ba + 1], x - 1.0, y, z - 1.0),
+                ),
+                lerp(
+                    u,
+                    gradient(p[ab + 1], x, y - 1.0, z - 1.0),
+                    gradient(p[bb + 1], x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        )
+    }
+
+    fn octave_sum(&self, point: &Point, value: impl Fn(f64) -> f64) -> f64 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        for _ in 0..self.octaves {
+            let p = Point::new(
+                point.x() * frequency,
+                point.y() * frequency,
+                point.z() * frequency,
+            );
+            sum += amplitude * value(self.noise(&p));
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        sum
+    }
+
+    /// Fractal brownian motion, noise summed over every octave.
+    pub fn fbm(&self, point: &Point) -> f64 {
+        self.octave_sum(point, |n| n)
+    }
+
+    /// Absolute noise summed over every octave, always positive with sharp
+    /// creases where the noise crosses zero.
+    pub fn turbulence(&self, point: &Point) -> f64 {
+        self.octave_sum(point, f64::abs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::util::assert_float_eq;
+
+    use super::*;
+
+    #[test]
+    fn noise_is_zero_on_the_lattice() {
+        let noise = Noise::new(3);
+        assert_eq!(noise.noise(&Point::new(0.0, 0.0, 0.0)), 0.0);
+        assert_eq!(noise.noise(&Point::new(-4.0, 17.0, 300.0)), 0.0);
+    }
+
+    #[test]
+    fn noise_is_smooth_and_bounded() {
+        let noise = Noise::new(3);
+        let mut previous = noise.noise(&Point::new(0.0, 0.3, 0.7));
+        for i in 1..1000 {
+            let value = noise.noise(&Point::new(i as f64 * 0.01, 0.3, 0.7));
+            assert!(value.abs() <= 1.1);
+            assert!((value - previous).abs() < 0.05);
+            previous = value;
+        }
+    }
+
+    #[test]
+    fn seeds_change_the_noise() {
+        let point = Point::new(0.5, 1.25, 2.75);
+        assert_eq!(Noise::new(1).noise(&point), Noise::new(1).noise(&point));
+        assert_ne!(Noise::new(1).noise(&point), Noise::new(2).noise(&point));
+    }
+
+    #[test]
+    fn fbm_and_turbulence_sum_octaves() {
+        let point = Point::new(0.37, 1.41, -2.6);
+        let one = Noise::new(5).with_octaves(1);
+        assert_eq!(one.fbm(&point), one.noise(&point));
+        assert_eq!(one.turbulence(&point), one.noise(&point).abs());
+        let noise = Noise::new(5).with_octaves(3);
+        let double = Point::new(0.74, 2.82, -5.2);
+        let quadruple = Point::new(1.48, 5.64, -10.4);
+        let expected =
+            noise.noise(&point) + noise.noise(&double) * 0.5 + noise.noise(&quadruple) * 0.25;
+        assert_float_eq!(noise.fbm(&point), expected);
+        assert!(noise.turbulence(&point) >= noise.fbm(&point).abs());
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use crate::{
     color::Color,
     point::Point,
@@ -6,12 +8,26 @@ use crate::{
 };
 
 use super::{
-    dummy::DummyPattern, CheckerPattern, GradientPattern, PatternKind, PatternLocal, PatternWorld,
-    RingPattern, StripedPattern,
+    dummy::DummyPattern,
+    mapping::{CubeMap, TextureMap, UvMapping},
+    noise::Noise,
+    uv::UvPattern,
+    CheckerPattern, GradientPattern, GranitePattern, MarblePattern, PatternKind, PatternLocal,
+    PatternWorld, PerturbedPattern, RingPattern, StripedPattern, WoodPattern,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub(crate) struct Pattern {
+/// Color varying over the surface of a shape.
+///
+/// ```
+/// use raytracer::{Color, Material, Pattern, Transform, Transformable};
+///
+/// let stripes = Pattern::stripe(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0))
+///     .with_transform(Transform::scaling(0.2, 0.2, 0.2));
+/// let material = Material::default().with_pattern(stripes);
+/// assert!(material.pattern().is_some());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
     pattern: PatternKind,
     transform: Transform,
     inversed_transform: InversedTransform,
@@ -56,19 +72,79 @@ impl Pattern {
         )))
     }
 
-    pub fn dummy() -> Pattern {
+    // `uv_pattern` wrapped around the shape by `mapping`
+    pub fn map(mapping: UvMapping, uv_pattern: UvPattern) -> Pattern {
+        Pattern::new(PatternKind::Map(TextureMap::new(mapping, uv_pattern)))
+    }
+
+    // one pattern on every face of a unit cube
+    pub fn cube_map(
+        left: UvPattern,
+        front: UvPattern,
+        right: UvPattern,
+        back: UvPattern,
+        up: UvPattern,
+        down: UvPattern,
+    ) -> Pattern {
+        Pattern::new(PatternKind::CubeMap(Arc::new(CubeMap::new([
+            left, front, right, back, up, down,
+        ]))))
+    }
+
+    // `pattern` read at points moved by up to about `scale` along every axis
+    pub fn perturbed(pattern: Pattern, noise: Noise, scale: f64) -> Pattern {
+        Pattern::new(PatternKind::Perturbed(PerturbedPattern::new(
+            pattern, noise, scale,
+        )))
+    }
+
+    // veins along the x axis, more twisted as `turbulence` grows
+    pub fn marble(left_color: Color, right_color: Color, noise: Noise, turbulence: f64) -> Pattern {
+        Pattern::new(PatternKind::Marble(MarblePattern::new(
+            left_color,
+            right_color,
+            noise,
+            turbulence,
+        )))
+    }
+
+    // rings around the y axis, more irregular as `turbulence` grows
+    pub fn wood(left_color: Color, right_color: Color, noise: Noise, turbulence: f64) -> Pattern {
+        Pattern::new(PatternKind::Wood(WoodPattern::new(
+            left_color,
+            right_color,
+            noise,
+            turbulence,
+        )))
+    }
+
+    pub fn granite(left_color: Color, right_color: Color, noise: Noise) -> Pattern {
+        Pattern::new(PatternKind::Granite(GranitePattern::new(
+            left_color,
+            right_color,
+            noise,
+        )))
+    }
+
+    pub(crate) fn dummy() -> Pattern {
         Pattern::new(PatternKind::Dummy(DummyPattern::default()))
     }
 }
 
 impl PatternLocal for Pattern {
     fn pattern_at(&self, point: &Point) -> Color {
-        match self.pattern {
+        match &self.pattern {
             PatternKind::Stripe(p) => p.pattern_at(point),
             PatternKind::Dummy(p) => p.pattern_at(point),
             PatternKind::Gradient(p) => p.pattern_at(point),
             PatternKind::Ring(p) => p.pattern_at(point),
             PatternKind::Checker(p) => p.pattern_at(point),
+            PatternKind::Map(p) => p.pattern_at(point),
+            PatternKind::CubeMap(p) => p.pattern_at(point),
+            PatternKind::Perturbed(p) => p.pattern_at(point),
+            PatternKind::Marble(p) => p.pattern_at(point),
+            PatternKind::Wood(p) => p.pattern_at(point),
+            PatternKind::Granite(p) => p.pattern_at(point),
         }
     }
 }
@@ -0,0 +1,78 @@
+use crate::{color::Color, point::Point, transform::Transformable, vector::Vector};
+
+use super::{noise::Noise, pattern::Pattern, PatternLocal};
+
+// offsets reading the noise somewhere else for every axis of the jitter
+const OFFSETS: [f64; 3] = [0.0, 31.416, 74.203];
+
+// another pattern read at a point moved by noise, blurring its boundaries
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PerturbedPattern {
+    pattern: Box<Pattern>,
+    noise: Noise,
+    scale: f64,
+}
+
+impl PerturbedPattern {
+    pub fn new(pattern: Pattern, noise: Noise, scale: f64) -> PerturbedPattern {
+        PerturbedPattern {
+            pattern: Box::new(pattern),
+            noise,
+            scale,
+        }
+    }
+
+    // `scale` is how far the point moves at most along every axis, roughly
+    fn jitter(&self, point: &Point) -> Point {
+        let [dx, dy, dz] = OFFSETS.map(|offset| {
+            let shifted = Point::new(point.x() + offset, point.y(), point.z() - offset);
+            self.noise.fbm(&shifted) * self.scale
+        });
+        *point + Vector::new(dx, dy, dz)
+    }
+}
+
+impl PatternLocal for PerturbedPattern {
+    fn pattern_at(&self, point: &Point) -> Color {
+        let jittered = self.jitter(point);
+        // the parser rejects inner patterns that cannot be inverted
+        match self.pattern.inversed_transform() {
+            Some(inversed) => self.pattern.pattern_at(&jittered.transform(inversed)),
+            None => Color::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{color, transform::Transform};
+
+    use super::*;
+
+    #[test]
+    fn no_jitter_without_scale() {
+        let stripes = Pattern::stripe(color::WHITE, color::BLACK);
+        let pattern = PerturbedPattern::new(stripes, Noise::new(1), 0.0);
+        assert_eq!(pattern.pattern_at(&Point::new(0.9, 0.3, 0.2)), color::WHITE);
+        assert_eq!(pattern.pattern_at(&Point::new(1.1, 0.3, 0.2)), color::BLACK);
+    }
+
+    #[test]
+    fn jitter_moves_the_boundaries() {
+        let stripes = Pattern::stripe(color::WHITE, color::BLACK);
+        let pattern = PerturbedPattern::new(stripes, Noise::new(1), 0.5);
+        let near_boundary = (0..100)
+            .map(|i| Point::new(0.95, i as f64 * 0.13, i as f64 * 0.07))
+            .filter(|point| pattern.pattern_at(point) == color::BLACK)
+            .count();
+        assert!(near_boundary > 0 && near_boundary < 100);
+    }
+
+    #[test]
+    fn inner_pattern_keeps_its_transform() {
+        let stripes = Pattern::stripe(color::WHITE, color::BLACK)
+            .with_transform(Transform::scaling(0.5, 1.0, 1.0));
+        let pattern = PerturbedPattern::new(stripes, Noise::new(1), 0.0);
+        assert_eq!(pattern.pattern_at(&Point::new(0.6, 0.0, 0.0)), color::BLACK);
+    }
+}
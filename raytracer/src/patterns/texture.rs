@@ -0,0 +1,271 @@
+use std::{path::Path, str::FromStr};
+
+use image::{
+    error::{ParameterError, ParameterErrorKind},
+    ImageError, ImageResult,
+};
+
+use crate::{canvas::Canvas, color::Color, tonemap::Gamma};
+
+/// How colors are read between the texels of an image texture. There are no
+/// ray differentials to know how much of a texture a pixel covers, so there
+/// is no mipmapping, antialiasing samples average distant texels instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextureFilter {
+    /// Color of the closest texel.
+    Nearest,
+    /// Blend of the four closest texels.
+    #[default]
+    Bilinear,
+}
+
+impl FromStr for TextureFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nearest" => Ok(TextureFilter::Nearest),
+            "bilinear" => Ok(TextureFilter::Bilinear),
+            s => Err(format!(
+                "unknown filter `{}`, expected one of `nearest`, `bilinear`",
+                s
+            )),
+        }
+    }
+}
+
+/// What texture coordinates outside of [0, 1] read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WrapMode {
+    /// The image tiles.
+    #[default]
+    Repeat,
+    /// The texels of the border are extended.
+    Clamp,
+    /// The image tiles, every other copy flipped.
+    Mirror,
+}
+
+impl WrapMode {
+    fn wrap(self, index: i64, size: usize) -> usize {
+        let size = size as i64;
+        let wrapped = match self {
+            WrapMode::Repeat => index.rem_euclid(size),
+            WrapMode::Clamp => index.clamp(0, size - 1),
+            WrapMode::Mirror => {
+                let index = index.rem_euclid(2 * size);
+                match index < size {
+                    true => index,
+                    false => 2 * size - 1 - index,
+                }
+            }
+        };
+        wrapped as usize
+    }
+}
+
+impl FromStr for WrapMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "repeat" => Ok(WrapMode::Repeat),
+            "clamp" => Ok(WrapMode::Clamp),
+            "mirror" => Ok(WrapMode::Mirror),
+            s => Err(format!(
+                "unknown wrap mode `{}`, expected one of `repeat`, `clamp`, `mirror`",
+                s
+            )),
+        }
+    }
+}
+
+// Image read through texture coordinates, texels are stored row by row from
+// the top of the image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    texels: Vec<Color>,
+    filter: TextureFilter,
+    wrap: WrapMode,
+}
+
+impl ImageTexture {
+    // an empty image has no texel to wrap texture coordinates to
+    pub fn new(
+        canvas: &Canvas,
+        filter: TextureFilter,
+        wrap: WrapMode,
+    ) -> ImageResult<ImageTexture> {
+        if canvas.width() == 0 || canvas.height() == 0 {
+            let kind = ParameterErrorKind::Generic("the image is empty".to_string());
+            return Err(ImageError::Parameter(ParameterError::from_kind(kind)));
+        }
+        Ok(ImageTexture {
+            width: canvas.width(),
+            height: canvas.height(),
+            texels: canvas.rows().iter().flatten().copied().collect(),
+            filter,
+            wrap,
+        })
+    }
+
+    // the colors of the file are encoded with `gamma` and decoded back to
+    // the linear colors light is computed with
+    pub fn from_file(
+        path: &Path,
+        filter: TextureFilter,
+        wrap: WrapMode,
+        gamma: Gamma,
+    ) -> ImageResult<ImageTexture> {
+        let mut texture = ImageTexture::new(&Canvas::read_image(path)?, filter, wrap)?;
+        for texel in texture.texels.iter_mut() {
+            *texel = Color::new(
+                gamma.decode(texel.r()),
+                gamma.decode(texel.g()),
+                gamma.decode(texel.b()),
+            );
+        }
+        Ok(texture)
+    }
+
+    pub fn filter(&self) -> TextureFilter {
+        self.filter
+    }
+
+    pub fn wrap(&self) -> WrapMode {
+        self.wrap
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Color {
+        let x = self.wrap.wrap(x, self.width);
+        let y = self.wrap.wrap(y, self.height);
+        self.texels[y * self.width + x]
+    }
+
+    // `v` goes up from the bottom of the image
+    fn nearest(&self, u: f64, v: f64) -> Color {
+        let x = (u * self.width as f64).floor() as i64;
+        let y = ((1.0 - v) * self.height as f64).floor() as i64;
+        self.texel(x, y)
+    }
+
+    fn bilinear(&self, u: f64, v: f64) -> Color {
+        let x = u * self.width as f64 - 0.5;
+        let y = (1.0 - v) * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = self.texel(x0, y0) * (1.0 - fx) + self.texel(x0 + 1, y0) * fx;
+        let bottom = self.texel(x0, y0 + 1) * (1.0 - fx) + self.texel(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+
+    pub(crate) fn color_at(&self, u: f64, v: f64) -> Color {
+        match self.filter {
+            TextureFilter::Nearest => self.nearest(u, v),
+            TextureFilter::Bilinear => self.bilinear(u, v),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{canvas::ImageFormat, color, util::assert_float_eq};
+
+    use super::*;
+
+    // black and white texels in a checker board
+    fn checkers(width: usize, height: usize) -> Canvas {
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                if (x + y) % 2 == 1 {
+                    canvas.write_pixel(x, y, &color::WHITE);
+                }
+            }
+        }
+        canvas
+    }
+
+    #[test]
+    fn wrapping_texel_indices() {
+        assert_eq!(WrapMode::Repeat.wrap(5, 4), 1);
+        assert_eq!(WrapMode::Repeat.wrap(-1, 4), 3);
+        assert_eq!(WrapMode::Clamp.wrap(5, 4), 3);
+        assert_eq!(WrapMode::Clamp.wrap(-1, 4), 0);
+        assert_eq!(WrapMode::Mirror.wrap(4, 4), 3);
+        assert_eq!(WrapMode::Mirror.wrap(-1, 4), 0);
+        assert_eq!(WrapMode::Mirror.wrap(9, 4), 1);
+    }
+
+    #[test]
+    fn nearest_texels_with_v_going_up() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.write_pixel(0, 0, &Color::new(1.0, 0.0, 0.0));
+        canvas.write_pixel(1, 1, &Color::new(0.0, 0.0, 1.0));
+        let texture = ImageTexture::new(&canvas, TextureFilter::Nearest, WrapMode::Repeat).unwrap();
+        // the first row of the image is the top of the texture
+        assert_eq!(texture.color_at(0.25, 0.75), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(texture.color_at(0.75, 0.25), Color::new(0.0, 0.0, 1.0));
+        assert_eq!(texture.color_at(1.25, 1.75), Color::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn bilinear_filtering_blends_neighbours() {
+        let texture =
+            ImageTexture::new(&checkers(2, 1), TextureFilter::Bilinear, WrapMode::Clamp).unwrap();
+        // texel centers are exact, halfway between them is a blend
+        assert_float_eq!(texture.color_at(0.25, 0.5).r(), 0.0);
+        assert_float_eq!(texture.color_at(0.75, 0.5).r(), 1.0);
+        assert_float_eq!(texture.color_at(0.5, 0.5).r(), 0.5);
+        // clamped borders do not blend with the other side
+        assert_float_eq!(texture.color_at(0.0, 0.5).r(), 0.0);
+        let repeat =
+            ImageTexture::new(&checkers(2, 1), TextureFilter::Bilinear, WrapMode::Repeat).unwrap();
+        assert_float_eq!(repeat.color_at(0.0, 0.5).r(), 0.5);
+    }
+
+    #[test]
+    fn empty_images_are_rejected() {
+        let new = |width, height| {
+            ImageTexture::new(
+                &Canvas::new(width, height),
+                TextureFilter::Nearest,
+                WrapMode::Repeat,
+            )
+        };
+        assert!(new(0, 2).is_err());
+        assert!(new(2, 0).is_err());
+        assert!(new(1, 1).is_ok());
+    }
+
+    #[test]
+    fn srgb_files_are_decoded_to_linear_colors() {
+        let file = tempfile::Builder::new().suffix(".ppm").tempfile().unwrap();
+        let mut canvas = Canvas::new(1, 1);
+        canvas.write_pixel(0, 0, &Color::new(1.0, 0.0, 0.0));
+        canvas.write_image(file.path(), ImageFormat::Ppm).unwrap();
+        let read = |gamma| {
+            ImageTexture::from_file(file.path(), TextureFilter::Nearest, WrapMode::Clamp, gamma)
+                .unwrap()
+                .color_at(0.5, 0.5)
+        };
+        assert_eq!(read(Gamma::Linear), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(read(Gamma::Srgb), Color::new(1.0, 0.0, 0.0));
+
+        canvas.write_pixel(0, 0, &Color::new(0.5, 0.5, 0.5));
+        canvas.write_image(file.path(), ImageFormat::Ppm).unwrap();
+        // 8 bit files round the colors a little
+        assert!((read(Gamma::Linear).r() - 0.5).abs() < 0.01);
+        assert!((read(Gamma::Srgb).r() - 0.214).abs() < 0.01);
+    }
+
+    #[test]
+    fn parse_filter_and_wrap_names() {
+        assert_eq!("nearest".parse(), Ok(TextureFilter::Nearest));
+        assert_eq!("mirror".parse(), Ok(WrapMode::Mirror));
+        assert!("trilinear".parse::<TextureFilter>().is_err());
+    }
+}
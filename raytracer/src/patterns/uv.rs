@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use crate::color::Color;
+
+use super::texture::ImageTexture;
+
+/// Two dimensional pattern over texture coordinates `u` and `v` in [0, 1],
+/// applied to shapes through a [`UvMapping`](crate::UvMapping).
+#[derive(Debug, Clone, PartialEq)]
+pub enum UvPattern {
+    /// `width` by `height` squares alternating between two colors.
+    Checkers {
+        width: f64,
+        height: f64,
+        left_color: Color,
+        right_color: Color,
+    },
+    /// One color with a different one in every corner, shows how a mapping
+    /// is oriented.
+    AlignCheck {
+        main: Color,
+        upper_left: Color,
+        upper_right: Color,
+        bottom_left: Color,
+        bottom_right: Color,
+    },
+    /// Image loaded from disk, shared by every copy of the pattern.
+    Image(Arc<ImageTexture>),
+}
+
+impl UvPattern {
+    pub fn checkers(width: f64, height: f64, left_color: Color, right_color: Color) -> UvPattern {
+        UvPattern::Checkers {
+            width,
+            height,
+            left_color,
+            right_color,
+        }
+    }
+
+    pub fn align_check(
+        main: Color,
+        upper_left: Color,
+        upper_right: Color,
+        bottom_left: Color,
+        bottom_right: Color,
+    ) -> UvPattern {
+        UvPattern::AlignCheck {
+            main,
+            upper_left,
+            upper_right,
+            bottom_left,
+            bottom_right,
+        }
+    }
+
+    pub fn image(texture: ImageTexture) -> UvPattern {
+        UvPattern::Image(Arc::new(texture))
+    }
+
+    pub(crate) fn uv_pattern_at(&self, u: f64, v: f64) -> Color {
+        match *self {
+            UvPattern::Checkers {
+                width,
+                height,
+                left_color,
+                right_color,
+            } => {
+                let u = (u * width).floor() as i64;
+                let v = (v * height).floor() as i64;
+                match (u + v).rem_euclid(2) == 0 {
+                    true => left_color,
+                    false => right_color,
+                }
+            }
+            UvPattern::AlignCheck {
+                main,
+                upper_left,
+                upper_right,
+                bottom_left,
+                bottom_right,
+            } => match (u, v) {
+                (u, v) if v > 0.8 && u < 0.2 => upper_left,
+                (u, v) if v > 0.8 && u > 0.8 => upper_right,
+                (u, v) if v < 0.2 && u < 0.2 => bottom_left,
+                (u, v) if v < 0.2 && u > 0.8 => bottom_right,
+                _ => main,
+            },
+            UvPattern::Image(ref texture) => texture.color_at(u, v),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::color;
+
+    use super::*;
+
+    #[test]
+    fn checkers_in_uv_space() {
+        let checkers = UvPattern::checkers(2.0, 2.0, color::BLACK, color::WHITE);
+        let expected = [
+            (0.0, 0.0, color::BLACK),
+            (0.5, 0.0, color::WHITE),
+            (0.0, 0.5, color::WHITE),
+            (0.5, 0.5, color::BLACK),
+            (1.0, 1.0, color::BLACK),
+        ];
+        for (u, v, color) in expected {
+            assert_eq!(checkers.uv_pattern_at(u, v), color);
+        }
+    }
+
+    #[test]
+    fn align_check_corners() {
+        let main = Color::new(1.0, 1.0, 1.0);
+        let ul = Color::new(1.0, 0.0, 0.0);
+        let ur = Color::new(1.0, 1.0, 0.0);
+        let bl = Color::new(0.0, 1.0, 0.0);
+        let br = Color::new(0.0, 1.0, 1.0);
+        let pattern = UvPattern::align_check(main, ul, ur, bl, br);
+        let expected = [
+            (0.5, 0.5, main),
+            (0.1, 0.9, ul),
+            (0.9, 0.9, ur),
+            (0.1, 0.1, bl),
+            (0.9, 0.1, br),
+        ];
+        for (u, v, color) in expected {
+            assert_eq!(pattern.uv_pattern_at(u, v), color);
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use crate::{color::Color, point::Point};
+
+use super::{noise::Noise, PatternLocal};
+
+// growth rings around the y axis, wobbling with the noise
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct WoodPattern {
+    left_color: Color,
+    right_color: Color,
+    noise: Noise,
+    turbulence: f64,
+}
+
+impl WoodPattern {
+    pub fn new(
+        left_color: Color,
+        right_color: Color,
+        noise: Noise,
+        turbulence: f64,
+    ) -> WoodPattern {
+        WoodPattern {
+            left_color,
+            right_color,
+            noise,
+            turbulence,
+        }
+    }
+}
+
+impl PatternLocal for WoodPattern {
+    fn pattern_at(&self, point: &Point) -> Color {
+        let radius = (point.x() * point.x() + point.z() * point.z()).sqrt();
+        let radius = radius + self.turbulence * self.noise.fbm(point);
+        // every ring slowly darkens to a sharp edge, like a year of growth
+        let fraction = radius.rem_euclid(1.0);
+        self.left_color + (self.right_color - self.left_color) * fraction
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{color, util::assert_float_eq};
+
+    use super::*;
+
+    #[test]
+    fn wood_rings_without_turbulence() {
+        let pattern = WoodPattern::new(color::WHITE, color::BLACK, Noise::new(0), 0.0);
+        assert_eq!(pattern.pattern_at(&Point::new(0.0, 0.0, 0.0)), color::WHITE);
+        assert_float_eq!(pattern.pattern_at(&Point::new(0.6, 5.0, 0.8)).r(), 1.0);
+        assert_float_eq!(pattern.pattern_at(&Point::new(0.0, 0.0, 1.25)).r(), 0.75);
+    }
+
+    #[test]
+    fn turbulence_wobbles_the_rings() {
+        let plain = WoodPattern::new(color::WHITE, color::BLACK, Noise::new(0), 0.0);
+        let wobbly = WoodPattern::new(color::WHITE, color::BLACK, Noise::new(0), 0.3);
+        let point = Point::new(0.33, 0.71, 1.27);
+        assert_ne!(plain.pattern_at(&point), wobbly.pattern_at(&point));
+    }
+}
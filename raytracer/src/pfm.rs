@@ -0,0 +1,150 @@
+use std::io::{BufRead, Write};
+
+use image::{
+    error::{DecodingError, ImageFormatHint},
+    ImageError, ImageResult,
+};
+
+use crate::{canvas::Canvas, color::Color};
+
+// Portable Float Map: a small text header followed by raw 32 bit floats,
+// rows are stored from bottom to top.
+// A negative scale means little endian samples.
+const COLOR_MAGIC: &str = "PF";
+const GRAY_MAGIC: &str = "Pf";
+
+fn decoding_error(message: &str) -> ImageError {
+    ImageError::Decoding(DecodingError::new(
+        ImageFormatHint::Name("PFM".to_string()),
+        message.to_string(),
+    ))
+}
+
+pub(crate) fn write_pfm<W: Write>(writer: &mut W, canvas: &Canvas) -> ImageResult<()> {
+    let header = format!(
+        "{}\n{} {}\n-1.0\n",
+        COLOR_MAGIC,
+        canvas.width(),
+        canvas.height()
+    );
+    writer.write_all(header.as_bytes())?;
+    for row in canvas.rows().iter().rev() {
+        for color in row {
+            for c in [color.r(), color.g(), color.b()] {
+                writer.write_all(&(c as f32).to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// whitespace separated header field, the single whitespace after it is consumed
+fn read_token<R: BufRead>(reader: &mut R) -> ImageResult<String> {
+    let mut token = String::new();
+    let mut byte = [0u8];
+    loop {
+        if reader.read(&mut byte)? == 0 {
+            return Err(decoding_error("unexpected end of header"));
+        }
+        let c = byte[0] as char;
+        match (c.is_ascii_whitespace(), token.is_empty()) {
+            (true, true) => continue,
+            (true, false) => return Ok(token),
+            (false, _) => token.push(c),
+        }
+    }
+}
+
+fn parse_field<T: std::str::FromStr>(token: &str) -> ImageResult<T> {
+    token
+        .parse()
+        .map_err(|_| decoding_error(&format!("invalid header value `{}`", token)))
+}
+
+pub(crate) fn read_pfm<R: BufRead>(reader: &mut R) -> ImageResult<Canvas> {
+    let channels = match read_token(reader)?.as_str() {
+        COLOR_MAGIC => 3,
+        GRAY_MAGIC => 1,
+        _ => return Err(decoding_error("not a portable float map")),
+    };
+    let width: usize = parse_field(&read_token(reader)?)?;
+    let height: usize = parse_field(&read_token(reader)?)?;
+    let scale: f32 = parse_field(&read_token(reader)?)?;
+    let little_endian = scale < 0.0;
+
+    let mut canvas = Canvas::new(width, height);
+    let mut sample = [0u8; 4];
+    let mut values = [0.0; 3];
+    for y in (0..height).rev() {
+        for x in 0..width {
+            for value in values.iter_mut().take(channels) {
+                reader
+                    .read_exact(&mut sample)
+                    .map_err(|_| decoding_error("missing pixel data"))?;
+                let v = match little_endian {
+                    true => f32::from_le_bytes(sample),
+                    false => f32::from_be_bytes(sample),
+                };
+                *value = v as f64;
+            }
+            let color = match channels {
+                3 => Color::new(values[0], values[1], values[2]),
+                _ => Color::new(values[0], values[0], values[0]),
+            };
+            canvas.write_pixel(x, y, &color);
+        }
+    }
+    Ok(canvas)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn pfm_header_and_bottom_up_rows() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.write_pixel(0, 1, &Color::new(2.5, 0.0, -1.0));
+        let mut bytes = vec![];
+        write_pfm(&mut bytes, &canvas).unwrap();
+
+        let header = b"PF\n2 2\n-1.0\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(bytes.len(), header.len() + 2 * 2 * 3 * 4);
+        // the bottom row comes first
+        assert_eq!(
+            &bytes[header.len()..header.len() + 4],
+            &2.5f32.to_le_bytes()
+        );
+    }
+
+    #[test]
+    fn pfm_round_trip_is_lossless() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 0, &Color::new(12.5, 0.25, -0.5));
+        canvas.write_pixel(2, 1, &Color::new(0.0, 1000.0, 0.125));
+        let mut bytes = vec![];
+        write_pfm(&mut bytes, &canvas).unwrap();
+
+        let read = read_pfm(&mut Cursor::new(bytes)).unwrap();
+        assert_eq!((read.width(), read.height()), (3, 2));
+        assert_eq!(read.rows(), canvas.rows());
+    }
+
+    #[test]
+    fn read_big_endian_grayscale_pfm() {
+        let mut bytes = b"Pf\n2 1\n1.0\n".to_vec();
+        bytes.extend(0.5f32.to_be_bytes());
+        bytes.extend(4.0f32.to_be_bytes());
+        let canvas = read_pfm(&mut Cursor::new(bytes)).unwrap();
+        assert_eq!(canvas.color(0, 0), Some(&Color::new(0.5, 0.5, 0.5)));
+        assert_eq!(canvas.color(1, 0), Some(&Color::new(4.0, 4.0, 4.0)));
+    }
+
+    #[test]
+    fn reading_invalid_pfm_fails() {
+        assert!(read_pfm(&mut Cursor::new(b"P6\n1 1\n255\n".to_vec())).is_err());
+        assert!(read_pfm(&mut Cursor::new(b"PF\n2 2\n-1.0\n".to_vec())).is_err());
+    }
+}
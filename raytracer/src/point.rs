@@ -2,8 +2,17 @@ use std::ops::{Add, Sub};
 
 use crate::{transform::Transform, util::equal, vector::Vector};
 
+/// Position in space.
+///
+/// ```
+/// use raytracer::{Point, Vector};
+///
+/// let p = Point::new(1.0, 2.0, 3.0) + Vector::new(1.0, 0.0, 0.0);
+/// assert_eq!(p, Point::new(2.0, 2.0, 3.0));
+/// assert_eq!(p - Point::new(2.0, 0.0, 3.0), Vector::new(0.0, 2.0, 0.0));
+/// ```
 #[derive(Debug, Clone, Copy, Default)]
-pub(crate) struct Point {
+pub struct Point {
     x: f64,
     y: f64,
     z: f64,
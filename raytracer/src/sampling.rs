@@ -0,0 +1,421 @@
+use std::str::FromStr;
+
+use crate::{color::Color, rng::Rng, vector::Vector};
+
+// How sample positions are placed inside a pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SamplingStrategy {
+    #[default]
+    Regular,
+    Jittered,
+    Random,
+}
+
+impl FromStr for SamplingStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "regular" => Ok(SamplingStrategy::Regular),
+            "jittered" | "stratified" => Ok(SamplingStrategy::Jittered),
+            "random" => Ok(SamplingStrategy::Random),
+            s => Err(format!(
+                "unknown sampling `{}`, expected one of `regular`, `jittered`, `random`",
+                s
+            )),
+        }
+    }
+}
+
+// Reconstruction filter, used to weight samples by their distance to the pixel center.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Filter {
+    #[default]
+    Box,
+    Tent,
+    Gaussian,
+}
+
+const GAUSSIAN_ALPHA: f64 = 2.0;
+
+impl Filter {
+    // half width of the filter in pixels, samples are spread over the whole filter
+    pub fn radius(&self) -> f64 {
+        match self {
+            Filter::Box => 0.5,
+            Filter::Tent => 1.0,
+            Filter::Gaussian => 1.5,
+        }
+    }
+
+    // `dx` and `dy` are offsets from the pixel center, in pixels
+    pub fn weight(&self, dx: f64, dy: f64) -> f64 {
+        let radius = self.radius();
+        match self {
+            Filter::Box => 1.0,
+            Filter::Tent => {
+                let tent = |d: f64| (1.0 - d.abs() / radius).max(0.0);
+                tent(dx) * tent(dy)
+            }
+            Filter::Gaussian => {
+                let edge = f64::exp(-GAUSSIAN_ALPHA * radius * radius);
+                let gaussian = |d: f64| (f64::exp(-GAUSSIAN_ALPHA * d * d) - edge).max(0.0);
+                gaussian(dx) * gaussian(dy)
+            }
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "box" => Ok(Filter::Box),
+            "tent" => Ok(Filter::Tent),
+            "gaussian" => Ok(Filter::Gaussian),
+            s => Err(format!(
+                "unknown filter `{}`, expected one of `box`, `tent`, `gaussian`",
+                s
+            )),
+        }
+    }
+}
+
+// Direction around `normal` with a density proportional to the cosine of
+// their angle, from two uniform numbers in [0, 1).
+pub(crate) fn cosine_hemisphere(normal: &Vector, u1: f64, u2: f64) -> Vector {
+    let cos_theta = (1.0 - u1).max(0.0).sqrt();
+    around_normal(normal, cos_theta, 2.0 * std::f64::consts::PI * u2)
+}
+
+// Direction inside the cone of directions within `cos_max` of `axis`, uniformly
+// distributed over the cone's solid angle.
+pub(crate) fn uniform_cone(axis: &Vector, cos_max: f64, u1: f64, u2: f64) -> Vector {
+    let cos_theta = 1.0 - u1 * (1.0 - cos_max);
+    around_normal(axis, cos_theta, 2.0 * std::f64::consts::PI * u2)
+}
+
+// Direction at angle theta from `normal`, turned by phi around it.
+pub(crate) fn around_normal(normal: &Vector, cos_theta: f64, phi: f64) -> Vector {
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let helper = match normal.x().abs() > 0.9 {
+        true => Vector::new(0.0, 1.0, 0.0),
+        false => Vector::new(1.0, 0.0, 0.0),
+    };
+    let tangent = helper.cross(normal).normalize();
+    let bitangent = normal.cross(&tangent);
+    (tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + *normal * cos_theta)
+        .normalize()
+}
+
+// Position of a sample relative to the top left corner of its pixel, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PixelSample {
+    pub x: f64,
+    pub y: f64,
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sampler {
+    samples: usize,
+    strategy: SamplingStrategy,
+    filter: Filter,
+    seed: u64,
+}
+
+impl Default for Sampler {
+    // one ray through the pixel center
+    fn default() -> Self {
+        Sampler {
+            samples: 1,
+            strategy: SamplingStrategy::default(),
+            filter: Filter::default(),
+            seed: 0,
+        }
+    }
+}
+
+impl Sampler {
+    pub fn new(samples: usize, strategy: SamplingStrategy, filter: Filter, seed: u64) -> Sampler {
+        Sampler {
+            samples: samples.max(1),
+            strategy,
+            filter,
+            seed,
+        }
+    }
+
+    pub fn requested_samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Samples actually shot through every pixel, the requested number rounded
+    /// up to a square for the regular and jittered grids.
+    pub fn samples(&self) -> usize {
+        match self.strategy {
+            SamplingStrategy::Random => self.samples,
+            SamplingStrategy::Regular | SamplingStrategy::Jittered => {
+                self.grid_size() * self.grid_size()
+            }
+        }
+    }
+
+    pub fn strategy(&self) -> SamplingStrategy {
+        self.strategy
+    }
+
+    pub fn filter(&self) -> Filter {
+        self.filter
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn with_samples(mut self, samples: usize) -> Sampler {
+        self.samples = samples.max(1);
+        self
+    }
+
+    pub fn with_strategy(mut self, strategy: SamplingStrategy) -> Sampler {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Sampler {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Sampler {
+        self.seed = seed;
+        self
+    }
+
+    // regular and jittered sampling use a square grid,
+    // so the number of samples is rounded up to the next square
+    fn grid_size(&self) -> usize {
+        ((self.samples as f64).sqrt().ceil() as usize).max(1)
+    }
+
+    // positions inside the unit square, before the filter footprint is applied
+    fn unit_positions(&self, rng: &mut Rng) -> Vec<(f64, f64)> {
+        match self.strategy {
+            SamplingStrategy::Random => (0..self.samples)
+                .map(|_| (rng.next_f64(), rng.next_f64()))
+                .collect(),
+            SamplingStrategy::Regular | SamplingStrategy::Jittered => {
+                let n = self.grid_size();
+                let cell = 1.0 / n as f64;
+                (0..n * n)
+                    .map(|i| {
+                        let (col, row) = ((i % n) as f64, (i / n) as f64);
+                        let (du, dv) = match self.strategy {
+                            SamplingStrategy::Jittered => (rng.next_f64(), rng.next_f64()),
+                            _ => (0.5, 0.5),
+                        };
+                        ((col + du) * cell, (row + dv) * cell)
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    // samples for pixel (`x`, `y`), the same pixel and seed always give the same samples
+    pub(crate) fn pixel_samples(&self, x: usize, y: usize) -> Vec<PixelSample> {
+        let stream = ((y as u64) << 32) | x as u64;
+        let mut rng = Rng::with_stream(self.seed, stream);
+        let radius = self.filter.radius();
+        self.unit_positions(&mut rng)
+            .into_iter()
+            .map(|(u, v)| {
+                let (dx, dy) = ((u - 0.5) * 2.0 * radius, (v - 0.5) * 2.0 * radius);
+                PixelSample {
+                    x: 0.5 + dx,
+                    y: 0.5 + dy,
+                    weight: self.filter.weight(dx, dy),
+                }
+            })
+            .collect()
+    }
+}
+
+// Contrast driven antialiasing: every pixel gets one sample first, pixels whose color
+// differs from a neighbor by more than `threshold` are split in four up to `max_depth` times.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Adaptive {
+    threshold: f64,
+    max_depth: usize,
+}
+
+impl Default for Adaptive {
+    fn default() -> Self {
+        Adaptive {
+            threshold: 0.1,
+            max_depth: 2,
+        }
+    }
+}
+
+impl Adaptive {
+    pub fn new(threshold: f64, max_depth: usize) -> Adaptive {
+        Adaptive {
+            threshold,
+            max_depth,
+        }
+    }
+
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Adaptive {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Adaptive {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn is_contrasted(&self, a: &Color, b: &Color) -> bool {
+        a.contrast(b) > self.threshold
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::util::assert_float_eq;
+
+    use super::*;
+
+    #[test]
+    fn default_sampler_shoots_through_pixel_center() {
+        let samples = Sampler::default().pixel_samples(3, 4);
+        assert_eq!(
+            samples,
+            vec![PixelSample {
+                x: 0.5,
+                y: 0.5,
+                weight: 1.0
+            }]
+        );
+    }
+
+    #[test]
+    fn regular_sampling_uses_square_grid() {
+        let sampler = Sampler::new(4, SamplingStrategy::Regular, Filter::Box, 0);
+        let samples = sampler.pixel_samples(0, 0);
+        let positions: Vec<(f64, f64)> = samples.iter().map(|s| (s.x, s.y)).collect();
+        assert_eq!(
+            positions,
+            vec![(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)]
+        );
+
+        let sampler = sampler.with_samples(5);
+        assert_eq!(sampler.pixel_samples(0, 0).len(), 9);
+    }
+
+    #[test]
+    fn grid_sample_counts_round_up_to_a_square() {
+        let sampler = Sampler::new(1, SamplingStrategy::Jittered, Filter::Box, 0);
+        for (requested, used) in [(1, 1), (2, 4), (3, 4), (4, 4), (7, 9), (10, 16)] {
+            let sampler = sampler.with_samples(requested);
+            assert_eq!(sampler.requested_samples(), requested);
+            assert_eq!(sampler.samples(), used);
+            assert_eq!(sampler.pixel_samples(3, 5).len(), used);
+        }
+        let random = sampler
+            .with_strategy(SamplingStrategy::Random)
+            .with_samples(7);
+        assert_eq!(random.samples(), 7);
+    }
+
+    #[test]
+    fn jittered_samples_stay_in_their_cell() {
+        let sampler = Sampler::new(16, SamplingStrategy::Jittered, Filter::Box, 7);
+        let samples = sampler.pixel_samples(10, 20);
+        assert_eq!(samples.len(), 16);
+        for (i, s) in samples.iter().enumerate() {
+            let (col, row) = ((i % 4) as f64, (i / 4) as f64);
+            assert!(s.x >= col * 0.25 && s.x < (col + 1.0) * 0.25);
+            assert!(s.y >= row * 0.25 && s.y < (row + 1.0) * 0.25);
+        }
+    }
+
+    #[test]
+    fn random_sampling_is_deterministic_for_a_seed() {
+        let sampler = Sampler::new(6, SamplingStrategy::Random, Filter::Box, 42);
+        assert_eq!(sampler.pixel_samples(1, 2), sampler.pixel_samples(1, 2));
+        assert_eq!(sampler.pixel_samples(1, 2).len(), 6);
+        assert_ne!(sampler.pixel_samples(1, 2), sampler.pixel_samples(2, 1));
+        let other = sampler.with_seed(43);
+        assert_ne!(sampler.pixel_samples(1, 2), other.pixel_samples(1, 2));
+    }
+
+    #[test]
+    fn filters_weight_samples_by_distance() {
+        assert_float_eq!(Filter::Box.weight(0.4, -0.4), 1.0);
+        assert_float_eq!(Filter::Tent.weight(0.0, 0.0), 1.0);
+        assert_float_eq!(Filter::Tent.weight(0.5, 0.0), 0.5);
+        assert_float_eq!(Filter::Tent.weight(1.0, 0.0), 0.0);
+        assert!(Filter::Gaussian.weight(0.0, 0.0) > Filter::Gaussian.weight(0.5, 0.5));
+        assert_float_eq!(Filter::Gaussian.weight(1.5, 0.0), 0.0);
+    }
+
+    #[test]
+    fn wide_filters_spread_samples_over_neighbor_pixels() {
+        let sampler = Sampler::new(4, SamplingStrategy::Regular, Filter::Tent, 0);
+        let samples = sampler.pixel_samples(0, 0);
+        assert_float_eq!(samples[0].x, 0.0);
+        assert_float_eq!(samples[3].y, 1.0);
+        assert_float_eq!(samples[0].weight, 0.25);
+    }
+
+    #[test]
+    fn parsing_strategy_and_filter_names() {
+        assert_eq!("jittered".parse(), Ok(SamplingStrategy::Jittered));
+        assert_eq!("stratified".parse(), Ok(SamplingStrategy::Jittered));
+        assert_eq!("gaussian".parse(), Ok(Filter::Gaussian));
+        assert!("lanczos".parse::<Filter>().is_err());
+    }
+
+    #[test]
+    fn cosine_hemisphere_directions() {
+        let normal = Vector::new(1.0, 1.0, 0.0).normalize();
+        let mut rng = Rng::new(11);
+        let n = 4000;
+        let mut total_cos = 0.0;
+        for _ in 0..n {
+            let direction = cosine_hemisphere(&normal, rng.next_f64(), rng.next_f64());
+            assert_float_eq!(direction.magnitude(), 1.0);
+            let cos = direction.dot(&normal);
+            assert!(cos >= 0.0);
+            total_cos += cos;
+        }
+        // the mean cosine of a cosine weighted hemisphere is 2/3
+        assert!((total_cos / n as f64 - 2.0 / 3.0).abs() < 0.02);
+        assert_eq!(cosine_hemisphere(&normal, 0.0, 0.3), normal);
+    }
+
+    #[test]
+    fn uniform_cone_directions() {
+        let axis = Vector::new(0.0, 1.0, 1.0).normalize();
+        let cos_max = 0.8;
+        let mut rng = Rng::new(5);
+        for _ in 0..1000 {
+            let direction = uniform_cone(&axis, cos_max, rng.next_f64(), rng.next_f64());
+            assert_float_eq!(direction.magnitude(), 1.0);
+            assert!(direction.dot(&axis) >= cos_max - 1e-9);
+        }
+        assert_eq!(uniform_cone(&axis, 1.0, 0.6, 0.3), axis);
+    }
+}
@@ -0,0 +1,291 @@
+use crate::{
+    point::Point,
+    ray::Ray,
+    transform::Transform,
+    util::{check_axis, INFINITY},
+};
+
+// Axis-aligned bounding box, an empty box has minimum greater than maximum.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct BoundingBox {
+    minimum: Point,
+    maximum: Point,
+}
+
+impl Default for BoundingBox {
+    fn default() -> Self {
+        Self {
+            minimum: Point::new(INFINITY, INFINITY, INFINITY),
+            maximum: Point::new(-INFINITY, -INFINITY, -INFINITY),
+        }
+    }
+}
+
+impl BoundingBox {
+    pub fn new(minimum: Point, maximum: Point) -> BoundingBox {
+        BoundingBox { minimum, maximum }
+    }
+
+    #[cfg(test)]
+    pub fn infinite() -> BoundingBox {
+        BoundingBox::new(
+            Point::new(-INFINITY, -INFINITY, -INFINITY),
+            Point::new(INFINITY, INFINITY, INFINITY),
+        )
+    }
+
+    pub fn minimum(&self) -> Point {
+        self.minimum
+    }
+
+    pub fn maximum(&self) -> Point {
+        self.maximum
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.minimum.x() > self.maximum.x()
+            || self.minimum.y() > self.maximum.y()
+            || self.minimum.z() > self.maximum.z()
+    }
+
+    // a box reaching `INFINITY` on any axis can not be put into a hierarchy
+    pub fn is_bounded(&self) -> bool {
+        let finite = |v: f64| v > -INFINITY && v < INFINITY;
+        self.is_empty()
+            || [
+                self.minimum.x(),
+                self.minimum.y(),
+                self.minimum.z(),
+                self.maximum.x(),
+                self.maximum.y(),
+                self.maximum.z(),
+            ]
+            .into_iter()
+            .all(finite)
+    }
+
+    pub fn add_point(&mut self, point: &Point) {
+        self.minimum = Point::new(
+            self.minimum.x().min(point.x()),
+            self.minimum.y().min(point.y()),
+            self.minimum.z().min(point.z()),
+        );
+        self.maximum = Point::new(
+            self.maximum.x().max(point.x()),
+            self.maximum.y().max(point.y()),
+            self.maximum.z().max(point.z()),
+        );
+    }
+
+    pub fn merge(mut self, other: &BoundingBox) -> BoundingBox {
+        if !other.is_empty() {
+            self.add_point(&other.minimum);
+            self.add_point(&other.maximum);
+        }
+        self
+    }
+
+    #[cfg(test)]
+    pub fn contains_point(&self, point: &Point) -> bool {
+        self.minimum.x() <= point.x()
+            && point.x() <= self.maximum.x()
+            && self.minimum.y() <= point.y()
+            && point.y() <= self.maximum.y()
+            && self.minimum.z() <= point.z()
+            && point.z() <= self.maximum.z()
+    }
+
+    #[cfg(test)]
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains_point(&other.minimum) && self.contains_point(&other.maximum)
+    }
+
+    pub fn centroid(&self) -> Point {
+        Point::new(
+            (self.minimum.x() + self.maximum.x()) / 2.0,
+            (self.minimum.y() + self.maximum.y()) / 2.0,
+            (self.minimum.z() + self.maximum.z()) / 2.0,
+        )
+    }
+
+    // index of the longest axis: 0 for x, 1 for y, 2 for z
+    pub fn longest_axis(&self) -> usize {
+        let extent = self.maximum - self.minimum;
+        if extent.x() >= extent.y() && extent.x() >= extent.z() {
+            0
+        } else if extent.y() >= extent.z() {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn transform(&self, transformation: Transform) -> BoundingBox {
+        if self.is_empty() {
+            return *self;
+        }
+        let (min, max) = (self.minimum, self.maximum);
+        [
+            Point::new(min.x(), min.y(), min.z()),
+            Point::new(min.x(), min.y(), max.z()),
+            Point::new(min.x(), max.y(), min.z()),
+            Point::new(min.x(), max.y(), max.z()),
+            Point::new(max.x(), min.y(), min.z()),
+            Point::new(max.x(), min.y(), max.z()),
+            Point::new(max.x(), max.y(), min.z()),
+            Point::new(max.x(), max.y(), max.z()),
+        ]
+        .iter()
+        .fold(BoundingBox::default(), |mut bounds, corner| {
+            bounds.add_point(&corner.transform(transformation));
+            bounds
+        })
+    }
+
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let (origin, direction) = (ray.origin(), ray.direction());
+        let (xtmin, xtmax) = check_axis(
+            origin.x(),
+            direction.x(),
+            self.minimum.x(),
+            self.maximum.x(),
+        );
+        let (ytmin, ytmax) = check_axis(
+            origin.y(),
+            direction.y(),
+            self.minimum.y(),
+            self.maximum.y(),
+        );
+        let (ztmin, ztmax) = check_axis(
+            origin.z(),
+            direction.z(),
+            self.minimum.z(),
+            self.maximum.z(),
+        );
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        tmin <= tmax
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::vector::Vector;
+
+    use super::*;
+
+    #[test]
+    fn creating_an_empty_bounding_box() {
+        let b = BoundingBox::default();
+        assert!(b.is_empty());
+        assert!(b.is_bounded());
+    }
+
+    #[test]
+    fn adding_points_to_an_empty_bounding_box() {
+        let mut b = BoundingBox::default();
+        b.add_point(&Point::new(-5.0, 2.0, 0.0));
+        b.add_point(&Point::new(7.0, 0.0, -3.0));
+        assert_eq!(b.minimum(), Point::new(-5.0, 0.0, -3.0));
+        assert_eq!(b.maximum(), Point::new(7.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn merging_bounding_boxes() {
+        let b1 = BoundingBox::new(Point::new(-5.0, -2.0, 0.0), Point::new(7.0, 4.0, 4.0));
+        let b2 = BoundingBox::new(Point::new(8.0, -7.0, -2.0), Point::new(14.0, 2.0, 8.0));
+        let b = b1.merge(&b2);
+        assert_eq!(b.minimum(), Point::new(-5.0, -7.0, -2.0));
+        assert_eq!(b.maximum(), Point::new(14.0, 4.0, 8.0));
+    }
+
+    #[test]
+    fn checking_whether_box_contains_point_or_box() {
+        let b = BoundingBox::new(Point::new(5.0, -2.0, 0.0), Point::new(11.0, 4.0, 7.0));
+        assert!(b.contains_point(&Point::new(5.0, -2.0, 0.0)));
+        assert!(b.contains_point(&Point::new(8.0, 1.0, 3.0)));
+        assert!(!b.contains_point(&Point::new(3.0, 0.0, 3.0)));
+        assert!(!b.contains_point(&Point::new(8.0, 1.0, 8.0)));
+
+        let inner = BoundingBox::new(Point::new(6.0, -1.0, 1.0), Point::new(10.0, 3.0, 6.0));
+        let outer = BoundingBox::new(Point::new(4.0, -3.0, -1.0), Point::new(10.0, 3.0, 6.0));
+        assert!(b.contains_box(&inner));
+        assert!(!b.contains_box(&outer));
+    }
+
+    #[test]
+    fn transforming_a_bounding_box() {
+        let b = BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+        let t = Transform::rotation_y(std::f64::consts::FRAC_PI_4)
+            .rotate_x(std::f64::consts::FRAC_PI_4);
+        let b = b.transform(t);
+        let (x, yz) = (
+            std::f64::consts::SQRT_2,
+            1.0 + std::f64::consts::FRAC_1_SQRT_2,
+        );
+        assert_eq!(b.minimum(), Point::new(-x, -yz, -yz));
+        assert_eq!(b.maximum(), Point::new(x, yz, yz));
+    }
+
+    #[test]
+    fn infinite_box_is_not_bounded() {
+        assert!(!BoundingBox::infinite().is_bounded());
+        let b = BoundingBox::new(Point::new(-1.0, -INFINITY, -1.0), Point::new(1.0, 1.0, 1.0));
+        assert!(!b.is_bounded());
+    }
+
+    #[test]
+    fn intersecting_ray_with_bounding_box() {
+        let b = BoundingBox::new(Point::new(5.0, -2.0, 0.0), Point::new(11.0, 4.0, 7.0));
+        let test_intersect = |origin: Point, direction: Vector, expected: bool| {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(b.intersects(&r), expected);
+        };
+
+        test_intersect(
+            Point::new(15.0, 1.0, 2.0),
+            Vector::new(-1.0, 0.0, 0.0),
+            true,
+        );
+        test_intersect(
+            Point::new(-5.0, -1.0, 4.0),
+            Vector::new(1.0, 0.0, 0.0),
+            true,
+        );
+        test_intersect(Point::new(7.0, 6.0, 5.0), Vector::new(0.0, -1.0, 0.0), true);
+        test_intersect(Point::new(9.0, -5.0, 6.0), Vector::new(0.0, 1.0, 0.0), true);
+        test_intersect(
+            Point::new(8.0, 2.0, 12.0),
+            Vector::new(0.0, 0.0, -1.0),
+            true,
+        );
+        test_intersect(Point::new(6.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0), true);
+        test_intersect(Point::new(8.0, 1.0, 3.5), Vector::new(0.0, 0.0, 1.0), true);
+        test_intersect(
+            Point::new(9.0, -1.0, -8.0),
+            Vector::new(2.0, 4.0, 6.0),
+            false,
+        );
+        test_intersect(
+            Point::new(8.0, 3.0, -4.0),
+            Vector::new(6.0, 2.0, 4.0),
+            false,
+        );
+        test_intersect(
+            Point::new(9.0, -1.0, -2.0),
+            Vector::new(4.0, 6.0, 2.0),
+            false,
+        );
+        test_intersect(
+            Point::new(18.0, 3.0, 7.0),
+            Vector::new(0.0, 0.0, -1.0),
+            false,
+        );
+    }
+}
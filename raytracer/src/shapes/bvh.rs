@@ -0,0 +1,202 @@
+use crate::{intersect::multiple_intersections::Intersections, ray::Ray};
+
+use super::{bounds::BoundingBox, shape::Shape, ShapeWorld};
+
+// maximum number of shapes kept inside a leaf before it is split
+const LEAF_SIZE: usize = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+enum BvhNode {
+    Leaf {
+        bounds: BoundingBox,
+        indices: Vec<usize>,
+    },
+    Branch {
+        bounds: BoundingBox,
+        left: Box<BvhNode>,
+        right: Box<BvhNode>,
+    },
+}
+
+impl BvhNode {
+    fn build(mut entries: Vec<(usize, BoundingBox)>) -> BvhNode {
+        let bounds = entries
+            .iter()
+            .fold(BoundingBox::default(), |bounds, (_, b)| bounds.merge(b));
+
+        if entries.len() <= LEAF_SIZE {
+            let indices = entries.iter().map(|(index, _)| *index).collect();
+            return BvhNode::Leaf { bounds, indices };
+        }
+
+        // split at the median centroid along the longest axis of the centroids
+        let centroid_bounds =
+            entries
+                .iter()
+                .fold(BoundingBox::default(), |mut centroids, (_, b)| {
+                    centroids.add_point(&b.centroid());
+                    centroids
+                });
+        let axis = centroid_bounds.longest_axis();
+        let key = |b: &BoundingBox| {
+            let c = b.centroid();
+            [c.x(), c.y(), c.z()][axis]
+        };
+        entries.sort_unstable_by(|(_, a), (_, b)| key(a).total_cmp(&key(b)));
+
+        let right = entries.split_off(entries.len() / 2);
+        BvhNode::Branch {
+            bounds,
+            left: Box::new(BvhNode::build(entries)),
+            right: Box::new(BvhNode::build(right)),
+        }
+    }
+
+    fn bounds(&self) -> &BoundingBox {
+        match self {
+            BvhNode::Leaf { bounds, .. } => bounds,
+            BvhNode::Branch { bounds, .. } => bounds,
+        }
+    }
+
+    fn intersect<'a>(&self, shapes: &'a [Shape], ray: &Ray) -> Intersections<'a> {
+        // bounding boxes are tested against the whole line, not only the positive half,
+        // so intersections behind the origin are still reported for refractive indices
+        if !self.bounds().intersects(ray) {
+            return Intersections::default();
+        }
+        match self {
+            BvhNode::Leaf { indices, .. } => indices
+                .iter()
+                .map(|&index| shapes[index].intersect(ray))
+                .fold(Intersections::default(), |merged, xs| merged.merge(xs)),
+            BvhNode::Branch { left, right, .. } => left
+                .intersect(shapes, ray)
+                .merge(right.intersect(shapes, ray)),
+        }
+    }
+}
+
+// Bounding volume hierarchy over a list of shapes, refering to them by index.
+// Unbounded shapes (planes, uncapped cylinders or cones) are kept outside the tree
+// and always tested.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct Bvh {
+    root: Option<BvhNode>,
+    unbounded: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn new(shapes: &[Shape]) -> Bvh {
+        let mut entries = Vec::with_capacity(shapes.len());
+        let mut unbounded = Vec::new();
+        for (index, shape) in shapes.iter().enumerate() {
+            let bounds = shape.bounds();
+            if shape.is_bounded() {
+                entries.push((index, bounds));
+            } else {
+                unbounded.push(index);
+            }
+        }
+        let root = match entries.is_empty() {
+            true => None,
+            false => Some(BvhNode::build(entries)),
+        };
+        Bvh { root, unbounded }
+    }
+
+    #[cfg(test)]
+    pub fn bounds(&self) -> BoundingBox {
+        self.root
+            .as_ref()
+            .map_or(BoundingBox::default(), |root| *root.bounds())
+    }
+
+    pub fn intersect<'a>(&self, shapes: &'a [Shape], ray: &Ray) -> Intersections<'a> {
+        let bounded = self
+            .root
+            .as_ref()
+            .map(|root| root.intersect(shapes, ray))
+            .unwrap_or_default();
+        self.unbounded
+            .iter()
+            .map(|&index| shapes[index].intersect(ray))
+            .fold(bounded, |merged, xs| merged.merge(xs))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::{
+        point::Point,
+        transform::{Transform, Transformable},
+        vector::Vector,
+    };
+
+    use super::*;
+
+    fn spheres_along_x(count: usize) -> Vec<Shape> {
+        (0..count)
+            .map(|i| {
+                Shape::sphere().with_transform(Transform::translation(3.0 * i as f64, 0.0, 0.0))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unbounded_shapes_are_kept_outside_the_tree() {
+        let shapes = vec![Shape::sphere(), Shape::plane(), Shape::cylinder()];
+        let bvh = Bvh::new(&shapes);
+        assert_eq!(bvh.unbounded, vec![1, 2]);
+        match bvh.root {
+            Some(BvhNode::Leaf { indices, .. }) => assert_eq!(indices, vec![0]),
+            _ => panic!("expected a single leaf"),
+        }
+    }
+
+    #[test]
+    fn large_list_of_shapes_is_split() {
+        let shapes = spheres_along_x(20);
+        let bvh = Bvh::new(&shapes);
+        assert!(matches!(bvh.root, Some(BvhNode::Branch { .. })));
+        assert_eq!(bvh.bounds().minimum(), Point::new(-1.0, -1.0, -1.0));
+        assert_eq!(bvh.bounds().maximum(), Point::new(58.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn intersecting_through_the_hierarchy_matches_brute_force() {
+        let mut shapes = spheres_along_x(20);
+        shapes.push(Shape::plane().with_transform(Transform::translation(0.0, -1.0, 0.0)));
+        let bvh = Bvh::new(&shapes);
+
+        let rays = [
+            Ray::new(Point::new(-5.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0)),
+            Ray::new(Point::new(30.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(
+                Point::new(31.5, 5.0, -5.0),
+                Vector::new(0.0, -1.0, 1.0).normalize(),
+            ),
+            Ray::new(Point::new(0.0, 5.0, 0.0), Vector::new(0.0, 0.0, 1.0)),
+        ];
+        for ray in rays {
+            let expected = shapes
+                .iter()
+                .map(|shape| shape.intersect(&ray))
+                .fold(Intersections::default(), |merged, xs| merged.merge(xs));
+            let xs = bvh.intersect(&shapes, &ray);
+            assert_eq!(xs.count(), expected.count());
+            for i in 0..xs.count() {
+                assert_eq!(xs.get(i), expected.get(i));
+            }
+        }
+    }
+
+    #[test]
+    fn ray_missing_every_bounding_box_has_no_intersection() {
+        let shapes = spheres_along_x(20);
+        let bvh = Bvh::new(&shapes);
+        let r = Ray::new(Point::new(0.0, 5.0, -5.0), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(bvh.intersect(&shapes, &r).count(), 0);
+    }
+}
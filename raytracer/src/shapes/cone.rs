@@ -6,7 +6,7 @@ use crate::{
     vector::Vector,
 };
 
-use super::ShapeLocal;
+use super::{bounds::BoundingBox, ShapeLocal};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) struct Cone {
@@ -106,13 +106,21 @@ impl ShapeLocal for Cone {
             false => solve_quadratic_equation(a, b, c),
         };
 
-        roots.extend(cap_roots.into_iter());
+        roots.extend(cap_roots);
         roots.extend(normal_roots.into_iter().filter(|&t| {
             let y = local_ray.position(t).y();
             self.minimum < y && y < self.maximum
         }));
         roots
     }
+
+    fn local_bounds(&self) -> BoundingBox {
+        let r = self.radius(self.minimum).max(self.radius(self.maximum));
+        BoundingBox::new(
+            Point::new(-r, self.minimum, -r),
+            Point::new(r, self.maximum, r),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -214,6 +222,16 @@ mod test {
         );
     }
 
+    #[test]
+    fn bounding_box_of_cone() {
+        assert!(!Cone::default().local_bounds().is_bounded());
+
+        let b = Cone::new(-5.0, 3.0).local_bounds();
+        assert!(b.is_bounded());
+        assert_eq!(b.minimum(), Point::new(-5.0, -5.0, -5.0));
+        assert_eq!(b.maximum(), Point::new(5.0, 3.0, 5.0));
+    }
+
     #[test]
     fn computing_normal_vector_on_cone() {
         let shape = Cone::default();
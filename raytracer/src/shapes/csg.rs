@@ -0,0 +1,229 @@
+use crate::{intersect::multiple_intersections::Intersections, ray::Ray};
+
+use super::{bounds::BoundingBox, shape::Shape, ShapeWorld};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOperation {
+    // `left_hit` tells which child is hit, `inside_left` and `inside_right`
+    // whether the hit happens inside the other children
+    pub fn intersection_allowed(
+        &self,
+        left_hit: bool,
+        inside_left: bool,
+        inside_right: bool,
+    ) -> bool {
+        match self {
+            CsgOperation::Union => (left_hit && !inside_right) || (!left_hit && !inside_left),
+            CsgOperation::Intersection => (left_hit && inside_right) || (!left_hit && inside_left),
+            CsgOperation::Difference => (left_hit && !inside_right) || (!left_hit && inside_left),
+        }
+    }
+}
+
+// Constructive solid geometry, children are stored with their world transform like in groups.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Csg {
+    operation: CsgOperation,
+    left: Box<Shape>,
+    right: Box<Shape>,
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: Shape, right: Shape) -> Csg {
+        Csg {
+            operation,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn operation(&self) -> CsgOperation {
+        self.operation
+    }
+
+    pub fn left(&self) -> &Shape {
+        &self.left
+    }
+
+    pub fn right(&self) -> &Shape {
+        &self.right
+    }
+
+    pub fn left_mut(&mut self) -> &mut Shape {
+        &mut self.left
+    }
+
+    pub fn right_mut(&mut self) -> &mut Shape {
+        &mut self.right
+    }
+
+    pub fn includes(&self, object: &Shape) -> bool {
+        self.left.includes(object) || self.right.includes(object)
+    }
+
+    pub fn bounds(&self) -> BoundingBox {
+        self.left.bounds().merge(&self.right.bounds())
+    }
+
+    // every intersection is kept, including negative ones, so the walk starts outside of both children
+    pub fn filter_intersections<'a>(&self, xs: Intersections<'a>) -> Intersections<'a> {
+        let mut inside_left = false;
+        let mut inside_right = false;
+        xs.filter(|comp| {
+            let left_hit = self.left.includes(comp.object());
+            let allowed = self
+                .operation
+                .intersection_allowed(left_hit, inside_left, inside_right);
+            match left_hit {
+                true => inside_left = !inside_left,
+                false => inside_right = !inside_right,
+            }
+            allowed
+        })
+    }
+
+    pub fn intersect<'a>(&'a self, ray: &Ray) -> Intersections<'a> {
+        let xs = self.left.intersect(ray).merge(self.right.intersect(ray));
+        self.filter_intersections(xs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::{
+        point::Point,
+        transform::{Transform, Transformable},
+        vector::Vector,
+    };
+
+    use super::*;
+
+    #[test]
+    fn evaluating_rule_for_csg_operations() {
+        let rules = [
+            (
+                CsgOperation::Union,
+                [false, true, false, true, false, false, true, true],
+            ),
+            (
+                CsgOperation::Intersection,
+                [true, false, true, false, true, true, false, false],
+            ),
+            (
+                CsgOperation::Difference,
+                [false, true, false, true, true, true, false, false],
+            ),
+        ];
+        let cases = [
+            (true, true, true),
+            (true, true, false),
+            (true, false, true),
+            (true, false, false),
+            (false, true, true),
+            (false, true, false),
+            (false, false, true),
+            (false, false, false),
+        ];
+        for (operation, expected) in rules {
+            for ((lhit, inl, inr), result) in cases.into_iter().zip(expected) {
+                assert_eq!(operation.intersection_allowed(lhit, inl, inr), result);
+            }
+        }
+    }
+
+    #[test]
+    fn filtering_list_of_intersections() {
+        let expected = [
+            (CsgOperation::Union, [0, 3]),
+            (CsgOperation::Intersection, [1, 2]),
+            (CsgOperation::Difference, [0, 1]),
+        ];
+        for (operation, [x0, x1]) in expected {
+            let cube = Shape::cube().with_transform(Transform::translation(0.0, 0.0, 0.5));
+            let c = Shape::csg(operation, Shape::sphere(), cube);
+            let csg = c.as_csg().unwrap();
+            let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+            // hits alternate between the sphere and the cube
+            let xs = csg.left().intersect(&r).merge(csg.right().intersect(&r));
+            let all: Vec<f64> = (0..xs.count()).map(|i| xs.get(i).unwrap().t()).collect();
+            let result = csg.filter_intersections(xs);
+            assert_eq!(result.count(), 2);
+            assert_eq!(result.get(0).unwrap().t(), all[x0]);
+            assert_eq!(result.get(1).unwrap().t(), all[x1]);
+        }
+    }
+
+    #[test]
+    fn ray_misses_csg_object() {
+        let c = Shape::csg(CsgOperation::Union, Shape::sphere(), Shape::cube());
+        let r = Ray::new(Point::new(0.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(c.intersect(&r).count(), 0);
+    }
+
+    #[test]
+    fn ray_hits_csg_object() {
+        let s1 = Shape::sphere();
+        let s2 = Shape::sphere().with_transform(Transform::translation(0.0, 0.0, 0.5));
+        let c = Shape::csg(CsgOperation::Union, s1, s2);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = c.intersect(&r);
+        let csg = c.as_csg().unwrap();
+        assert_eq!(xs.count(), 2);
+        assert_eq!(xs.get(0).unwrap().t(), 4.0);
+        assert_eq!(xs.get(0).unwrap().object(), csg.left());
+        assert_eq!(xs.get(1).unwrap().t(), 6.5);
+        assert_eq!(xs.get(1).unwrap().object(), csg.right());
+    }
+
+    #[test]
+    fn cube_with_spherical_hole() {
+        let cube = Shape::cube();
+        let sphere = Shape::sphere().with_transform(Transform::scaling(1.3, 1.3, 1.3));
+        let c = Shape::csg(CsgOperation::Difference, cube, sphere);
+
+        // through the center, the sphere removes the whole inside of the cube
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(c.intersect(&r).count(), 0);
+
+        // near the corner, the cube is still solid
+        let r = Ray::new(Point::new(0.95, 0.95, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = c.intersect(&r);
+        assert_eq!(xs.count(), 2);
+        assert_eq!(xs.get(0).unwrap().object(), c.as_csg().unwrap().left());
+    }
+
+    #[test]
+    fn csg_children_take_csg_transform() {
+        let c = Shape::csg(CsgOperation::Union, Shape::sphere(), Shape::cube())
+            .transformed_by(Transform::translation(5.0, 0.0, 0.0));
+        let csg = c.as_csg().unwrap();
+        assert_eq!(
+            csg.left().transform(),
+            Transform::translation(5.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            csg.right().transform(),
+            Transform::translation(5.0, 0.0, 0.0)
+        );
+        let b = c.bounds();
+        assert_eq!(b.minimum(), Point::new(4.0, -1.0, -1.0));
+        assert_eq!(b.maximum(), Point::new(6.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn csg_transform_moves_its_children() {
+        let c = Shape::csg(CsgOperation::Union, Shape::sphere(), Shape::cube())
+            .with_transform(Transform::translation(5.0, 0.0, 0.0));
+        let b = c.bounds();
+        assert_eq!(b.minimum(), Point::new(4.0, -1.0, -1.0));
+        assert_eq!(b.maximum(), Point::new(6.0, 1.0, 1.0));
+    }
+}
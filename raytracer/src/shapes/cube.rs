@@ -5,7 +5,7 @@ use crate::{
     vector::Vector,
 };
 
-use super::ShapeLocal;
+use super::{bounds::BoundingBox, ShapeLocal};
 
 // Cube always located at (0,0,0) and extended from -1 to 1.
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
@@ -24,9 +24,12 @@ impl ShapeLocal for Cube {
     }
 
     fn local_intersection(&self, local_ray: &crate::ray::Ray) -> IntersectionsFactor {
-        let (xtmin, xtmax) = check_axis(local_ray.origin().x(), local_ray.direction().x());
-        let (ytmin, ytmax) = check_axis(local_ray.origin().y(), local_ray.direction().y());
-        let (ztmin, ztmax) = check_axis(local_ray.origin().z(), local_ray.direction().z());
+        let (xtmin, xtmax) =
+            check_axis(local_ray.origin().x(), local_ray.direction().x(), -1.0, 1.0);
+        let (ytmin, ytmax) =
+            check_axis(local_ray.origin().y(), local_ray.direction().y(), -1.0, 1.0);
+        let (ztmin, ztmax) =
+            check_axis(local_ray.origin().z(), local_ray.direction().z(), -1.0, 1.0);
 
         let tmin = xtmin.max(ytmin).max(ztmin);
         let tmax = xtmax.min(ytmax).min(ztmax);
@@ -39,6 +42,10 @@ impl ShapeLocal for Cube {
             vec![tmin, tmax]
         }
     }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+    }
 }
 
 #[cfg(test)]
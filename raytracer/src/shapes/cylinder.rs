@@ -6,7 +6,7 @@ use crate::{
     vector::Vector,
 };
 
-use super::ShapeLocal;
+use super::{bounds::BoundingBox, ShapeLocal};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) struct Cylinder {
@@ -100,6 +100,14 @@ impl ShapeLocal for Cylinder {
         }));
         roots
     }
+
+    fn local_bounds(&self) -> BoundingBox {
+        let r = self.radius();
+        BoundingBox::new(
+            Point::new(-r, self.minimum, -r),
+            Point::new(r, self.maximum, r),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -200,6 +208,16 @@ mod test {
         test_intersect(Point::new(0.0, -1.0, -2.0), Vector::new(0.0, 1.0, 1.0), 2);
     }
 
+    #[test]
+    fn bounding_box_of_cylinder() {
+        assert!(!Cylinder::default().local_bounds().is_bounded());
+
+        let b = Cylinder::new(-5.0, 3.0).local_bounds();
+        assert!(b.is_bounded());
+        assert_eq!(b.minimum(), Point::new(-1.0, -5.0, -1.0));
+        assert_eq!(b.maximum(), Point::new(1.0, 3.0, 1.0));
+    }
+
     #[test]
     fn normal_vector_on_closed_cylinder_end_caps() {
         let cyl = Cylinder::new(1.0, 2.0);
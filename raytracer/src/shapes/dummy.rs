@@ -1,6 +1,6 @@
 use crate::{intersect::intersection::IntersectionsFactor, point::Point, ray::Ray, vector::Vector};
 
-use super::ShapeLocal;
+use super::{bounds::BoundingBox, ShapeLocal};
 
 // use for test shape's behavior
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
@@ -14,4 +14,8 @@ impl ShapeLocal for Dummy {
     fn local_intersection(&self, _: &Ray) -> IntersectionsFactor {
         vec![1.0]
     }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+    }
 }
@@ -1,16 +1,45 @@
 use std::ops::{Deref, DerefMut};
 
-use super::shape::Shape;
+use crate::{intersect::multiple_intersections::Intersections, ray::Ray};
+
+use super::{bounds::BoundingBox, bvh::Bvh, shape::Shape, ShapeWorld};
 
 #[derive(Debug, Clone, PartialEq, Default)]
 pub(crate) struct Group {
     children: Vec<Shape>,
+    bvh: Option<Bvh>,
 }
 
 #[allow(dead_code)]
 impl Group {
     pub fn add_shape(&mut self, shape: Shape) {
-        self.children.push(shape)
+        self.children.push(shape);
+        self.bvh = None;
+    }
+
+    // children are stored with their world transform, so are their bounds
+    pub fn bounds(&self) -> BoundingBox {
+        self.children
+            .iter()
+            .fold(BoundingBox::default(), |bounds, child| {
+                bounds.merge(&child.bounds())
+            })
+    }
+
+    pub fn build_bvh(&mut self) {
+        self.children.iter_mut().for_each(Shape::build_bvh);
+        self.bvh = Some(Bvh::new(&self.children));
+    }
+
+    pub fn intersect<'a>(&'a self, ray: &Ray) -> Intersections<'a> {
+        match &self.bvh {
+            Some(bvh) => bvh.intersect(&self.children, ray),
+            None => self
+                .children
+                .iter()
+                .map(|child| child.intersect(ray))
+                .fold(Intersections::default(), |merged, xs| merged.merge(xs)),
+        }
     }
 }
 
@@ -23,6 +52,8 @@ impl Deref for Group {
 
 impl DerefMut for Group {
     fn deref_mut(&mut self) -> &mut Self::Target {
+        // children might be changed, the hierarchy has to be built again
+        self.bvh = None;
         &mut self.children
     }
 }
@@ -88,6 +119,81 @@ mod test {
         assert_eq!(xs.count(), 2);
     }
 
+    #[test]
+    fn group_has_bounding_box_containing_its_children() {
+        let s = Shape::sphere()
+            .with_transform(Transform::scaling(2.0, 2.0, 2.0).translate(2.0, 5.0, -3.0));
+        let c = Shape::closed_cylinder(-2.0, 2.0)
+            .with_transform(Transform::scaling(0.5, 1.0, 0.5).translate(-4.0, -1.0, 4.0));
+        let mut g = Shape::group();
+        g.add_shape(s);
+        g.add_shape(c);
+        let b = g.bounds();
+        assert_eq!(b.minimum(), Point::new(-4.5, -3.0, -5.0));
+        assert_eq!(b.maximum(), Point::new(4.0, 7.0, 4.5));
+    }
+
+    #[test]
+    fn group_bounds_follow_group_transform() {
+        let mut g = Shape::group().with_transform(Transform::translation(10.0, 0.0, 0.0));
+        g.add_shape(Shape::sphere());
+        let b = g.bounds();
+        assert_eq!(b.minimum(), Point::new(9.0, -1.0, -1.0));
+        assert_eq!(b.maximum(), Point::new(11.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn group_transform_set_after_children_moves_them() {
+        let g = Shape::group()
+            .with_child(Shape::sphere())
+            .with_transform(Transform::translation(10.0, 0.0, 0.0));
+        let b = g.bounds();
+        assert_eq!(b.minimum(), Point::new(9.0, -1.0, -1.0));
+        assert_eq!(b.maximum(), Point::new(11.0, 1.0, 1.0));
+
+        // a new transform replaces the previous one instead of stacking on it
+        let g = g.with_transform(Transform::translation(0.0, 5.0, 0.0));
+        let b = g.bounds();
+        assert_eq!(b.minimum(), Point::new(-1.0, 4.0, -1.0));
+        assert_eq!(b.maximum(), Point::new(1.0, 6.0, 1.0));
+    }
+
+    #[test]
+    fn intersecting_group_with_bounding_volume_hierarchy() {
+        let mut g = Shape::group();
+        for i in 0..10 {
+            g.add_shape(Shape::sphere().with_transform(Transform::translation(
+                0.0,
+                0.0,
+                3.0 * i as f64,
+            )));
+        }
+        g.add_shape(Shape::plane().with_transform(Transform::translation(0.0, -1.0, 0.0)));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let expected = g.intersect(&r);
+
+        let mut divided = g.clone();
+        divided.build_bvh();
+        assert!(divided.as_group().unwrap().bvh.is_some());
+        let xs = divided.intersect(&r);
+        assert_eq!(xs.count(), 20);
+        assert_eq!(xs.count(), expected.count());
+
+        let r = Ray::new(Point::new(5.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(divided.intersect(&r).count(), 0);
+    }
+
+    #[test]
+    fn adding_child_invalidates_bounding_volume_hierarchy() {
+        let mut g = Shape::group();
+        g.add_shape(Shape::sphere());
+        g.build_bvh();
+        g.add_shape(Shape::sphere().with_transform(Transform::translation(0.0, 0.0, 5.0)));
+        assert!(g.as_group().unwrap().bvh.is_none());
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(g.intersect(&r).count(), 4);
+    }
+
     #[test]
     fn convert_point_from_world_to_object_space() {
         let mut g1 =
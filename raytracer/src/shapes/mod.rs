@@ -14,9 +14,22 @@ pub(crate) mod cone;
 
 pub(crate) mod group;
 
+pub(crate) mod triangle;
+
+pub(crate) mod smooth_triangle;
+
+pub(crate) mod csg;
+
+pub(crate) mod bounds;
+
+pub(crate) mod bvh;
+
 use crate::{
     color::Color,
-    intersect::{intersection::IntersectionsFactor, multiple_intersections::Intersections},
+    intersect::{
+        intersection::{IntersectionsFactor, UvFactor},
+        multiple_intersections::Intersections,
+    },
     material::Material,
     patterns::pattern::Pattern,
     point::Point,
@@ -26,8 +39,9 @@ use crate::{
 };
 
 use self::{
-    cone::Cone, cube::Cube, cylinder::Cylinder, dummy::Dummy, group::Group, plane::Plane,
-    sphere::Sphere,
+    bounds::BoundingBox, cone::Cone, csg::Csg, cube::Cube, cylinder::Cylinder, dummy::Dummy,
+    group::Group, plane::Plane, smooth_triangle::SmoothTriangle, sphere::Sphere,
+    triangle::Triangle,
 };
 
 #[derive(Debug, Clone, PartialEq)]
@@ -39,9 +53,12 @@ pub(crate) enum ShapeKind {
     Cylinder(Cylinder),
     Cone(Cone),
     Group(Group),
+    Triangle(Triangle),
+    SmoothTriangle(SmoothTriangle),
+    Csg(Csg),
 }
 
-pub(crate) trait ShapeMaterial {
+pub trait ShapeMaterial {
     fn material(&self) -> &Material;
     fn with_material(self, material: Material) -> Self;
 
@@ -53,13 +70,18 @@ pub(crate) trait ShapeMaterial {
     fn with_reflective(self, reflective: f64) -> Self;
     fn with_transparency(self, transparency: f64) -> Self;
     fn with_refractive_index(self, refractive_index: f64) -> Self;
+    fn with_emissive(self, emissive: Color) -> Self;
 
     fn with_pattern(self, pattern: Pattern) -> Self;
 }
 
 pub(crate) trait ShapeLocal {
     fn local_normal_at(&self, local_point: &Point) -> Vector;
+    fn local_normal_at_uv(&self, local_point: &Point, _uv: UvFactor) -> Vector {
+        self.local_normal_at(local_point)
+    }
     fn local_intersection(&self, local_ray: &Ray) -> IntersectionsFactor;
+    fn local_bounds(&self) -> BoundingBox;
 }
 
 pub(crate) trait ShapeWorld: Transformable + ShapeLocal {
@@ -75,12 +97,20 @@ pub(crate) trait ShapeWorld: Transformable + ShapeLocal {
         Some((self.transpose_inversed_transform()? * *normal).normalize())
     }
 
+    #[cfg(test)]
     fn normal_at(&self, point: &Point) -> Option<Vector> {
+        self.normal_at_uv(point, None)
+    }
+
+    fn normal_at_uv(&self, point: &Point, uv: Option<UvFactor>) -> Option<Vector> {
         let object_point = self.world_to_object(point)?;
-        let local_normal = self.local_normal_at(&object_point);
+        let local_normal = match uv {
+            Some(uv) => self.local_normal_at_uv(&object_point, uv),
+            None => self.local_normal_at(&object_point),
+        };
         let world_normal = self.normal_to_world(&local_normal)?;
         Some(world_normal)
     }
 
-    fn intersect(&self, ray: &Ray) -> Intersections;
+    fn intersect(&self, ray: &Ray) -> Intersections<'_>;
 }
@@ -1,9 +1,11 @@
 use crate::{
-    intersect::intersection::IntersectionsFactor, point::Point, util::solve_linear_equation,
+    intersect::intersection::IntersectionsFactor,
+    point::Point,
+    util::{solve_linear_equation, INFINITY},
     vector::Vector,
 };
 
-use super::ShapeLocal;
+use super::{bounds::BoundingBox, ShapeLocal};
 
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub(crate) struct Plane {}
@@ -17,6 +19,13 @@ impl ShapeLocal for Plane {
         let b = local_ray.origin().y();
         solve_linear_equation(a, b)
     }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point::new(-INFINITY, 0.0, -INFINITY),
+            Point::new(INFINITY, 0.0, INFINITY),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -62,6 +71,15 @@ mod test {
         assert_eq!(xs, [1.0]);
     }
 
+    #[test]
+    fn plane_is_not_bounded() {
+        let p = Plane::default();
+        let b = p.local_bounds();
+        assert!(!b.is_bounded());
+        assert_eq!(b.minimum().y(), 0.0);
+        assert_eq!(b.maximum().y(), 0.0);
+    }
+
     #[test]
     fn ray_intersecting_plane_from_below() {
         let p = Plane::default();
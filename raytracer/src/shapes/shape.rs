@@ -1,21 +1,46 @@
 use crate::{
     color::Color,
-    intersect::{intersection::IntersectionsFactor, multiple_intersections::Intersections},
+    intersect::{
+        intersection::{IntersectionsFactor, UvFactor},
+        multiple_intersections::Intersections,
+    },
     material::Material,
     patterns::pattern::Pattern,
     point::Point,
     ray::Ray,
-    transform::{transformable, InversedTransform, Transform, Transformable},
+    transform::{InversedTransform, Transform, Transformable},
     vector::Vector,
 };
 
 use super::{
-    cone::Cone, cube::Cube, cylinder::Cylinder, dummy::Dummy, group::Group, plane::Plane,
-    sphere::Sphere, ShapeKind, ShapeLocal, ShapeMaterial, ShapeWorld,
+    bounds::BoundingBox,
+    cone::Cone,
+    csg::{Csg, CsgOperation},
+    cube::Cube,
+    cylinder::Cylinder,
+    dummy::Dummy,
+    group::Group,
+    plane::Plane,
+    smooth_triangle::SmoothTriangle,
+    sphere::Sphere,
+    triangle::Triangle,
+    ShapeKind, ShapeLocal, ShapeMaterial, ShapeWorld,
 };
 
+/// Object of the scene, built from one of the constructors then placed with
+/// [`Transformable`](crate::Transformable) and painted with [`ShapeMaterial`](crate::ShapeMaterial).
+///
+/// ```
+/// use raytracer::{Color, Shape, ShapeMaterial, Transform, Transformable};
+///
+/// let floor = Shape::plane()
+///     .with_transform(Transform::translation(0.0, -1.0, 0.0))
+///     .with_color(Color::new(0.5, 0.5, 0.5))
+///     .with_reflective(0.3);
+/// assert_eq!(floor.material().reflective(), 0.3);
+/// ```
 #[derive(Debug, Clone, PartialEq)]
-pub(crate) struct Shape {
+pub struct Shape {
     shape: ShapeKind,
     transform: Transform,
     inversed_transform: InversedTransform,
@@ -23,11 +48,37 @@ pub(crate) struct Shape {
     material: Material,
 }
 
-transformable!(Shape);
+impl Transformable for Shape {
+    fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    fn inversed_transform(&self) -> InversedTransform {
+        self.inversed_transform
+    }
+
+    fn transpose_inversed_transform(&self) -> InversedTransform {
+        self.transpose_inversed_transform
+    }
+
+    // children of groups and csg carry the current transform already, they are moved from it to
+    // the new one so the call order with `add_shape` does not matter
+    fn set_transform(&mut self, transform: Transform) {
+        if let Some(inversed) = self.inversed_transform {
+            self.populate_children_transform(transform * inversed);
+        }
+        self.set_own_transform(transform);
+    }
+
+    fn with_transform(mut self, transform: Transform) -> Shape {
+        self.set_transform(transform);
+        self
+    }
+}
 
 #[allow(dead_code)]
 impl Shape {
-    pub fn new(shape: ShapeKind) -> Shape {
+    pub(crate) fn new(shape: ShapeKind) -> Shape {
         Shape {
             shape,
             transform: Transform::identity(),
@@ -82,7 +133,7 @@ impl Shape {
         Shape::new(ShapeKind::Cylinder(Cylinder::new(minimum, maximum)))
     }
 
-    pub fn as_cylinder(&self) -> Option<&Cylinder> {
+    pub(crate) fn as_cylinder(&self) -> Option<&Cylinder> {
         match &self.shape {
             ShapeKind::Cylinder(cylinder) => Some(cylinder),
             _ => None,
@@ -97,7 +148,7 @@ impl Shape {
         Shape::new(ShapeKind::Cone(Cone::new(minimum, maximum)))
     }
 
-    pub fn as_cone(&self) -> Option<&Cone> {
+    pub(crate) fn as_cone(&self) -> Option<&Cone> {
         match &self.shape {
             ShapeKind::Cone(cone) => Some(cone),
             _ => None,
@@ -108,7 +159,7 @@ impl Shape {
         Shape::new(ShapeKind::Group(Group::default()))
     }
 
-    pub fn as_group(&self) -> Option<&Group> {
+    pub(crate) fn as_group(&self) -> Option<&Group> {
         match &self.shape {
             ShapeKind::Group(g) => Some(g),
             _ => None,
@@ -119,15 +170,52 @@ impl Shape {
         self.as_group().is_some()
     }
 
-    fn polulate_transform(&mut self, transform: Transform) {
+    fn set_own_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+        self.inversed_transform = transform.inverse();
+        self.transpose_inversed_transform = self.inversed_transform.map(|t| t.transpose());
+    }
+
+    fn populate_children_transform(&mut self, transform: Transform) {
         match &mut self.shape {
             ShapeKind::Group(g) => g
                 .iter_mut()
                 .for_each(|child| child.polulate_transform(transform)),
-            _ => self.set_transform(transform * self.transform),
+            ShapeKind::Csg(c) => {
+                c.left_mut().polulate_transform(transform);
+                c.right_mut().polulate_transform(transform);
+            }
+            _ => (),
         }
     }
 
+    fn polulate_transform(&mut self, transform: Transform) {
+        self.populate_children_transform(transform);
+        self.set_own_transform(transform * self.transform);
+    }
+
+    // applies `transform` on top of the current one, children of groups and csg included
+    pub fn transformed_by(mut self, transform: Transform) -> Shape {
+        self.polulate_transform(transform);
+        self
+    }
+
+    fn polulate_material(&mut self, material: Material) {
+        self.material = material.clone();
+        match &mut self.shape {
+            ShapeKind::Group(g) => g
+                .iter_mut()
+                .for_each(|child| child.polulate_material(material.clone())),
+            ShapeKind::Csg(c) => {
+                c.left_mut().polulate_material(material.clone());
+                c.right_mut().polulate_material(material);
+            }
+            _ => (),
+        }
+    }
+
+    /// Adds `shape` to a group, it takes the transform of the group on top of its own.
+    /// Does nothing on other shapes.
     pub fn add_shape(&mut self, mut shape: Shape) {
         if let ShapeKind::Group(g) = &mut self.shape {
             shape.polulate_transform(self.transform);
@@ -135,22 +223,124 @@ impl Shape {
         }
     }
 
-    pub fn dummy() -> Shape {
+    /// Builder version of [`Shape::add_shape`].
+    ///
+    /// ```
+    /// use raytracer::{Shape, Transform, Transformable};
+    ///
+    /// let group = Shape::group()
+    ///     .with_transform(Transform::translation(0.0, 1.0, 0.0))
+    ///     .with_child(Shape::sphere())
+    ///     .with_child(Shape::cube().with_transform(Transform::scaling(0.5, 0.5, 0.5)));
+    /// assert!(group.is_group());
+    ///
+    /// // the transform of the group can also be set after its children
+    /// let same = Shape::group()
+    ///     .with_child(Shape::sphere())
+    ///     .with_child(Shape::cube().with_transform(Transform::scaling(0.5, 0.5, 0.5)))
+    ///     .with_transform(Transform::translation(0.0, 1.0, 0.0));
+    /// assert_eq!(same, group);
+    /// ```
+    pub fn with_child(mut self, shape: Shape) -> Shape {
+        self.add_shape(shape);
+        self
+    }
+
+    pub fn triangle(p1: Point, p2: Point, p3: Point) -> Shape {
+        Shape::new(ShapeKind::Triangle(Triangle::new(p1, p2, p3)))
+    }
+
+    pub(crate) fn as_triangle(&self) -> Option<&Triangle> {
+        match &self.shape {
+            ShapeKind::Triangle(triangle) => Some(triangle),
+            _ => None,
+        }
+    }
+
+    pub fn smooth_triangle(
+        p1: Point,
+        p2: Point,
+        p3: Point,
+        n1: Vector,
+        n2: Vector,
+        n3: Vector,
+    ) -> Shape {
+        Shape::new(ShapeKind::SmoothTriangle(SmoothTriangle::new(
+            p1, p2, p3, n1, n2, n3,
+        )))
+    }
+
+    pub(crate) fn as_smooth_triangle(&self) -> Option<&SmoothTriangle> {
+        match &self.shape {
+            ShapeKind::SmoothTriangle(triangle) => Some(triangle),
+            _ => None,
+        }
+    }
+
+    pub fn csg(operation: CsgOperation, left: Shape, right: Shape) -> Shape {
+        Shape::new(ShapeKind::Csg(Csg::new(operation, left, right)))
+    }
+
+    pub(crate) fn as_csg(&self) -> Option<&Csg> {
+        match &self.shape {
+            ShapeKind::Csg(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    // whether `object` is this shape or one of its descendants
+    pub(crate) fn includes(&self, object: &Shape) -> bool {
+        match &self.shape {
+            ShapeKind::Group(g) => g.iter().any(|child| child.includes(object)),
+            ShapeKind::Csg(c) => c.includes(object),
+            _ => std::ptr::eq(self, object),
+        }
+    }
+
+    pub(crate) fn dummy() -> Shape {
         Shape::new(ShapeKind::Dummy(Dummy::default()))
     }
+
+    pub(crate) fn bounds(&self) -> BoundingBox {
+        match &self.shape {
+            ShapeKind::Group(g) => g.bounds(),
+            ShapeKind::Csg(c) => c.bounds(),
+            _ => self.local_bounds().transform(self.transform),
+        }
+    }
+
+    pub(crate) fn is_bounded(&self) -> bool {
+        match &self.shape {
+            ShapeKind::Group(g) => g.iter().all(Shape::is_bounded),
+            ShapeKind::Csg(c) => c.left().is_bounded() && c.right().is_bounded(),
+            _ => self.local_bounds().is_bounded(),
+        }
+    }
+
+    pub(crate) fn build_bvh(&mut self) {
+        match &mut self.shape {
+            ShapeKind::Group(g) => g.build_bvh(),
+            ShapeKind::Csg(c) => {
+                c.left_mut().build_bvh();
+                c.right_mut().build_bvh();
+            }
+            _ => (),
+        }
+    }
 }
 
 impl ShapeWorld for Shape {
-    fn intersect(&self, ray: &Ray) -> Intersections {
+    fn intersect(&self, ray: &Ray) -> Intersections<'_> {
         self.transform_ray(ray)
             .map_or(Default::default(), |local_ray| match &self.shape {
-                ShapeKind::Group(g) => g
-                    .iter()
-                    .map(|child| child.intersect(ray))
-                    .reduce(|merged_intersections, intersections| {
-                        merged_intersections.merge(intersections)
-                    })
-                    .unwrap_or_default(),
+                ShapeKind::Group(g) => g.intersect(ray),
+                ShapeKind::Csg(c) => c.intersect(ray).with_solid(self),
+                ShapeKind::Triangle(t) => {
+                    Intersections::with_uv(t.local_intersection_uv(&local_ray), self, ray)
+                }
+                ShapeKind::SmoothTriangle(t) => {
+                    Intersections::with_uv(t.local_intersection_uv(&local_ray), self, ray)
+                }
                 _ => {
                     let roots = self.local_intersection(&local_ray);
                     Intersections::new(roots, self, ray)
@@ -168,7 +358,10 @@ impl ShapeLocal for Shape {
             ShapeKind::Cube(c) => c.local_intersection(local_ray),
             ShapeKind::Cylinder(c) => c.local_intersection(local_ray),
             ShapeKind::Cone(c) => c.local_intersection(local_ray),
+            ShapeKind::Triangle(t) => t.local_intersection(local_ray),
+            ShapeKind::SmoothTriangle(t) => t.local_intersection(local_ray),
             ShapeKind::Group(_) => unreachable!("Group `local_intersection` should not be called."),
+            ShapeKind::Csg(_) => unreachable!("Csg `local_intersection` should not be called."),
         }
     }
 
@@ -180,7 +373,32 @@ impl ShapeLocal for Shape {
             ShapeKind::Cube(c) => c.local_normal_at(object_point),
             ShapeKind::Cylinder(c) => c.local_normal_at(object_point),
             ShapeKind::Cone(c) => c.local_normal_at(object_point),
+            ShapeKind::Triangle(t) => t.local_normal_at(object_point),
+            ShapeKind::SmoothTriangle(t) => t.local_normal_at(object_point),
             ShapeKind::Group(_) => unreachable!("Group `local_normal_at` should not be called."),
+            ShapeKind::Csg(_) => unreachable!("Csg `local_normal_at` should not be called."),
+        }
+    }
+
+    fn local_normal_at_uv(&self, object_point: &Point, uv: UvFactor) -> Vector {
+        match &self.shape {
+            ShapeKind::SmoothTriangle(t) => t.local_normal_at_uv(object_point, uv),
+            _ => self.local_normal_at(object_point),
+        }
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        match &self.shape {
+            ShapeKind::Sphere(s) => s.local_bounds(),
+            ShapeKind::Dummy(s) => s.local_bounds(),
+            ShapeKind::Plane(p) => p.local_bounds(),
+            ShapeKind::Cube(c) => c.local_bounds(),
+            ShapeKind::Cylinder(c) => c.local_bounds(),
+            ShapeKind::Cone(c) => c.local_bounds(),
+            ShapeKind::Triangle(t) => t.local_bounds(),
+            ShapeKind::SmoothTriangle(t) => t.local_bounds(),
+            ShapeKind::Group(_) => unreachable!("Group `local_bounds` should not be called."),
+            ShapeKind::Csg(_) => unreachable!("Csg `local_bounds` should not be called."),
         }
     }
 }
@@ -190,53 +408,62 @@ impl ShapeMaterial for Shape {
         &self.material
     }
 
+    // material of a group is shared by all of its children
     fn with_material(mut self, material: Material) -> Self {
-        self.material = material;
+        self.polulate_material(material);
         self
     }
 
     fn with_color(self, color: Color) -> Self {
-        let material = self.material.with_color(color);
+        let material = self.material.clone().with_color(color);
         self.with_material(material)
     }
 
     fn with_ambient(self, ambient: f64) -> Self {
-        let material = self.material.with_ambient(ambient);
+        let material = self.material.clone().with_ambient(ambient);
         self.with_material(material)
     }
 
     fn with_diffuse(self, diffuse: f64) -> Self {
-        let material = self.material.with_diffuse(diffuse);
+        let material = self.material.clone().with_diffuse(diffuse);
         self.with_material(material)
     }
 
     fn with_specular(self, specular: f64) -> Self {
-        let material = self.material.with_specular(specular);
+        let material = self.material.clone().with_specular(specular);
         self.with_material(material)
     }
 
     fn with_shininess(self, shininess: f64) -> Self {
-        let material = self.material.with_shininess(shininess);
+        let material = self.material.clone().with_shininess(shininess);
         self.with_material(material)
     }
 
     fn with_reflective(self, reflective: f64) -> Self {
-        let material = self.material.with_reflective(reflective);
+        let material = self.material.clone().with_reflective(reflective);
         self.with_material(material)
     }
 
     fn with_pattern(self, pattern: Pattern) -> Self {
-        let material = self.material.with_pattern(pattern);
+        let material = self.material.clone().with_pattern(pattern);
         self.with_material(material)
     }
 
     fn with_transparency(self, transparency: f64) -> Self {
-        let material = self.material.with_transparency(transparency);
+        let material = self.material.clone().with_transparency(transparency);
         self.with_material(material)
     }
 
     fn with_refractive_index(self, refractive_index: f64) -> Self {
-        let material = self.material.with_refractive_index(refractive_index);
+        let material = self
+            .material
+            .clone()
+            .with_refractive_index(refractive_index);
+        self.with_material(material)
+    }
+
+    fn with_emissive(self, emissive: Color) -> Self {
+        let material = self.material.clone().with_emissive(emissive);
         self.with_material(material)
     }
 }
@@ -277,10 +504,19 @@ mod test {
     #[test]
     fn assigning_a_material() {
         let m = Material::default().with_ambient(1.0);
-        let s = Shape::dummy().with_material(m);
+        let s = Shape::dummy().with_material(m.clone());
         assert_eq!(s.material, m);
     }
 
+    #[test]
+    fn assigning_a_material_to_group_assigns_it_to_children() {
+        let m = Material::default().with_ambient(1.0);
+        let mut g = Shape::group();
+        g.add_shape(Shape::sphere());
+        let g = g.with_material(m.clone());
+        assert_eq!(g.as_group().unwrap()[0].material, m);
+    }
+
     #[test]
     fn shape_with_color() {
         let color = Color::new(0.1, 0.1, 0.1);
@@ -340,7 +576,7 @@ mod test {
     #[test]
     fn shape_with_default_pattern() {
         let p = Pattern::dummy();
-        let s = Shape::dummy().with_pattern(p);
+        let s = Shape::dummy().with_pattern(p.clone());
         assert_eq!(s.material.pattern(), Some(&p));
     }
 
@@ -371,6 +607,42 @@ mod test {
         assert_eq!(local_ray, Some(expected_ray));
     }
 
+    #[test]
+    fn bounds_of_transformed_shape() {
+        let s = Shape::sphere()
+            .with_transform(Transform::scaling(0.5, 2.0, 4.0).translate(1.0, -3.0, 5.0));
+        let b = s.bounds();
+        assert_eq!(b.minimum(), Point::new(0.5, -5.0, 1.0));
+        assert_eq!(b.maximum(), Point::new(1.5, -1.0, 9.0));
+    }
+
+    #[test]
+    fn group_with_unbounded_child_is_unbounded() {
+        let mut g = Shape::group();
+        g.add_shape(Shape::sphere());
+        assert!(g.is_bounded());
+        g.add_shape(Shape::plane());
+        assert!(!g.is_bounded());
+    }
+
+    #[test]
+    fn intersecting_triangle_carries_u_and_v() {
+        let s = Shape::triangle(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+        )
+        .with_transform(Transform::translation(0.0, 0.0, 1.0));
+        let r = Ray::new(Point::new(-0.2, 0.3, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = s.intersect(&r);
+        assert_eq!(xs.count(), 1);
+        let hit = xs.hit().unwrap();
+        assert_float_eq!(hit.t(), 3.0);
+        let (u, v) = hit.uv().unwrap();
+        assert_float_eq!(u, 0.45);
+        assert_float_eq!(v, 0.25);
+    }
+
     #[test]
     fn normal_on_translated_shape() {
         let s = Shape::dummy().with_transform(Transform::translation(0.0, 1.0, 0.0));
@@ -0,0 +1,118 @@
+use crate::{
+    intersect::intersection::{IntersectionsFactor, UvFactor, UvIntersectionsFactor},
+    point::Point,
+    ray::Ray,
+    vector::Vector,
+};
+
+use super::{bounds::BoundingBox, triangle::Triangle, ShapeLocal};
+
+// Triangle with a normal on each vertex, normals are interpolated across the surface.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct SmoothTriangle {
+    triangle: Triangle,
+    n1: Vector,
+    n2: Vector,
+    n3: Vector,
+}
+
+impl SmoothTriangle {
+    pub fn new(p1: Point, p2: Point, p3: Point, n1: Vector, n2: Vector, n3: Vector) -> Self {
+        SmoothTriangle {
+            triangle: Triangle::new(p1, p2, p3),
+            n1,
+            n2,
+            n3,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn triangle(&self) -> &Triangle {
+        &self.triangle
+    }
+
+    #[cfg(test)]
+    pub fn n1(&self) -> Vector {
+        self.n1
+    }
+
+    #[cfg(test)]
+    pub fn n2(&self) -> Vector {
+        self.n2
+    }
+
+    #[cfg(test)]
+    pub fn n3(&self) -> Vector {
+        self.n3
+    }
+
+    pub fn local_intersection_uv(&self, local_ray: &Ray) -> UvIntersectionsFactor {
+        self.triangle.local_intersection_uv(local_ray)
+    }
+}
+
+impl ShapeLocal for SmoothTriangle {
+    // without barycentric coordinates, fallback to the flat normal
+    fn local_normal_at(&self, point: &Point) -> Vector {
+        self.triangle.local_normal_at(point)
+    }
+
+    fn local_normal_at_uv(&self, _: &Point, (u, v): UvFactor) -> Vector {
+        self.n2 * u + self.n3 * v + self.n1 * (1.0 - u - v)
+    }
+
+    fn local_intersection(&self, local_ray: &Ray) -> IntersectionsFactor {
+        self.triangle.local_intersection(local_ray)
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        self.triangle.local_bounds()
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::util::assert_float_eq;
+
+    use super::*;
+
+    fn default_smooth_triangle() -> SmoothTriangle {
+        SmoothTriangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(-1.0, 0.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn constructing_a_smooth_triangle() {
+        let tri = default_smooth_triangle();
+        assert_eq!(tri.triangle().p1(), Point::new(0.0, 1.0, 0.0));
+        assert_eq!(tri.triangle().p2(), Point::new(-1.0, 0.0, 0.0));
+        assert_eq!(tri.triangle().p3(), Point::new(1.0, 0.0, 0.0));
+        assert_eq!(tri.n1(), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(tri.n2(), Vector::new(-1.0, 0.0, 0.0));
+        assert_eq!(tri.n3(), Vector::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn intersection_with_smooth_triangle_stores_u_v() {
+        let tri = default_smooth_triangle();
+        let r = Ray::new(Point::new(-0.2, 0.3, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = tri.local_intersection_uv(&r);
+        let (_, u, v) = xs[0];
+        assert_float_eq!(u, 0.45);
+        assert_float_eq!(v, 0.25);
+    }
+
+    #[test]
+    fn smooth_triangle_uses_u_v_to_interpolate_normal() {
+        let tri = default_smooth_triangle();
+        let n = tri.local_normal_at_uv(&Point::new(0.0, 0.0, 0.0), (0.45, 0.25));
+        assert_eq!(n, Vector::new(-0.2, 0.3, 0.0));
+    }
+}
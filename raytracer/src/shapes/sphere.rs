@@ -3,7 +3,7 @@ use crate::{
     util::solve_quadratic_equation, vector::Vector,
 };
 
-use super::ShapeLocal;
+use super::{bounds::BoundingBox, ShapeLocal};
 
 // Sphere always located at (0,0,0) with radius 1.0
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
@@ -27,6 +27,10 @@ impl ShapeLocal for Sphere {
         let c = sphere_to_ray.dot(&sphere_to_ray) - 1.0;
         solve_quadratic_equation(a, b, c)
     }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+    }
 }
 
 #[cfg(test)]
@@ -103,6 +107,14 @@ mod test {
         assert_eq!(n, Vector::new(v, v, v));
     }
 
+    #[test]
+    fn sphere_has_bounding_box() {
+        let s = Sphere::default();
+        let b = s.local_bounds();
+        assert_eq!(b.minimum(), Point::new(-1.0, -1.0, -1.0));
+        assert_eq!(b.maximum(), Point::new(1.0, 1.0, 1.0));
+    }
+
     #[test]
     fn normal_is_normalized_vector() {
         let s = Sphere::default();
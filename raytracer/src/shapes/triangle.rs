@@ -0,0 +1,202 @@
+use crate::{
+    intersect::intersection::{IntersectionsFactor, UvIntersectionsFactor},
+    point::Point,
+    ray::Ray,
+    util::EPSILON,
+    vector::Vector,
+};
+
+use super::{bounds::BoundingBox, ShapeLocal};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Triangle {
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    e1: Vector,
+    e2: Vector,
+    normal: Vector,
+}
+
+impl Triangle {
+    pub fn new(p1: Point, p2: Point, p3: Point) -> Triangle {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        let normal = e2.cross(&e1).normalize();
+        Triangle {
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn p1(&self) -> Point {
+        self.p1
+    }
+
+    #[cfg(test)]
+    pub fn p2(&self) -> Point {
+        self.p2
+    }
+
+    #[cfg(test)]
+    pub fn p3(&self) -> Point {
+        self.p3
+    }
+
+    #[cfg(test)]
+    pub fn e1(&self) -> Vector {
+        self.e1
+    }
+
+    #[cfg(test)]
+    pub fn e2(&self) -> Vector {
+        self.e2
+    }
+
+    #[cfg(test)]
+    pub fn normal(&self) -> Vector {
+        self.normal
+    }
+
+    // Möller–Trumbore algorithm, returns `t` alongside barycentric `u` and `v`
+    pub fn local_intersection_uv(&self, local_ray: &Ray) -> UvIntersectionsFactor {
+        let dir_cross_e2 = local_ray.direction().cross(&self.e2);
+        let det = self.e1.dot(&dir_cross_e2);
+        if det.abs() < EPSILON {
+            return vec![];
+        }
+
+        let f = 1.0 / det;
+        let p1_to_origin = local_ray.origin() - self.p1;
+        let u = f * p1_to_origin.dot(&dir_cross_e2);
+        if !(0.0..=1.0).contains(&u) {
+            return vec![];
+        }
+
+        let origin_cross_e1 = p1_to_origin.cross(&self.e1);
+        let v = f * local_ray.direction().dot(&origin_cross_e1);
+        if v < 0.0 || u + v > 1.0 {
+            return vec![];
+        }
+
+        let t = f * self.e2.dot(&origin_cross_e1);
+        vec![(t, u, v)]
+    }
+}
+
+impl ShapeLocal for Triangle {
+    fn local_normal_at(&self, _: &Point) -> Vector {
+        self.normal
+    }
+
+    fn local_intersection(&self, local_ray: &Ray) -> IntersectionsFactor {
+        self.local_intersection_uv(local_ray)
+            .into_iter()
+            .map(|(t, _, _)| t)
+            .collect()
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::default();
+        bounds.add_point(&self.p1);
+        bounds.add_point(&self.p2);
+        bounds.add_point(&self.p3);
+        bounds
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::util::assert_float_eq;
+
+    use super::*;
+
+    fn default_triangle() -> Triangle {
+        Triangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn constructing_a_triangle() {
+        let t = default_triangle();
+        assert_eq!(t.p1(), Point::new(0.0, 1.0, 0.0));
+        assert_eq!(t.p2(), Point::new(-1.0, 0.0, 0.0));
+        assert_eq!(t.p3(), Point::new(1.0, 0.0, 0.0));
+        assert_eq!(t.e1(), Vector::new(-1.0, -1.0, 0.0));
+        assert_eq!(t.e2(), Vector::new(1.0, -1.0, 0.0));
+        assert_eq!(t.normal(), Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn finding_normal_on_a_triangle() {
+        let t = default_triangle();
+        let n1 = t.local_normal_at(&Point::new(0.0, 0.5, 0.0));
+        let n2 = t.local_normal_at(&Point::new(-0.5, 0.75, 0.0));
+        let n3 = t.local_normal_at(&Point::new(0.5, 0.25, 0.0));
+        assert_eq!(n1, t.normal());
+        assert_eq!(n2, t.normal());
+        assert_eq!(n3, t.normal());
+    }
+
+    #[test]
+    fn intersecting_ray_parallel_to_triangle() {
+        let t = default_triangle();
+        let r = Ray::new(Point::new(0.0, -1.0, -2.0), Vector::new(0.0, 1.0, 0.0));
+        assert!(t.local_intersection(&r).is_empty());
+    }
+
+    #[test]
+    fn ray_misses_triangle_edges() {
+        let t = default_triangle();
+        let test_miss = |origin: Point| {
+            let r = Ray::new(origin, Vector::new(0.0, 0.0, 1.0));
+            assert!(t.local_intersection(&r).is_empty());
+        };
+
+        test_miss(Point::new(1.0, 1.0, -2.0));
+        test_miss(Point::new(-1.0, 1.0, -2.0));
+        test_miss(Point::new(0.0, -1.0, -2.0));
+    }
+
+    #[test]
+    fn ray_strikes_triangle() {
+        let t = default_triangle();
+        let r = Ray::new(Point::new(0.0, 0.5, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = t.local_intersection(&r);
+        assert_eq!(xs.len(), 1);
+        assert_float_eq!(xs[0], 2.0);
+    }
+
+    #[test]
+    fn intersection_stores_barycentric_u_v() {
+        let t = default_triangle();
+        let r = Ray::new(Point::new(-0.2, 0.3, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = t.local_intersection_uv(&r);
+        assert_eq!(xs.len(), 1);
+        let (t, u, v) = xs[0];
+        assert_float_eq!(t, 2.0);
+        assert_float_eq!(u, 0.45);
+        assert_float_eq!(v, 0.25);
+    }
+
+    #[test]
+    fn triangle_has_bounding_box() {
+        let t = Triangle::new(
+            Point::new(-3.0, 7.0, 2.0),
+            Point::new(6.0, 2.0, -4.0),
+            Point::new(2.0, -1.0, -1.0),
+        );
+        let b = t.local_bounds();
+        assert_eq!(b.minimum(), Point::new(-3.0, -1.0, -4.0));
+        assert_eq!(b.maximum(), Point::new(6.0, 7.0, 2.0));
+    }
+}
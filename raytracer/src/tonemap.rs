@@ -0,0 +1,295 @@
+use std::str::FromStr;
+
+use crate::{canvas::Canvas, color::Color};
+
+// Curve mapping linear radiance to displayable values between 0 and 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneOperator {
+    // values above 1 are cut off
+    #[default]
+    Clamp,
+    Reinhard,
+    // reinhard reaching 1 at the white point
+    ExtendedReinhard,
+    // filmic curve fitted to the aces reference transform
+    Aces,
+    // 1 - e^-c, like the response of a film
+    Exposure,
+}
+
+impl ToneOperator {
+    fn map(self, c: f64, white: f64) -> f64 {
+        let c = c.max(0.0);
+        let mapped = match self {
+            ToneOperator::Clamp => c,
+            ToneOperator::Reinhard => c / (1.0 + c),
+            ToneOperator::ExtendedReinhard => c * (1.0 + c / (white * white)) / (1.0 + c),
+            ToneOperator::Aces => (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14),
+            ToneOperator::Exposure => 1.0 - (-c).exp(),
+        };
+        mapped.clamp(0.0, 1.0)
+    }
+}
+
+impl FromStr for ToneOperator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clamp" => Ok(ToneOperator::Clamp),
+            "reinhard" => Ok(ToneOperator::Reinhard),
+            "extended-reinhard" => Ok(ToneOperator::ExtendedReinhard),
+            "aces" => Ok(ToneOperator::Aces),
+            "exposure" => Ok(ToneOperator::Exposure),
+            s => Err(format!(
+                "unknown tone mapping `{}`, expected one of `clamp`, `reinhard`, `extended-reinhard`, `aces`, `exposure`",
+                s
+            )),
+        }
+    }
+}
+
+// Transfer function applied after tone mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Gamma {
+    #[default]
+    Linear,
+    Srgb,
+}
+
+impl Gamma {
+    fn encode(self, c: f64) -> f64 {
+        match self {
+            Gamma::Linear => c,
+            Gamma::Srgb if c <= 0.0031308 => 12.92 * c,
+            Gamma::Srgb => 1.055 * c.powf(1.0 / 2.4) - 0.055,
+        }
+    }
+
+    // back to linear, for colors read from images
+    pub(crate) fn decode(self, c: f64) -> f64 {
+        match self {
+            Gamma::Linear => c,
+            Gamma::Srgb if c <= 0.04045 => c / 12.92,
+            Gamma::Srgb => ((c + 0.055) / 1.055).powf(2.4),
+        }
+    }
+}
+
+impl FromStr for Gamma {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Gamma::Linear),
+            "srgb" => Ok(Gamma::Srgb),
+            s => Err(format!(
+                "unknown gamma `{}`, expected one of `linear`, `srgb`",
+                s
+            )),
+        }
+    }
+}
+
+/// Post processing turning a rendered canvas into displayable colors.
+/// The default clamps linear colors, like writing the canvas directly.
+///
+/// ```
+/// use raytracer::{Canvas, Color, Gamma, ToneMapping, ToneOperator};
+///
+/// let mut canvas = Canvas::new(1, 1);
+/// canvas.write_pixel(0, 0, &Color::new(3.0, 1.0, 0.0));
+/// let mapping = ToneMapping::default()
+///     .with_operator(ToneOperator::Reinhard)
+///     .with_gamma(Gamma::Linear);
+/// assert_eq!(mapping.apply(&canvas).color(0, 0), Some(&Color::new(0.75, 0.5, 0.0)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneMapping {
+    operator: ToneOperator,
+    // in stops, every stop doubles the brightness
+    exposure: f64,
+    // smallest value mapped to white by the extended reinhard operator
+    white: f64,
+    gamma: Gamma,
+}
+
+impl Default for ToneMapping {
+    fn default() -> ToneMapping {
+        ToneMapping {
+            operator: ToneOperator::default(),
+            exposure: 0.0,
+            white: 4.0,
+            gamma: Gamma::default(),
+        }
+    }
+}
+
+impl ToneMapping {
+    pub fn new(operator: ToneOperator, exposure: f64) -> ToneMapping {
+        ToneMapping {
+            operator,
+            exposure,
+            ..ToneMapping::default()
+        }
+    }
+
+    pub fn operator(&self) -> ToneOperator {
+        self.operator
+    }
+
+    pub fn with_operator(mut self, operator: ToneOperator) -> ToneMapping {
+        self.operator = operator;
+        self
+    }
+
+    pub fn exposure(&self) -> f64 {
+        self.exposure
+    }
+
+    pub fn with_exposure(mut self, exposure: f64) -> ToneMapping {
+        self.exposure = exposure;
+        self
+    }
+
+    pub fn white(&self) -> f64 {
+        self.white
+    }
+
+    pub fn with_white(mut self, white: f64) -> ToneMapping {
+        self.white = white;
+        self
+    }
+
+    pub fn gamma(&self) -> Gamma {
+        self.gamma
+    }
+
+    pub fn with_gamma(mut self, gamma: Gamma) -> ToneMapping {
+        self.gamma = gamma;
+        self
+    }
+
+    pub fn map(&self, color: &Color) -> Color {
+        let scale = self.exposure.exp2();
+        let map = |c: f64| {
+            let c = self.operator.map(c * scale, self.white);
+            self.gamma.encode(c)
+        };
+        Color::new(map(color.r()), map(color.g()), map(color.b()))
+    }
+
+    pub fn apply(&self, canvas: &Canvas) -> Canvas {
+        let mut mapped = Canvas::new(canvas.width(), canvas.height());
+        for (y, row) in canvas.rows().iter().enumerate() {
+            for (x, color) in row.iter().enumerate() {
+                mapped.write_pixel(x, y, &self.map(color));
+            }
+        }
+        mapped
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::util::assert_float_eq;
+
+    use super::*;
+
+    fn map(operator: ToneOperator, c: f64) -> f64 {
+        ToneMapping::default()
+            .with_operator(operator)
+            .map(&Color::new(c, c, c))
+            .r()
+    }
+
+    #[test]
+    fn default_tone_mapping_clamps() {
+        let mapping = ToneMapping::default();
+        let color = mapping.map(&Color::new(1.5, 0.5, -0.5));
+        assert_eq!(color, Color::new(1.0, 0.5, 0.0));
+    }
+
+    #[test]
+    fn reinhard_operators() {
+        assert_float_eq!(map(ToneOperator::Reinhard, 1.0), 0.5);
+        assert_float_eq!(map(ToneOperator::Reinhard, 3.0), 0.75);
+        // the extended operator reaches white at the white point
+        assert_float_eq!(map(ToneOperator::ExtendedReinhard, 4.0), 1.0);
+        assert_float_eq!(map(ToneOperator::ExtendedReinhard, 1.0), 0.53125);
+    }
+
+    #[test]
+    fn aces_and_exposure_operators() {
+        assert_float_eq!(map(ToneOperator::Aces, 0.0), 0.0);
+        assert_float_eq!(map(ToneOperator::Aces, 1.0), 0.8038);
+        assert_float_eq!(map(ToneOperator::Aces, 100.0), 1.0);
+        assert_float_eq!(map(ToneOperator::Exposure, 1.0), 0.63212);
+        // operators keep the order of brightness
+        for operator in [
+            ToneOperator::Reinhard,
+            ToneOperator::ExtendedReinhard,
+            ToneOperator::Aces,
+            ToneOperator::Exposure,
+        ] {
+            assert!(map(operator, 0.5) < map(operator, 2.0));
+            assert!(map(operator, 2.0) <= map(operator, 8.0));
+        }
+    }
+
+    #[test]
+    fn exposure_is_measured_in_stops() {
+        let mapping = ToneMapping::new(ToneOperator::Clamp, 1.0);
+        assert_eq!(
+            mapping.map(&Color::new(0.25, 0.1, 1.0)),
+            Color::new(0.5, 0.2, 1.0)
+        );
+        let mapping = mapping.with_exposure(-2.0);
+        assert_eq!(
+            mapping.map(&Color::new(2.0, 1.0, 0.0)),
+            Color::new(0.5, 0.25, 0.0)
+        );
+    }
+
+    #[test]
+    fn srgb_gamma() {
+        let mapping = ToneMapping::default().with_gamma(Gamma::Srgb);
+        let color = mapping.map(&Color::new(0.0, 0.002, 0.5));
+        assert_float_eq!(color.r(), 0.0);
+        assert_float_eq!(color.g(), 0.02584);
+        assert_float_eq!(color.b(), 0.73536);
+        assert_eq!(
+            mapping.map(&Color::new(1.0, 2.0, 1.0)),
+            Color::new(1.0, 1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn srgb_decoding_inverts_encoding() {
+        for c in [0.0, 0.002, 0.2, 0.5, 1.0] {
+            assert_float_eq!(Gamma::Srgb.decode(Gamma::Srgb.encode(c)), c);
+        }
+        assert_float_eq!(Gamma::Srgb.decode(0.73536), 0.5);
+        assert_float_eq!(Gamma::Linear.decode(0.5), 0.5);
+    }
+
+    #[test]
+    fn apply_to_canvas() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(1, 0, &Color::new(1.0, 3.0, 0.0));
+        let mapped = ToneMapping::new(ToneOperator::Reinhard, 0.0).apply(&canvas);
+        assert_eq!(mapped.color(0, 0), Some(&Color::new(0.0, 0.0, 0.0)));
+        assert_eq!(mapped.color(1, 0), Some(&Color::new(0.5, 0.75, 0.0)));
+    }
+
+    #[test]
+    fn parse_operator_names() {
+        assert_eq!("aces".parse(), Ok(ToneOperator::Aces));
+        assert_eq!(
+            "extended-reinhard".parse(),
+            Ok(ToneOperator::ExtendedReinhard)
+        );
+        assert!("filmic".parse::<ToneOperator>().is_err());
+        assert_eq!("srgb".parse(), Ok(Gamma::Srgb));
+    }
+}
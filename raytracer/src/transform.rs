@@ -1,7 +1,8 @@
 use std::ops::Mul;
 
 pub(crate) type InversedTransform = Option<Transform>;
-pub(crate) trait Transformable {
+/// Objects placed in the scene with a [`Transform`].
+pub trait Transformable {
     fn transform(&self) -> Transform;
     fn inversed_transform(&self) -> InversedTransform;
     fn transpose_inversed_transform(&self) -> InversedTransform;
@@ -42,8 +43,17 @@ pub(crate) use transformable;
 
 use crate::{matrix::Matrix4, point::Point, ray::Ray, vector::Vector};
 
+/// 4x4 transformation matrix. Chained calls apply from left to right,
+/// `a.scale(..)` scales the result of `a`.
+///
+/// ```
+/// use raytracer::{Point, Transform};
+///
+/// let t = Transform::identity().scale(2.0, 2.0, 2.0).translate(1.0, 0.0, 0.0);
+/// assert_eq!(t * Point::new(1.0, 1.0, 1.0), Point::new(3.0, 2.0, 2.0));
+/// ```
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub(crate) struct Transform {
+pub struct Transform {
     matrix: Matrix4,
 }
 
@@ -28,9 +28,9 @@ pub(crate) fn solve_linear_equation(a: f64, b: f64) -> Vec<f64> {
     solve_quadratic_equation(0.0, a, b)
 }
 
-pub(crate) fn check_axis(origin: f64, direction: f64) -> (f64, f64) {
-    let tmin_numerator = -1.0 - origin;
-    let tmax_numerator = 1.0 - origin;
+pub(crate) fn check_axis(origin: f64, direction: f64, minimum: f64, maximum: f64) -> (f64, f64) {
+    let tmin_numerator = minimum - origin;
+    let tmax_numerator = maximum - origin;
 
     let tmin;
     let tmax;
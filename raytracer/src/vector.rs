@@ -2,8 +2,17 @@ use std::ops::{Add, Div, Mul, Neg, Sub};
 
 use crate::{transform::Transform, util::equal};
 
+/// Direction in space.
+///
+/// ```
+/// use raytracer::Vector;
+///
+/// let v = Vector::new(0.0, 3.0, 4.0);
+/// assert_eq!(v.magnitude(), 5.0);
+/// assert_eq!(v.normalize(), Vector::new(0.0, 0.6, 0.8));
+/// ```
 #[derive(Debug, Clone, Copy)]
-pub(crate) struct Vector {
+pub struct Vector {
     x: f64,
     y: f64,
     z: f64,
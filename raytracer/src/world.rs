@@ -1,121 +1,479 @@
 use crate::{
-    color::Color,
+    color::{self, Color},
+    dispersion::CHANNEL_WAVELENGTHS,
     intersect::{intersection::ComputedIntersection, multiple_intersections::Intersections},
-    light::PointLight,
+    light::{AreaLight, Attenuation, Light, LightSample},
+    microfacet::Microfacet,
     point::Point,
     ray::Ray,
-    shapes::{shape::Shape, ShapeWorld},
+    rng::Rng,
+    shapes::{bvh::Bvh, shape::Shape},
     util::equal,
+    vector::Vector,
 };
 
 const REFLECTION_LIMIT: usize = 5;
 const REFRACTION_LIMIT: usize = 5;
 
+// rays averaged for the reflection of rough microfacet surfaces seen directly,
+// scaled by their fresnel reflectance, deeper reflections use one ray
+const ROUGH_REFLECTION_SAMPLES: usize = 8;
+
+// dull reflections of rough dielectrics are not worth many rays
+fn rough_reflection_samples(fresnel: Color) -> usize {
+    let weight = fresnel
+        .r()
+        .max(fresnel.g())
+        .max(fresnel.b())
+        .clamp(0.0, 1.0);
+    ((ROUGH_REFLECTION_SAMPLES as f64 * weight).ceil() as usize).max(1)
+}
+
+// samples per side of the light standing in for an emissive shape
+const EMITTER_STEPS: usize = 4;
+
+// Sampled light of a bounded emissive shape, a sphere around its bounding box.
+fn emitter_light(shape: &Shape) -> Option<Light> {
+    let emissive = shape.material().emissive();
+    if emissive == color::BLACK || !shape.is_bounded() {
+        return None;
+    }
+    let bounds = shape.bounds();
+    let diagonal = bounds.maximum() - bounds.minimum();
+    let center = bounds.minimum() + diagonal / 2.0;
+    // the radius of a sphere for its own bounding box
+    let radius = diagonal.magnitude() / (2.0 * 3f64.sqrt());
+    let light = AreaLight::sphere(center, radius, emissive)
+        .with_steps(EMITTER_STEPS, EMITTER_STEPS)
+        .with_jitter(true)
+        .with_attenuation(Attenuation::InverseSquare { radius });
+    Some(light.into())
+}
+
+// Light standing in for an emissive shape, `path` leads to the shape from the
+// objects of the world: the index of its object, then the index of the child
+// at every group or csg on the way.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Emitter {
+    light: Light,
+    path: Vec<usize>,
+}
+
+impl Emitter {
+    pub(crate) fn light(&self) -> &Light {
+        &self.light
+    }
+}
+
+// children of groups and csg, in the order of the emitter paths
+fn children(shape: &Shape) -> Vec<&Shape> {
+    if let Some(group) = shape.as_group() {
+        group.iter().collect()
+    } else if let Some(csg) = shape.as_csg() {
+        vec![csg.left(), csg.right()]
+    } else {
+        vec![]
+    }
+}
+
+// lights of every emissive shape inside `shape`, found at `path`
+fn emitter_lights(shape: &Shape, path: &mut Vec<usize>, emitters: &mut Vec<Emitter>) {
+    let children = children(shape);
+    if children.is_empty() {
+        if let Some(light) = emitter_light(shape) {
+            let path = path.clone();
+            emitters.push(Emitter { light, path });
+        }
+    }
+    for (index, child) in children.into_iter().enumerate() {
+        path.push(index);
+        emitter_lights(child, path, emitters);
+        path.pop();
+    }
+}
+
+// emissive shapes without bounds glow, but cannot light other shapes
+fn has_unbounded_emitter(shape: &Shape) -> bool {
+    let children = children(shape);
+    match children.is_empty() {
+        true => shape.material().emissive() != color::BLACK && !shape.is_bounded(),
+        false => children.into_iter().any(has_unbounded_emitter),
+    }
+}
+
+/// Lights and objects of a scene.
+///
+/// ```
+/// use raytracer::{Color, Point, PointLight, Shape, World};
+///
+/// let world = World::default()
+///     .with_light(PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0)))
+///     .with_object(Shape::sphere());
+/// assert_eq!(world.lights().len(), 1);
+/// assert_eq!(world.objects().len(), 1);
+/// ```
 #[derive(Debug, Clone, PartialEq, Default)]
-pub(crate) struct World {
-    lights: Vec<PointLight>,
+pub struct World {
+    lights: Vec<Light>,
     objects: Vec<Shape>,
+    bvh: Bvh,
+    // lights of the emissive shapes
+    emitters: Vec<Emitter>,
 }
 
 impl World {
-    pub fn new(lights: Vec<PointLight>, objects: Vec<Shape>) -> World {
-        World { lights, objects }
+    pub fn new(lights: Vec<Light>, mut objects: Vec<Shape>) -> World {
+        objects.iter_mut().for_each(Shape::build_bvh);
+        let bvh = Bvh::new(&objects);
+        let mut emitters = Vec::new();
+        for (index, object) in objects.iter().enumerate() {
+            emitter_lights(object, &mut vec![index], &mut emitters);
+        }
+        World {
+            lights,
+            objects,
+            bvh,
+            emitters,
+        }
     }
 
-    pub fn intersect(&self, ray: &Ray) -> Intersections {
-        self.objects
-            .iter()
-            .map(|object| object.intersect(ray))
-            .reduce(|merged_intersections, intersections| merged_intersections.merge(intersections))
-            .unwrap_or_default()
+    pub fn add_object(&mut self, mut object: Shape) {
+        object.build_bvh();
+        emitter_lights(&object, &mut vec![self.objects.len()], &mut self.emitters);
+        self.objects.push(object);
+        self.bvh = Bvh::new(&self.objects);
+    }
+
+    pub fn add_light<L: Into<Light>>(&mut self, light: L) {
+        self.lights.push(light.into());
+    }
+
+    pub fn with_object(mut self, object: Shape) -> World {
+        self.add_object(object);
+        self
+    }
+
+    pub fn with_light<L: Into<Light>>(mut self, light: L) -> World {
+        self.add_light(light);
+        self
+    }
+
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    pub fn objects(&self) -> &[Shape] {
+        &self.objects
+    }
+
+    // lights standing in for the bounded emissive shapes of the world
+    #[cfg(test)]
+    pub(crate) fn emitters(&self) -> &[Emitter] {
+        &self.emitters
+    }
+
+    pub(crate) fn has_unbounded_emitter(&self) -> bool {
+        self.objects.iter().any(has_unbounded_emitter)
+    }
+
+    // the shape `emitter` stands in for
+    fn emitter_shape(&self, emitter: &Emitter) -> Option<&Shape> {
+        let (object, path) = emitter.path.split_first()?;
+        path.iter()
+            .try_fold(self.objects.get(*object)?, |shape, &index| {
+                children(shape).get(index).copied()
+            })
+    }
+
+    pub(crate) fn intersect(&self, ray: &Ray) -> Intersections<'_> {
+        self.bvh
+            .intersect(&self.objects, ray)
             .update_refractive_index()
     }
 
-    pub fn shade_hit(&self, comp: &ComputedIntersection, depth: usize) -> Color {
-        self.lights
+    // `wavelength` is set once a dispersive surface split the ray into its
+    // channels, the light of a single channel is traced from then on
+    pub(crate) fn shade_hit(
+        &self,
+        comp: &ComputedIntersection,
+        depth: usize,
+        wavelength: Option<f64>,
+    ) -> Color {
+        let material = comp.object().material();
+        let glow = material.emissive() + self.emitted_lighting(comp);
+        let surface = self
+            .lights
             .iter()
             .fold(Color::default(), |total_color, light| {
-                let shadowed = self.is_shadowed(light, comp.over_point());
-
-                let surface = light.lighting(
-                    comp.object(),
-                    comp.object().material(),
-                    comp.over_point(),
-                    comp.eye_vector(),
-                    comp.normal_vector(),
-                    shadowed,
-                );
-
-                let reflected_color = self.reflected_color(comp, depth);
-                let refracted_color = self.refracted_color(comp, depth);
-
-                let material = comp.object().material();
-                if material.reflective() > 0.0 && material.transparency() > 0.0 {
-                    let reflectance = comp.schlick();
-                    total_color
-                        + surface
-                        + reflected_color * reflectance
-                        + refracted_color * (1.0 - reflectance)
-                } else {
-                    total_color + surface + reflected_color + refracted_color
-                }
+                let visibility = self.light_visibility(light, comp.over_point());
+
+                total_color
+                    + light.lighting(
+                        comp.object(),
+                        material,
+                        comp.over_point(),
+                        comp.eye_vector(),
+                        comp.normal_vector(),
+                        visibility,
+                    )
+            });
+        // traced once, whatever the number of lights
+        glow + surface + self.secondary_color(comp, depth, wavelength)
+    }
+
+    // reflected and refracted light, weighted by fresnel for glass-like materials
+    fn secondary_color(
+        &self,
+        comp: &ComputedIntersection,
+        depth: usize,
+        wavelength: Option<f64>,
+    ) -> Color {
+        let reflected_color = self.reflected_color(comp, depth, wavelength);
+        let refracted_color = self.refracted_color(comp, depth, wavelength);
+
+        let material = comp.object().material();
+        if material.reflective() > 0.0 && material.transparency() > 0.0 {
+            let reflectance = comp.schlick();
+            reflected_color * reflectance + refracted_color * (1.0 - reflectance)
+        } else {
+            reflected_color + refracted_color
+        }
+    }
+
+    // direct light of the emissive shapes, a shape is not lit by itself
+    pub(crate) fn emitted_lighting(&self, comp: &ComputedIntersection) -> Color {
+        self.emitters
+            .iter()
+            .filter(|emitter| !self.is_emitter_of(emitter, comp.object()))
+            .fold(color::BLACK, |total, emitter| {
+                let emitter = emitter.light();
+                let visibility = self.emitter_visibility(emitter, comp.over_point());
+                total
+                    + emitter.direct_lighting(
+                        comp.object(),
+                        comp.object().material(),
+                        comp.over_point(),
+                        comp.eye_vector(),
+                        comp.normal_vector(),
+                        visibility,
+                    )
             })
     }
 
-    pub fn color_at(&self, ray: &Ray) -> Color {
-        self.recursive_color_at(ray, 0)
+    fn is_emitter_of(&self, emitter: &Emitter, object: &Shape) -> bool {
+        self.emitter_shape(emitter)
+            .is_some_and(|shape| std::ptr::eq(shape, object))
     }
 
-    fn recursive_color_at(&self, ray: &Ray, depth: usize) -> Color {
+    pub(crate) fn color_at(&self, ray: &Ray) -> Color {
+        self.recursive_color_at(ray, 0, None)
+    }
+
+    fn recursive_color_at(&self, ray: &Ray, depth: usize, wavelength: Option<f64>) -> Color {
         self.intersect(ray)
             .hit()
-            .map(|hit| self.shade_hit(hit, depth))
+            .map(|hit| self.shade_hit(hit, depth, wavelength) & hit.medium_transmittance())
             .unwrap_or_default()
     }
 
-    fn is_shadowed(&self, light: &PointLight, point: &Point) -> bool {
-        let v = light.position() - *point;
-        let distance = v.magnitude();
-        let direction = v.normalize();
-        let r = Ray::new(*point, direction);
+    // light left on the way from `point` to the light sample, every object
+    // in between filters it once through its transmittance, emissive shapes
+    // are skipped toward emitters so they do not shadow their own light
+    fn transmittance(&self, sample: &LightSample, point: &Point, emitter: bool) -> Color {
+        let r = Ray::new(*point, sample.direction);
         let intersections = self.intersect(&r);
-        intersections.hit().map_or(false, |hit| hit.t() < distance)
+        let mut blockers: Vec<&Shape> = Vec::new();
+        let mut transmittance = color::WHITE;
+        for hit in intersections.iter() {
+            let seen = blockers.iter().any(|b| std::ptr::eq(*b, hit.object()));
+            if hit.t() < 0.0 {
+                continue;
+            }
+            if hit.t() >= sample.distance {
+                break;
+            }
+            // absorbed on the way through the object being left or crossed
+            transmittance = transmittance & hit.medium_transmittance();
+            if seen {
+                continue;
+            }
+            let material = hit.object().material();
+            if !material.casts_shadow() || (emitter && material.emissive() != color::BLACK) {
+                continue;
+            }
+            transmittance = transmittance & material.transmittance();
+            if transmittance == color::BLACK {
+                break;
+            }
+            blockers.push(hit.object());
+        }
+        transmittance
+    }
+
+    #[cfg(test)]
+    fn is_shadowed(&self, light: &Light, point: &Point) -> bool {
+        self.light_visibility(light, point) == color::BLACK
+    }
+
+    // average light of the samples reaching `point`
+    pub fn light_visibility(&self, light: &Light, point: &Point) -> Color {
+        self.visibility(light, point, false)
     }
 
-    pub(crate) fn reflected_color(&self, comp: &ComputedIntersection, depth: usize) -> Color {
+    pub(crate) fn emitter_visibility(&self, emitter: &Light, point: &Point) -> Color {
+        self.visibility(emitter, point, true)
+    }
+
+    fn visibility(&self, light: &Light, point: &Point, emitter: bool) -> Color {
+        let samples = light.samples(point);
+        let total = samples.iter().fold(color::BLACK, |total, sample| {
+            total + self.transmittance(sample, point, emitter)
+        });
+        total / samples.len() as f64
+    }
+
+    pub(crate) fn reflected_color(
+        &self,
+        comp: &ComputedIntersection,
+        depth: usize,
+        wavelength: Option<f64>,
+    ) -> Color {
+        if let Some(microfacet) = comp.object().material().microfacet() {
+            return self.microfacet_reflection(comp, depth, wavelength, &microfacet);
+        }
         if depth >= REFLECTION_LIMIT || equal(comp.object().material().reflective(), 0.0) {
             return Color::default();
         }
-        let reflect_ray = Ray::new(*comp.over_point(), *comp.reflect_vector());
-        let color = self.recursive_color_at(&reflect_ray, depth + 1);
+        let reflect_vector = comp.reflect_vector();
+        let color = self.glossy_color(comp, comp.over_point(), reflect_vector, depth, wavelength);
         color * comp.object().material().reflective()
     }
 
-    pub(crate) fn refracted_color(&self, comp: &ComputedIntersection, depth: usize) -> Color {
-        if depth >= REFRACTION_LIMIT || equal(comp.object().material().transparency(), 0.0) {
+    // reflection weighted by fresnel, rough surfaces average rays around the
+    // mirror direction sampled from the microfacet distribution
+    fn microfacet_reflection(
+        &self,
+        comp: &ComputedIntersection,
+        depth: usize,
+        wavelength: Option<f64>,
+        microfacet: &Microfacet,
+    ) -> Color {
+        if depth >= REFLECTION_LIMIT {
             return Color::default();
         }
+        let base_color = comp
+            .object()
+            .material()
+            .color_at(comp.object(), comp.over_point());
+        let normal = comp.normal_vector();
+        let eye = comp.eye_vector();
 
-        let n1 = comp.n1().expect("n1 should be already calculated");
-        let n2 = comp.n2().expect("n1 should be already calculated");
+        if microfacet.is_smooth() {
+            let reflect_ray = Ray::new(*comp.over_point(), *comp.reflect_vector());
+            let fresnel = microfacet.fresnel(base_color, eye.dot(normal));
+            return self.recursive_color_at(&reflect_ray, depth + 1, wavelength) & fresnel;
+        }
 
-        let n_ratio = n1 / n2;
-        let cos_i = comp.eye_vector().dot(comp.normal_vector());
-        let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
+        let samples = match depth {
+            0 => rough_reflection_samples(microfacet.fresnel(base_color, eye.dot(normal))),
+            _ => 1,
+        };
+        let mut rng = Rng::at_point(depth as u64, comp.over_point());
+        let total = (0..samples).fold(Color::default(), |total, _| {
+            let half = microfacet.sample_half_vector(normal, rng.next_f64(), rng.next_f64());
+            let direction = half * (2.0 * eye.dot(&half)) - *eye;
+            let weight = microfacet.sample_weight(base_color, normal, eye, &half, &direction);
+            if weight == color::BLACK {
+                return total;
+            }
+            let reflect_ray = Ray::new(*comp.over_point(), direction);
+            total + (self.recursive_color_at(&reflect_ray, depth + 1, wavelength) & weight)
+        });
+        total / samples as f64
+    }
 
-        if sin2_t >= 1.0 {
+    pub(crate) fn refracted_color(
+        &self,
+        comp: &ComputedIntersection,
+        depth: usize,
+        wavelength: Option<f64>,
+    ) -> Color {
+        if depth >= REFRACTION_LIMIT || equal(comp.object().material().transparency(), 0.0) {
             return Color::default();
         }
 
-        let cos_t = (1.0 - sin2_t).sqrt();
-        let direction =
-            *comp.normal_vector() * (n_ratio * cos_i - cos_t) - *comp.eye_vector() * n_ratio;
+        if comp.is_dispersive() {
+            let color = self.dispersed_color(comp, depth, wavelength);
+            return color * comp.object().material().transparency();
+        }
+
+        let direction = match comp.refract_vector() {
+            Some(direction) => direction,
+            None => return Color::default(),
+        };
 
-        let refract_ray = Ray::new(*comp.under_point(), direction);
-        let color = self.recursive_color_at(&refract_ray, depth + 1);
+        let color = self.glossy_color(comp, comp.under_point(), &direction, depth, wavelength);
 
         color * comp.object().material().transparency()
     }
+
+    // every channel is refracted with the index of its own wavelength, a ray
+    // already split keeps its wavelength instead of splitting again
+    fn dispersed_color(
+        &self,
+        comp: &ComputedIntersection,
+        depth: usize,
+        wavelength: Option<f64>,
+    ) -> Color {
+        let channel = |wavelength: f64| match comp.refract_vector_at(wavelength) {
+            Some(direction) => self.glossy_color(
+                comp,
+                comp.under_point(),
+                &direction,
+                depth,
+                Some(wavelength),
+            ),
+            None => Color::default(),
+        };
+        if let Some(wavelength) = wavelength {
+            return channel(wavelength);
+        }
+        let [red, green, blue] = CHANNEL_WAVELENGTHS.map(channel);
+        Color::new(red.r(), green.g(), blue.b())
+    }
+
+    // color seen from `origin` along `direction`, rough materials average rays
+    // spread around it, only the first bounce is sampled several times
+    fn glossy_color(
+        &self,
+        comp: &ComputedIntersection,
+        origin: &Point,
+        direction: &Vector,
+        depth: usize,
+        wavelength: Option<f64>,
+    ) -> Color {
+        let material = comp.object().material();
+        if !material.is_glossy() {
+            return self.recursive_color_at(&Ray::new(*origin, *direction), depth + 1, wavelength);
+        }
+
+        let samples = match depth {
+            0 => material.glossy_samples(),
+            _ => 1,
+        };
+        // perturbed rays stay on the same side of the surface as the perfect one
+        let side = comp.normal_vector().dot(direction).signum();
+        let mut rng = Rng::at_point(depth as u64, origin);
+        let total = (0..samples).fold(Color::default(), |total, _| {
+            let perturbed = material.glossy_direction(direction, rng.next_f64(), rng.next_f64());
+            let perturbed = match comp.normal_vector().dot(&perturbed) * side > 0.0 {
+                true => perturbed,
+                false => *direction,
+            };
+            total + self.recursive_color_at(&Ray::new(*origin, perturbed), depth + 1, wavelength)
+        });
+        total / samples as f64
+    }
 }
 
 #[cfg(test)]
@@ -124,7 +482,9 @@ mod test {
     use crate::{
         camera::Camera,
         color,
+        dispersion::Dispersion,
         intersect::intersection::Intersection,
+        light::{AreaLight, DirectionalLight, PointLight},
         material::Material,
         patterns::pattern::Pattern,
         shapes::ShapeMaterial,
@@ -145,7 +505,7 @@ mod test {
         );
         let s2 = Shape::sphere().with_transform(Transform::scaling(0.5, 0.5, 0.5));
 
-        World::new(vec![point_light], vec![s1, s2])
+        World::new(vec![point_light.into()], vec![s1, s2])
     }
 
     #[test]
@@ -173,18 +533,18 @@ mod test {
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let s = w.objects[0].clone();
         let comp = Intersection::new(4.0, &s).prepare_computations(&r).unwrap();
-        let c = w.shade_hit(&comp, 0);
+        let c = w.shade_hit(&comp, 0, None);
         assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
     }
 
     #[test]
     fn shading_an_intersection_from_the_inside() {
         let mut w = default_world();
-        w.lights = vec![PointLight::new(Point::new(0.0, 0.25, 0.0), color::WHITE)];
+        w.lights = vec![PointLight::new(Point::new(0.0, 0.25, 0.0), color::WHITE).into()];
         let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
         let s = w.objects[1].clone();
         let comp = Intersection::new(0.5, &s).prepare_computations(&r).unwrap();
-        let c = w.shade_hit(&comp, 0);
+        let c = w.shade_hit(&comp, 0, None);
         assert_eq!(c, Color::new(0.90498, 0.90498, 0.90498));
     }
 
@@ -260,17 +620,97 @@ mod test {
         assert!(!w.is_shadowed(&w.lights[0], &p));
     }
 
+    #[test]
+    fn shadows_of_directional_light_are_cast_along_its_direction() {
+        let w = default_world();
+        let light = Light::from(DirectionalLight::new(
+            Vector::new(0.0, -1.0, 0.0),
+            color::WHITE,
+        ));
+        assert!(w.is_shadowed(&light, &Point::new(0.0, -100.0, 0.0)));
+        assert!(!w.is_shadowed(&light, &Point::new(2.0, -100.0, 0.0)));
+        assert!(!w.is_shadowed(&light, &Point::new(0.0, 100.0, 0.0)));
+    }
+
+    #[test]
+    fn transparent_objects_cast_colored_shadows() {
+        let light = PointLight::new(Point::new(0.0, 10.0, 0.0), color::WHITE);
+        let glass = Shape::sphere().with_material(
+            Material::default()
+                .with_color(Color::new(1.0, 0.5, 0.0))
+                .with_transparency(0.8),
+        );
+        let w = World::new(vec![light.into()], vec![glass]);
+        let visibility = w.light_visibility(&w.lights[0], &Point::new(0.0, -2.0, 0.0));
+        // the sphere is crossed twice but filters the light once
+        assert_eq!(visibility, Color::new(0.8, 0.4, 0.0));
+        assert!(!w.is_shadowed(&w.lights[0], &Point::new(0.0, -2.0, 0.0)));
+    }
+
+    #[test]
+    fn shadows_of_several_transparent_objects_add_up() {
+        let light = PointLight::new(Point::new(0.0, 10.0, 0.0), color::WHITE);
+        let glass = Material::default().with_transparency(0.5);
+        let s1 = Shape::sphere().with_material(glass.clone());
+        let s2 = Shape::sphere()
+            .with_transform(Transform::translation(0.0, 3.0, 0.0))
+            .with_material(glass);
+        let w = World::new(vec![light.into()], vec![s1, s2]);
+        let visibility = w.light_visibility(&w.lights[0], &Point::new(0.0, -2.0, 0.0));
+        assert_eq!(visibility, Color::new(0.25, 0.25, 0.25));
+    }
+
+    #[test]
+    fn objects_may_not_cast_shadows() {
+        let mut w = default_world();
+        let p = Point::new(10.0, -10.0, 10.0);
+        for object in w.objects.iter_mut() {
+            *object = object
+                .clone()
+                .with_material(object.material().clone().with_casts_shadow(false));
+        }
+        assert!(!w.is_shadowed(&w.lights[0], &p));
+        assert_eq!(w.light_visibility(&w.lights[0], &p), color::WHITE);
+    }
+
+    #[test]
+    fn area_light_visibility() {
+        let w = default_world();
+        let light = Light::from(
+            AreaLight::rect(
+                Point::new(-0.5, -0.5, -5.0),
+                Vector::new(1.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+                color::WHITE,
+            )
+            .with_steps(2, 2),
+        );
+        let cases = [
+            (Point::new(0.0, 0.0, 2.0), 0.0),
+            (Point::new(1.0, -1.0, 2.0), 0.25),
+            (Point::new(1.5, 0.0, 2.0), 0.5),
+            (Point::new(1.25, 1.25, 3.0), 0.75),
+            (Point::new(0.0, 0.0, -2.0), 1.0),
+        ];
+        for (point, expected) in cases {
+            assert_eq!(
+                w.light_visibility(&light, &point),
+                Color::new(expected, expected, expected)
+            );
+        }
+    }
+
     #[test]
     fn shade_hit_with_intersection_in_shadow() {
         let light_source = PointLight::new(Point::new(0.0, 0.0, -10.0), color::WHITE);
         let s1 = Shape::sphere();
         let s2 = Shape::sphere().with_transform(Transform::translation(0.0, 0.0, 10.0));
-        let w = World::new(vec![light_source], vec![s1, s2.clone()]);
+        let w = World::new(vec![light_source.into()], vec![s1, s2.clone()]);
         let r = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
         let comp = Intersection::new(4.0, &s2)
             .prepare_computations(&r)
             .unwrap();
-        let c = w.shade_hit(&comp, 0);
+        let c = w.shade_hit(&comp, 0, None);
         assert_eq!(c, Color::new(0.1, 0.1, 0.1));
     }
 
@@ -282,7 +722,7 @@ mod test {
         let comp = Intersection::new(1.0, &w.objects[1])
             .prepare_computations(&r)
             .unwrap();
-        let color = w.reflected_color(&comp, 0);
+        let color = w.reflected_color(&comp, 0, None);
         assert_eq!(color, color::BLACK);
     }
 
@@ -292,7 +732,7 @@ mod test {
         let shape = Shape::plane()
             .with_reflective(0.5)
             .with_transform(Transform::translation(0.0, -1.0, 0.0));
-        w.objects.push(shape);
+        w.add_object(shape);
 
         let r = Ray::new(
             Point::new(0.0, 0.0, -3.0),
@@ -306,17 +746,68 @@ mod test {
         let comp = Intersection::new(std::f64::consts::SQRT_2, &w.objects[2])
             .prepare_computations(&r)
             .unwrap();
-        let color = w.reflected_color(&comp, 0);
+        let color = w.reflected_color(&comp, 0, None);
         assert_eq!(color, Color::new(0.19033, 0.23791, 0.14274));
     }
 
+    #[test]
+    fn reflected_color_for_a_microfacet_material() {
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(
+                0.0,
+                -std::f64::consts::FRAC_1_SQRT_2,
+                std::f64::consts::FRAC_1_SQRT_2,
+            ),
+        );
+        let reflection = |roughness: f64| {
+            let mut w = default_world();
+            let material = Material::default().with_microfacet(Microfacet::new(1.0, roughness));
+            w.add_object(
+                Shape::plane()
+                    .with_material(material)
+                    .with_transform(Transform::translation(0.0, -1.0, 0.0)),
+            );
+            let comp = Intersection::new(std::f64::consts::SQRT_2, &w.objects[2])
+                .prepare_computations(&r)
+                .unwrap();
+            w.reflected_color(&comp, 0, None)
+        };
+        // a smooth white metal is a perfect mirror
+        assert_eq!(reflection(0.0), Color::new(0.38066, 0.47583, 0.2855));
+        // a rough one blurs the reflection but stays finite and lit
+        let rough = reflection(0.5);
+        assert!(rough != color::BLACK && rough.g() < 1.0);
+        assert_eq!(rough, reflection(0.5));
+    }
+
+    #[test]
+    fn rough_reflection_samples_follow_fresnel() {
+        let plastic = Microfacet::new(0.0, 0.5);
+        let gold = Microfacet::new(1.0, 0.5);
+        let base_color = Color::new(1.0, 0.78, 0.34);
+        assert_eq!(
+            rough_reflection_samples(plastic.fresnel(base_color, 1.0)),
+            1
+        );
+        assert_eq!(
+            rough_reflection_samples(gold.fresnel(base_color, 1.0)),
+            ROUGH_REFLECTION_SAMPLES
+        );
+        // even dielectrics reflect most of the light at grazing angles
+        assert_eq!(
+            rough_reflection_samples(plastic.fresnel(base_color, 0.0)),
+            ROUGH_REFLECTION_SAMPLES
+        );
+    }
+
     #[test]
     fn shade_hit_with_reflective_material() {
         let mut w = default_world();
         let shape = Shape::plane()
             .with_reflective(0.5)
             .with_transform(Transform::translation(0.0, -1.0, 0.0));
-        w.objects.push(shape);
+        w.add_object(shape);
 
         let r = Ray::new(
             Point::new(0.0, 0.0, -3.0),
@@ -330,10 +821,54 @@ mod test {
         let comp = Intersection::new(std::f64::consts::SQRT_2, &w.objects[2])
             .prepare_computations(&r)
             .unwrap();
-        let color = w.shade_hit(&comp, 0);
+        let color = w.shade_hit(&comp, 0, None);
         assert_eq!(color, Color::new(0.87675, 0.92433, 0.82917));
     }
 
+    #[test]
+    fn shade_hit_reflects_once_whatever_the_number_of_lights() {
+        // an unlit mirror facing a glowing sphere, neither depends on the lights
+        let mirror = Shape::plane()
+            .with_material(
+                Material::default()
+                    .with_ambient(0.0)
+                    .with_diffuse(0.0)
+                    .with_specular(0.0)
+                    .with_reflective(1.0),
+            )
+            .with_transform(Transform::translation(0.0, -1.0, 0.0));
+        let ball = Shape::sphere().with_material(
+            Material::default()
+                .with_ambient(0.0)
+                .with_diffuse(0.0)
+                .with_specular(0.0)
+                .with_emissive(Color::new(0.2, 0.4, 0.6)),
+        );
+        let light = |x: f64| PointLight::new(Point::new(x, 10.0, -10.0), color::WHITE).into();
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(
+                0.0,
+                -std::f64::consts::FRAC_1_SQRT_2,
+                std::f64::consts::FRAC_1_SQRT_2,
+            ),
+        );
+        let shade = |lights: Vec<Light>| {
+            let w = World::new(lights, vec![mirror.clone(), ball.clone()]);
+            let comp = Intersection::new(std::f64::consts::SQRT_2, &w.objects[0])
+                .prepare_computations(&r)
+                .unwrap();
+            (
+                w.shade_hit(&comp, 0, None),
+                w.reflected_color(&comp, 0, None),
+            )
+        };
+        let (one_light, reflected) = shade(vec![light(-10.0)]);
+        let (two_lights, _) = shade(vec![light(-10.0), light(10.0)]);
+        assert_eq!(one_light, reflected);
+        assert_eq!(two_lights, reflected);
+    }
+
     #[test]
     fn color_at_with_mutually_reflective_surfaces() {
         let lower = Shape::plane()
@@ -343,7 +878,7 @@ mod test {
             .with_reflective(1.0)
             .with_transform(Transform::translation(0.0, 1.0, 0.0));
         let w = World::new(
-            vec![PointLight::new(Point::new(0.0, 0.0, 0.0), color::WHITE)],
+            vec![PointLight::new(Point::new(0.0, 0.0, 0.0), color::WHITE).into()],
             vec![lower, upper],
         );
 
@@ -357,7 +892,7 @@ mod test {
         let shape = Shape::plane()
             .with_reflective(0.5)
             .with_transform(Transform::translation(0.0, -1.0, 0.0));
-        w.objects.push(shape);
+        w.add_object(shape);
 
         let r = Ray::new(
             Point::new(0.0, 0.0, -3.0),
@@ -372,7 +907,7 @@ mod test {
             .prepare_computations(&r)
             .unwrap();
 
-        let color = w.reflected_color(&comp, REFLECTION_LIMIT);
+        let color = w.reflected_color(&comp, REFLECTION_LIMIT, None);
         assert_eq!(color, color::BLACK);
     }
 
@@ -382,7 +917,7 @@ mod test {
         let s = w.objects[0].clone();
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let comps = Intersections::new(vec![4.0, 6.0], &s, &r).update_refractive_index();
-        let c = w.refracted_color(comps.get(0).unwrap(), 0);
+        let c = w.refracted_color(comps.get(0).unwrap(), 0, None);
         assert_eq!(c, color::BLACK);
     }
 
@@ -395,7 +930,7 @@ mod test {
             .with_refractive_index(1.5);
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let comps = Intersections::new(vec![4.0, 6.0], &w.objects[0], &r).update_refractive_index();
-        let c = w.refracted_color(comps.get(0).unwrap(), REFRACTION_LIMIT);
+        let c = w.refracted_color(comps.get(0).unwrap(), REFRACTION_LIMIT, None);
         assert_eq!(c, color::BLACK);
     }
 
@@ -419,7 +954,7 @@ mod test {
             &r,
         )
         .update_refractive_index();
-        let c = w.refracted_color(comps.get(0).unwrap(), 0);
+        let c = w.refracted_color(comps.get(0).unwrap(), 0, None);
         assert_eq!(c, color::BLACK);
     }
 
@@ -437,10 +972,144 @@ mod test {
 
         let r = Ray::new(Point::new(0.0, 0.0, 0.1), Vector::new(0.0, 1.0, 0.0));
         let xs = w.intersect(&r);
-        let c = w.refracted_color(xs.get(2).unwrap(), 0);
+        let c = w.refracted_color(xs.get(2).unwrap(), 0, None);
         assert_eq!(c, Color::new(0.0, 0.99888, 0.04722));
     }
 
+    #[test]
+    fn dispersion_refracts_every_channel_on_its_own() {
+        let refracted = |dispersion: Dispersion| {
+            let mut w = default_world();
+            w.objects[0] = w.objects[0]
+                .clone()
+                .with_ambient(1.0)
+                .with_pattern(Pattern::dummy());
+            let prism = Material::default()
+                .with_transparency(1.0)
+                .with_refractive_index(1.5)
+                .with_dispersion(dispersion);
+            w.objects[1] = w.objects[1].clone().with_material(prism);
+            let r = Ray::new(Point::new(0.0, 0.0, 0.1), Vector::new(0.0, 1.0, 0.0));
+            let xs = w.intersect(&r);
+            w.refracted_color(xs.get(2).unwrap(), 0, None)
+        };
+        // without spread every channel follows the same ray
+        assert_eq!(
+            refracted(Dispersion::Abbe(1e12)),
+            Color::new(0.0, 0.99888, 0.04722)
+        );
+        assert_ne!(
+            refracted(Dispersion::Abbe(5.0)),
+            Color::new(0.0, 0.99888, 0.04722)
+        );
+    }
+
+    #[test]
+    fn split_rays_trace_a_single_channel() {
+        let mut w = default_world();
+        w.objects[0] = w.objects[0]
+            .clone()
+            .with_ambient(1.0)
+            .with_pattern(Pattern::dummy());
+        let prism = Material::default()
+            .with_transparency(1.0)
+            .with_refractive_index(1.5)
+            .with_dispersion(Dispersion::Abbe(5.0));
+        w.objects[1] = w.objects[1].clone().with_material(prism);
+        let r = Ray::new(Point::new(0.0, 0.0, 0.1), Vector::new(0.0, 1.0, 0.0));
+        let xs = w.intersect(&r);
+        let comp = xs.get(2).unwrap();
+        let [red, green, blue] = CHANNEL_WAVELENGTHS.map(|wavelength| {
+            let split = w.refracted_color(comp, 0, Some(wavelength));
+            // the ray keeps the refraction of its wavelength only
+            let direction = comp.refract_vector_at(wavelength).unwrap();
+            let ray = Ray::new(*comp.under_point(), direction);
+            assert_eq!(split, w.recursive_color_at(&ray, 1, Some(wavelength)));
+            split
+        });
+        let color = w.refracted_color(comp, 0, None);
+        assert_eq!(color, Color::new(red.r(), green.g(), blue.b()));
+    }
+
+    #[test]
+    fn glossy_reflection_blurs_the_mirror_image() {
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(
+                0.0,
+                -std::f64::consts::FRAC_1_SQRT_2,
+                std::f64::consts::FRAC_1_SQRT_2,
+            ),
+        );
+        let reflection = |spread: f64| {
+            let mut w = default_world();
+            let floor = Material::default()
+                .with_reflective(0.5)
+                .with_glossy_spread(spread);
+            w.add_object(
+                Shape::plane()
+                    .with_material(floor)
+                    .with_transform(Transform::translation(0.0, -1.0, 0.0)),
+            );
+            let comp = Intersection::new(std::f64::consts::SQRT_2, &w.objects[2])
+                .prepare_computations(&r)
+                .unwrap();
+            w.reflected_color(&comp, 0, None)
+        };
+        assert_eq!(reflection(1e-6), Color::new(0.19033, 0.23791, 0.14274));
+        let glossy = reflection(0.4);
+        assert_ne!(glossy, reflection(0.0));
+        // the same rays are sampled every time
+        assert_eq!(glossy, reflection(0.4));
+    }
+
+    #[test]
+    fn frosted_refraction_averages_several_rays() {
+        let mut w = default_world();
+        w.objects[0] = w.objects[0]
+            .clone()
+            .with_ambient(1.0)
+            .with_pattern(Pattern::dummy());
+        let frosted = Material::default()
+            .with_transparency(1.0)
+            .with_refractive_index(1.5)
+            .with_glossy_spread(0.3)
+            .with_glossy_samples(4);
+        w.objects[1] = w.objects[1].clone().with_material(frosted);
+
+        let r = Ray::new(Point::new(0.0, 0.0, 0.1), Vector::new(0.0, 1.0, 0.0));
+        let xs = w.intersect(&r);
+        let c = w.refracted_color(xs.get(2).unwrap(), 0, None);
+        assert_ne!(c, Color::new(0.0, 0.99888, 0.04722));
+        assert!(c.g() > 0.5);
+    }
+
+    #[test]
+    fn thick_glass_absorbs_more_light() {
+        let seen_through = |radius: f64| {
+            let backdrop = Shape::plane().with_emissive(color::WHITE).with_transform(
+                Transform::rotation_x(std::f64::consts::FRAC_PI_2).translate(0.0, 0.0, 10.0),
+            );
+            let glass = Shape::sphere()
+                .with_material(
+                    Material::default()
+                        .with_transparency(1.0)
+                        .with_absorption(Color::new(0.0, 1.0, 1.0))
+                        .with_density(0.5),
+                )
+                .with_transform(Transform::scaling(radius, radius, radius));
+            let w = World::new(vec![], vec![backdrop, glass]);
+            let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+            w.color_at(&r)
+        };
+        // the light crosses twice the radius of glass
+        let thin = seen_through(1.0);
+        assert_float_eq!(thin.r(), 1.0);
+        assert_float_eq!(thin.g(), (-1.0_f64).exp());
+        let thick = seen_through(2.0);
+        assert_float_eq!(thick.g(), (-2.0_f64).exp());
+    }
+
     #[test]
     fn shade_hit_with_transparent_material() {
         let mut w = default_world();
@@ -453,8 +1122,8 @@ mod test {
             .with_ambient(0.5)
             .with_transform(Transform::translation(0.0, -3.5, -0.5));
 
-        w.objects.push(floor);
-        w.objects.push(ball);
+        w.add_object(floor);
+        w.add_object(ball);
 
         let r = Ray::new(
             Point::new(0.0, 0.0, -3.0),
@@ -465,8 +1134,9 @@ mod test {
             ),
         );
         let xs = w.intersect(&r);
-        let color = w.shade_hit(xs.get(0).unwrap(), 0);
-        assert_eq!(color, Color::new(0.93642, 0.68642, 0.68642));
+        let color = w.shade_hit(xs.get(0).unwrap(), 0, None);
+        // the ball is lit through the half transparent floor
+        assert_eq!(color, Color::new(1.12547, 0.68642, 0.68642));
     }
 
     #[test]
@@ -482,8 +1152,8 @@ mod test {
             .with_ambient(0.5)
             .with_transform(Transform::translation(0.0, -3.5, -0.5));
 
-        w.objects.push(floor);
-        w.objects.push(ball);
+        w.add_object(floor);
+        w.add_object(ball);
 
         let r = Ray::new(
             Point::new(0.0, 0.0, -3.0),
@@ -494,7 +1164,112 @@ mod test {
             ),
         );
         let xs = w.intersect(&r);
-        let color = w.shade_hit(xs.get(0).unwrap(), 0);
-        assert_eq!(color, Color::new(0.93391, 0.69643, 0.69243));
+        let color = w.shade_hit(xs.get(0).unwrap(), 0, None);
+        // the ball is lit through the half transparent floor
+        assert_eq!(color, Color::new(1.11500, 0.69643, 0.69243));
+    }
+
+    #[test]
+    fn emissive_color_is_added_regardless_of_lighting() {
+        let lamp = Shape::sphere()
+            .with_emissive(Color::new(1.0, 0.5, 0.25))
+            .with_ambient(0.0)
+            .with_diffuse(0.0)
+            .with_specular(0.0);
+        let w = World::default().with_object(lamp);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(w.color_at(&r), Color::new(1.0, 0.5, 0.25));
+    }
+
+    #[test]
+    fn bounded_emissive_shapes_are_lights() {
+        let lamp = Shape::sphere().with_emissive(color::WHITE).with_transform(
+            Transform::translation(1.0, 2.0, 3.0) * Transform::scaling(2.0, 2.0, 2.0),
+        );
+        let glowing_floor = Shape::plane().with_emissive(color::WHITE);
+        let mut group = Shape::group();
+        group.add_shape(Shape::cube().with_emissive(Color::new(0.5, 0.0, 0.0)));
+        let w = World::new(vec![], vec![lamp, glowing_floor, group]);
+
+        assert_eq!(w.emitters().len(), 2);
+        let lamp_light = AreaLight::sphere(Point::new(1.0, 2.0, 3.0), 2.0, color::WHITE)
+            .with_steps(EMITTER_STEPS, EMITTER_STEPS)
+            .with_jitter(true)
+            .with_attenuation(Attenuation::InverseSquare { radius: 2.0 });
+        assert_eq!(*w.emitters()[0].light(), lamp_light.into());
+        assert_eq!(w.emitters()[0].path, vec![0]);
+        assert_eq!(
+            w.emitters()[1].light().intensity(),
+            Color::new(0.5, 0.0, 0.0)
+        );
+        assert_eq!(w.emitters()[1].path, vec![2, 0]);
+        assert!(w.has_unbounded_emitter());
+    }
+
+    #[test]
+    fn emitters_are_linked_to_their_own_shape() {
+        let lamp = Shape::sphere().with_emissive(color::WHITE);
+        let mut group = Shape::group();
+        group.add_shape(lamp.clone());
+        // two lamps at the same place make identical lights
+        let w = World::new(vec![], vec![group, lamp]);
+        assert_eq!(w.emitters()[0].light(), w.emitters()[1].light());
+        assert!(!w.has_unbounded_emitter());
+
+        let grouped = &w.objects[0].as_group().unwrap()[0];
+        let single = &w.objects[1];
+        assert!(w.is_emitter_of(&w.emitters()[0], grouped));
+        assert!(!w.is_emitter_of(&w.emitters()[0], single));
+        assert!(w.is_emitter_of(&w.emitters()[1], single));
+        assert!(!w.is_emitter_of(&w.emitters()[1], grouped));
+    }
+
+    #[test]
+    fn emissive_shapes_light_other_surfaces() {
+        let lamp = Shape::sphere()
+            .with_emissive(color::WHITE)
+            .with_transform(Transform::translation(0.0, 3.0, 0.0));
+        let floor = Shape::plane().with_ambient(0.0).with_specular(0.0);
+        let w = World::default().with_object(floor).with_object(lamp);
+
+        // the floor straight under the lamp
+        let r = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let under = w.color_at(&r);
+        // at most the diffuse light of the lamp center, 3 units away
+        assert!(under.r() > 0.05 && under.r() < 0.9 / 9.0, "{:?}", under);
+
+        // the lamp is not shadowed by itself, but by other shapes
+        let mut w = w;
+        w.add_object(Shape::sphere().with_transform(
+            Transform::translation(0.0, 1.0, 0.0) * Transform::scaling(0.5, 0.5, 0.5),
+        ));
+        let r = Ray::new(
+            Point::new(0.0, 0.1, -1.0),
+            Vector::new(0.0, -0.1, 1.0).normalize(),
+        );
+        assert_eq!(w.color_at(&r), color::BLACK);
+    }
+
+    #[test]
+    fn emissive_shapes_are_seen_in_mirrors() {
+        let emissive = Color::new(0.5, 0.5, 1.0);
+        let mirror = Shape::plane()
+            .with_diffuse(0.0)
+            .with_specular(0.0)
+            .with_ambient(0.0)
+            .with_reflective(1.0)
+            .with_transform(Transform::translation(0.0, -1.0, 0.0));
+        let lamp = Shape::sphere()
+            .with_emissive(emissive)
+            .with_ambient(0.0)
+            .with_diffuse(0.0)
+            .with_specular(0.0)
+            .with_transform(Transform::translation(0.0, 1.0, 2.0));
+        let w = World::default().with_object(mirror).with_object(lamp);
+        let r = Ray::new(
+            Point::new(0.0, 1.0, -2.0),
+            Vector::new(0.0, -1.0, 1.0).normalize(),
+        );
+        assert_eq!(w.color_at(&r), emissive);
     }
 }